@@ -74,6 +74,11 @@ impl Context {
         })
     }
 
+    /// Returns the Wasm [`Module`] of the [`Context`].
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
     /// Returns a shared reference to the [`Store`] of the [`Context`].
     pub fn store(&self) -> &Store<WasiCtx> {
         &self.store
@@ -19,10 +19,10 @@ mod tests;
 fn main() -> Result<()> {
     let args = Args::parse();
     let wasm_file = args.wasm_file();
-    let tracer = Tracer::new();
-    let tracer = Rc::new(RefCell::new(tracer));
     let wasi_ctx = args.wasi_context()?;
     let mut ctx = Context::new(wasm_file, wasi_ctx, args.fuel(), args.compilation_mode())?;
+    let tracer = Tracer::new().with_func_names(ctx.module());
+    let tracer = Rc::new(RefCell::new(tracer));
     let (func_name, func) = get_invoked_func(&args, &ctx)?;
     let ty = func.ty(ctx.store());
     let func_args = utils::decode_func_args(&ty, args.func_args())?;
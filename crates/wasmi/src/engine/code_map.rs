@@ -302,6 +302,8 @@ pub struct CompiledFuncEntity {
     /// This includes registers to store the function local constant values,
     /// function parameters, function locals and dynamically used registers.
     len_registers: u16,
+    /// The number of registers storing the parameters and locals of the [`CompiledFunc`].
+    len_locals: u16,
     /// The constant values local to the [`CompiledFunc`].
     consts: Box<[UntypedVal]>,
 }
@@ -313,7 +315,7 @@ impl CompiledFuncEntity {
     ///
     /// - If `instrs` is empty.
     /// - If `instrs` contains more than `u32::MAX` instructions.
-    pub fn new<I, C>(len_registers: u16, instrs: I, consts: C) -> Self
+    pub fn new<I, C>(len_registers: u16, len_locals: u16, instrs: I, consts: C) -> Self
     where
         I: IntoIterator<Item = Instruction>,
         C: IntoIterator<Item = UntypedVal>,
@@ -327,6 +329,7 @@ impl CompiledFuncEntity {
         Self {
             instrs,
             len_registers,
+            len_locals,
             consts,
         }
     }
@@ -336,6 +339,7 @@ impl CompiledFuncEntity {
         Self {
             instrs: [].into(),
             len_registers: 0,
+            len_locals: 0,
             consts: [].into(),
         }
     }
@@ -350,6 +354,15 @@ impl CompiledFuncEntity {
         self.len_registers
     }
 
+    /// Returns the number of registers storing the parameters and locals of the [`CompiledFunc`].
+    ///
+    /// # Note
+    ///
+    /// These are the first registers of the function frame.
+    pub fn len_locals(&self) -> u16 {
+        self.len_locals
+    }
+
     /// Returns the number of mutable registers used by the [`CompiledFunc`].
     ///
     /// # Note
@@ -773,6 +786,14 @@ impl CodeMap {
             None => func.compile_and_get(fuel, &self.features),
         }
     }
+
+    /// Returns the [`CompiledFuncEntity`] of the [`CompiledFunc`] if it has been compiled.
+    ///
+    /// Returns `None` if the [`CompiledFunc`] has not yet been compiled.
+    /// Unlike [`CodeMap::get`] this never compiles the [`CompiledFunc`].
+    pub fn get_compiled(&self, compiled_func: CompiledFunc) -> Option<&CompiledFuncEntity> {
+        self.funcs.get(compiled_func)?.get_compiled()
+    }
 }
 
 /// The instruction pointer to the instruction of a function on the call stack.
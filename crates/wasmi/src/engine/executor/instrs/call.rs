@@ -35,7 +35,7 @@ pub enum CallOutcome {
 }
 
 /// The kind of a function call.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum CallKind {
     /// A nested function call.
     Nested,
@@ -0,0 +1,2386 @@
+use super::{CallKind, Executor, WasmOutcome};
+use crate::{
+    core::{UntypedVal, ValType},
+    engine::{
+        bytecode::{
+            BinInstr,
+            BranchBinOpInstr,
+            BranchBinOpInstrImm16,
+            BranchComparator,
+            BranchOffset,
+            ComparatorOffsetParam,
+            Const16,
+            GlobalIdx,
+            Instruction,
+            Register,
+            RegisterSpan,
+            TableIdx,
+        },
+        code_map::InstructionPtr,
+        executor::stack::FrameRegisters,
+        CompiledFunc,
+        VisitInputRegisters,
+    },
+    etable::{
+        BinOp,
+        FloatUnOp,
+        IVal,
+        LocalOpKind,
+        MemoryWord,
+        RelOp,
+        StepInfo,
+        TestOp,
+        UnOp,
+    },
+    module::DEFAULT_MEMORY_INDEX,
+    value::WithType,
+    Error,
+    Global,
+    Instance,
+    StoreInner,
+    Tracer,
+    Val,
+};
+use core::{
+    num::{NonZeroI32, NonZeroI64, NonZeroU64},
+    slice,
+};
+use smallvec::SmallVec;
+use std::{string::String, vec::Vec};
+
+/// The [`Executor`] state captured right before an [`Instruction`] executes.
+///
+/// Executed instructions may advance the instruction pointer, switch the
+/// function frame or overwrite their own operands, so the traced step is
+/// built from this snapshot instead.
+#[derive(Debug, Clone)]
+pub struct PreStep {
+    /// The number of pages of the default linear memory.
+    pages: u32,
+    /// The registers of the function frame executing the instruction.
+    ///
+    /// Used to trace the values written by the instruction.
+    sp: FrameRegisters,
+    /// The values of the registers read by the instruction before execution.
+    ///
+    /// Used to trace the values read by the instruction.
+    registers: PreRegisters,
+    /// The value stack offset of the first function local constant of the function frame.
+    frame: usize,
+    /// The value stack offset of the first register of the function frame.
+    ///
+    /// Stack addresses of traced registers are relative to this offset so
+    /// that the registers of different function frames never alias.
+    base: usize,
+    /// The height of the value stack.
+    ///
+    /// The registers of the executing function frame end at this height.
+    height: usize,
+    /// The value stack offset of the first register of the caller's function frame.
+    ///
+    /// This is `0` for the root function frame which returns to the host.
+    caller_base: usize,
+    /// The caller registers receiving the results of the function frame.
+    results: RegisterSpan,
+    /// The pointer to the executed instruction.
+    ip: InstructionPtr,
+    /// The instance executing the instruction.
+    instance: Instance,
+    /// The index of the function of the executed instruction within the executing instance.
+    func_index: u32,
+    /// The offset of the executed instruction within the instructions of its function.
+    pc: u32,
+    /// The number of locals of the function of the executed instruction.
+    len_locals: u16,
+}
+
+/// The values of registers captured by a [`PreStep`].
+#[derive(Debug, Clone)]
+enum PreRegisters {
+    /// The values of all registers of the function frame.
+    ///
+    /// Starts with the function local constants which precede the frame's `base` offset.
+    /// Captured for returns since they trace all registers of the returning frame.
+    Frame(Vec<UntypedVal>),
+    /// The value stack addresses and values of the registers read by the instruction.
+    Read(SmallVec<[(usize, UntypedVal); 4]>),
+}
+
+impl PreStep {
+    /// Returns the value of the register at the value stack address `addr` before execution.
+    ///
+    /// # Panics
+    ///
+    /// If the register was not captured, i.e. is not read by the instruction.
+    fn register(&self, addr: usize) -> UntypedVal {
+        match &self.registers {
+            PreRegisters::Frame(values) => values[addr - self.frame],
+            PreRegisters::Read(values) => values
+                .iter()
+                .find_map(|&(read, value)| (read == addr).then_some(value))
+                .expect("register must be read by the traced instruction"),
+        }
+    }
+}
+
+impl<'ctx, 'engine> Executor<'ctx, 'engine> {
+    /// Captures the [`PreStep`] state before executing the current instruction.
+    ///
+    /// Returns `None` if the current instruction is not located within the
+    /// compiled functions of the executing instance.
+    pub(super) fn execute_instruction_pre(&mut self, tracer: &mut Tracer) -> Option<PreStep> {
+        let instance = *self.cache.instance();
+        let (func_index, func, pc) =
+            tracer
+                .func_indices()
+                .locate(self.ctx, self.code_map, &instance, self.ip.ptr)?;
+        let len_locals = self.code_map.get_compiled(func)?.len_locals();
+        let pages = self.trace_pages();
+        let (callee, caller) = self
+            .call_stack
+            .peek_2()
+            .expect("must have call frame on the call stack");
+        let frame = usize::from(callee.frame_offset());
+        let base = usize::from(callee.base_offset());
+        let height = self.value_stack.len();
+        Some(PreStep {
+            pages,
+            sp: self.sp,
+            registers: self.trace_pre_registers(frame, base, height),
+            frame,
+            base,
+            height,
+            caller_base: caller.map_or(0, |caller| usize::from(caller.base_offset())),
+            results: callee.results(),
+            ip: self.ip,
+            instance,
+            func_index,
+            pc,
+            len_locals,
+        })
+    }
+
+    /// Captures the registers of the function frame `frame..height` read by the current instruction.
+    ///
+    /// The read registers are the input registers of the instruction and of its
+    /// parameter instruction words. Registers overwritten by the instruction are
+    /// captured as well if they are traced, e.g. the previous value of a `local.set`.
+    fn trace_pre_registers(&self, frame: usize, base: usize, height: usize) -> PreRegisters {
+        let frame_values = &self.value_stack.as_slice()[frame..height];
+        let mut instr = *self.ip.get();
+        if Self::trace_reads_frame(&instr) {
+            return PreRegisters::Frame(frame_values.to_vec());
+        }
+        let mut registers = SmallVec::new();
+        let mut capture = |register: &mut Register| {
+            let Some(addr) = base.checked_add_signed(isize::from(register.to_i16())) else {
+                return;
+            };
+            let index = addr.checked_sub(frame);
+            if let Some(&value) = index.and_then(|index| frame_values.get(index)) {
+                registers.push((addr, value));
+            }
+        };
+        // The results of copies are captured for the previous values of `local.set`s.
+        match instr {
+            Instruction::Copy { mut result, .. }
+            | Instruction::CopyImm32 { mut result, .. }
+            | Instruction::CopyI64Imm32 { mut result, .. }
+            | Instruction::CopyF64Imm32 { mut result, .. } => capture(&mut result),
+            Instruction::Copy2 { results, .. } => {
+                for mut result in results.iter(2) {
+                    capture(&mut result);
+                }
+            }
+            Instruction::CopySpan {
+                results,
+                values,
+                len,
+            }
+            | Instruction::CopySpanNonOverlapping {
+                results,
+                values,
+                len,
+            } => {
+                // The visitor only visits the first register of a span.
+                for mut register in results.iter_u16(len).chain(values.iter_u16(len)) {
+                    capture(&mut register);
+                }
+            }
+            // The visitor skips the parameters and the index of these instructions.
+            Instruction::BranchCmpFallback { mut params, .. } => capture(&mut params),
+            Instruction::TableGet { mut index, .. } => capture(&mut index),
+            // The condition is encoded by the second instruction word of the pair.
+            Instruction::SelectImm32 { .. }
+            | Instruction::SelectI64Imm32 { .. }
+            | Instruction::SelectF64Imm32 { .. } => {
+                let mut ip = self.ip;
+                ip.add(1);
+                match *ip.get() {
+                    Instruction::SelectImm32 {
+                        result_or_condition: mut condition,
+                        ..
+                    }
+                    | Instruction::SelectI64Imm32 {
+                        result_or_condition: mut condition,
+                        ..
+                    }
+                    | Instruction::SelectF64Imm32 {
+                        result_or_condition: mut condition,
+                        ..
+                    } => capture(&mut condition),
+                    unexpected => {
+                        unreachable!("expected a select instruction word but found: {unexpected:?}")
+                    }
+                }
+            }
+            _ => {}
+        }
+        instr.visit_input_registers(&mut capture);
+        let mut ip = self.ip;
+        loop {
+            ip.add(1);
+            let mut param = *ip.get();
+            if !Self::trace_is_param(&param) {
+                break;
+            }
+            param.visit_input_registers(&mut capture);
+        }
+        PreRegisters::Read(registers)
+    }
+
+    /// Returns `true` if tracing the `instr` reads all registers of the function frame.
+    fn trace_reads_frame(instr: &Instruction) -> bool {
+        matches!(
+            instr,
+            Instruction::Return
+                | Instruction::ReturnReg { .. }
+                | Instruction::ReturnReg2 { .. }
+                | Instruction::ReturnReg3 { .. }
+                | Instruction::ReturnImm32 { .. }
+                | Instruction::ReturnI64Imm32 { .. }
+                | Instruction::ReturnF64Imm32 { .. }
+                | Instruction::ReturnSpan { .. }
+                | Instruction::ReturnMany { .. }
+                | Instruction::ReturnNez { .. }
+                | Instruction::ReturnNezReg { .. }
+                | Instruction::ReturnNezReg2 { .. }
+                | Instruction::ReturnNezImm32 { .. }
+                | Instruction::ReturnNezI64Imm32 { .. }
+                | Instruction::ReturnNezF64Imm32 { .. }
+                | Instruction::ReturnNezSpan { .. }
+                | Instruction::ReturnNezMany { .. }
+        )
+    }
+
+    /// Returns `true` if `instr` is a parameter word of the preceding instruction.
+    fn trace_is_param(instr: &Instruction) -> bool {
+        matches!(
+            instr,
+            Instruction::TableIdx(_)
+                | Instruction::DataSegmentIdx(_)
+                | Instruction::ElementSegmentIdx(_)
+                | Instruction::Const32(_)
+                | Instruction::I64Const32(_)
+                | Instruction::F64Const32(_)
+                | Instruction::Register(_)
+                | Instruction::Register2(_)
+                | Instruction::Register3(_)
+                | Instruction::RegisterList(_)
+                | Instruction::CallIndirectParams(_)
+                | Instruction::CallIndirectParamsImm16(_)
+        )
+    }
+
+    /// Returns the number of pages of the default linear memory.
+    ///
+    /// Returns `0` if the executing instance has no linear memory.
+    pub(super) fn trace_pages(&mut self) -> u32 {
+        let instance = self.cache.instance();
+        let has_default_memory = self
+            .ctx
+            .resolve_instance(instance)
+            .get_memory(DEFAULT_MEMORY_INDEX)
+            .is_some();
+        if !has_default_memory {
+            return 0;
+        }
+        self.ctx
+            .resolve_memory(self.cache.default_memory(self.ctx))
+            .current_pages()
+            .into()
+    }
+
+    /// Records the step of the executed `instruction` into the `tracer`.
+    ///
+    /// Instructions that trapped are recorded as [`StepInfo::Trap`].
+    /// Instructions of functions other than the [`Tracer::only_func`] are not recorded.
+    pub(super) fn execute_instruction_post(
+        &mut self,
+        pre: PreStep,
+        instruction: &Instruction,
+        outcome: &Result<Option<WasmOutcome>, Error>,
+        tracer: &mut Tracer,
+    ) {
+        let (func_index, pc) = (pre.func_index, pre.pc);
+        let traced = match tracer.traced_func() {
+            Some(traced_func) => func_index == traced_func,
+            None => true,
+        };
+        if traced {
+            let step = match outcome {
+                Ok(outcome) => self.trace_step(&pre, instruction, outcome, tracer),
+                Err(error) => match error.as_trap_code() {
+                    Some(code) => StepInfo::Trap {
+                        code,
+                        reads: self.trace_trap_reads(&pre, instruction),
+                        data: self.trace_trap_data(&pre, instruction, tracer),
+                        func_index,
+                        pc,
+                    },
+                    // Host errors are not caused by the executed instruction.
+                    None => return,
+                },
+            };
+            tracer.push_step(func_index, pc, pre.pages, step);
+        }
+        self.trace_grown_memory(pre.pages, outcome, tracer);
+    }
+
+    /// Records the pages the executed instruction added to the default linear memory.
+    ///
+    /// The `pages` are the number of pages before the instruction was executed.
+    pub(super) fn trace_grown_memory(
+        &mut self,
+        pages: u32,
+        outcome: &Result<Option<WasmOutcome>, Error>,
+        tracer: &mut Tracer,
+    ) {
+        if outcome.is_ok() {
+            let new_pages = self.trace_pages();
+            if new_pages > pages {
+                tracer.push_grown_memory(pages, new_pages);
+            }
+        }
+    }
+
+    /// Returns the [`StepInfo`] of the successfully executed `instruction`.
+    fn trace_step(
+        &mut self,
+        pre: &PreStep,
+        instruction: &Instruction,
+        outcome: &Option<WasmOutcome>,
+        tracer: &mut Tracer,
+    ) -> StepInfo {
+        if let Some(step) = Self::trace_return(pre, instruction) {
+            return step;
+        }
+        if let Some(step) = self.trace_br_if(pre, instruction) {
+            return step;
+        }
+        match *instruction {
+            Instruction::CallInternal0 { func, .. }
+            | Instruction::CallInternal { func, .. }
+            | Instruction::ReturnCallInternal0 { func }
+            | Instruction::ReturnCallInternal { func } => {
+                let func_index = self.trace_internal_func_index(func, tracer);
+                self.trace_call(pre, instruction, func_index, false, tracer)
+            }
+            Instruction::CallImported0 { func, .. }
+            | Instruction::CallImported { func, .. }
+            | Instruction::ReturnCallImported0 { func }
+            | Instruction::ReturnCallImported { func } => {
+                // Calls to host functions are dispatched outside of the executor.
+                let host = matches!(outcome, Some(WasmOutcome::Call { .. }));
+                self.trace_call(pre, instruction, func.to_u32(), host, tracer)
+            }
+            Instruction::CallIndirect0 { .. }
+            | Instruction::CallIndirect { .. }
+            | Instruction::ReturnCallIndirect0 { .. }
+            | Instruction::ReturnCallIndirect { .. } => {
+                let host = matches!(outcome, Some(WasmOutcome::Call { .. }));
+                self.trace_call_indirect(pre, instruction, host, tracer)
+            }
+            Instruction::I32Add(instr) => {
+                let (left, right, result) = Self::trace_binary(pre, instr, ValType::I32);
+                StepInfo::I32BinOp {
+                    class: BinOp::Add,
+                    left,
+                    right,
+                    result,
+                }
+            }
+            Instruction::I32Clz(instr)
+            | Instruction::I32Ctz(instr)
+            | Instruction::I32Popcnt(instr) => {
+                let class = match instruction {
+                    Instruction::I32Clz(_) => UnOp::Clz,
+                    Instruction::I32Ctz(_) => UnOp::Ctz,
+                    _ => UnOp::Popcnt,
+                };
+                StepInfo::I32UnOp {
+                    class,
+                    operand: Self::trace_read(pre, instr.input, ValType::I32),
+                    result: Self::trace_write(pre, instr.result, ValType::I32),
+                }
+            }
+            Instruction::F32Abs(instr)
+            | Instruction::F32Neg(instr)
+            | Instruction::F32Sqrt(instr)
+            | Instruction::F32Ceil(instr)
+            | Instruction::F32Floor(instr)
+            | Instruction::F32Trunc(instr)
+            | Instruction::F32Nearest(instr) => StepInfo::FloatUnOp {
+                class: Self::trace_float_unop_class(instruction),
+                operand: Self::trace_read(pre, instr.input, ValType::F32),
+                result: Self::trace_write(pre, instr.result, ValType::F32),
+            },
+            Instruction::F64Abs(instr)
+            | Instruction::F64Neg(instr)
+            | Instruction::F64Sqrt(instr)
+            | Instruction::F64Ceil(instr)
+            | Instruction::F64Floor(instr)
+            | Instruction::F64Trunc(instr)
+            | Instruction::F64Nearest(instr) => StepInfo::FloatUnOp {
+                class: Self::trace_float_unop_class(instruction),
+                operand: Self::trace_read(pre, instr.input, ValType::F64),
+                result: Self::trace_write(pre, instr.result, ValType::F64),
+            },
+            Instruction::F32ConvertI32S(instr)
+            | Instruction::F32ConvertI32U(instr)
+            | Instruction::F32ConvertI64S(instr)
+            | Instruction::F32ConvertI64U(instr)
+            | Instruction::F64ConvertI32S(instr)
+            | Instruction::F64ConvertI32U(instr)
+            | Instruction::F64ConvertI64S(instr)
+            | Instruction::F64ConvertI64U(instr) => {
+                let (input_ty, result_ty, signed) = match instruction {
+                    Instruction::F32ConvertI32S(_) => (ValType::I32, ValType::F32, true),
+                    Instruction::F32ConvertI32U(_) => (ValType::I32, ValType::F32, false),
+                    Instruction::F32ConvertI64S(_) => (ValType::I64, ValType::F32, true),
+                    Instruction::F32ConvertI64U(_) => (ValType::I64, ValType::F32, false),
+                    Instruction::F64ConvertI32S(_) => (ValType::I32, ValType::F64, true),
+                    Instruction::F64ConvertI32U(_) => (ValType::I32, ValType::F64, false),
+                    Instruction::F64ConvertI64S(_) => (ValType::I64, ValType::F64, true),
+                    _ => (ValType::I64, ValType::F64, false),
+                };
+                StepInfo::ConvertInt {
+                    signed,
+                    input: Self::trace_read(pre, instr.input, input_ty),
+                    result: Self::trace_write(pre, instr.result, result_ty),
+                }
+            }
+            Instruction::I32Eq(instr)
+            | Instruction::I32Ne(instr)
+            | Instruction::I32LtS(instr)
+            | Instruction::I32LtU(instr)
+            | Instruction::I32GtS(instr)
+            | Instruction::I32GtU(instr)
+            | Instruction::I32LeS(instr)
+            | Instruction::I32LeU(instr)
+            | Instruction::I32GeS(instr)
+            | Instruction::I32GeU(instr) => {
+                let left = Self::trace_read(pre, instr.lhs, ValType::I32);
+                let right = Self::trace_read(pre, instr.rhs, ValType::I32);
+                Self::trace_compare(pre, instruction, left, right, instr.result)
+            }
+            Instruction::I64Eq(instr)
+            | Instruction::I64Ne(instr)
+            | Instruction::I64LtS(instr)
+            | Instruction::I64LtU(instr)
+            | Instruction::I64GtS(instr)
+            | Instruction::I64GtU(instr)
+            | Instruction::I64LeS(instr)
+            | Instruction::I64LeU(instr)
+            | Instruction::I64GeS(instr)
+            | Instruction::I64GeU(instr) => {
+                let left = Self::trace_read(pre, instr.lhs, ValType::I64);
+                let right = Self::trace_read(pre, instr.rhs, ValType::I64);
+                Self::trace_compare(pre, instruction, left, right, instr.result)
+            }
+            Instruction::I32EqImm16(instr) if i32::from(instr.imm_in) == 0 => StepInfo::TestOp {
+                class: TestOp::Eqz,
+                operand: Self::trace_read(pre, instr.reg_in, ValType::I32),
+                result: Self::trace_write(pre, instr.result, ValType::I32),
+            },
+            Instruction::I64EqImm16(instr) if i64::from(instr.imm_in) == 0 => StepInfo::TestOp {
+                class: TestOp::Eqz,
+                operand: Self::trace_read(pre, instr.reg_in, ValType::I64),
+                result: Self::trace_write(pre, instr.result, ValType::I32),
+            },
+            Instruction::I32EqImm16(instr)
+            | Instruction::I32NeImm16(instr)
+            | Instruction::I32LtSImm16(instr)
+            | Instruction::I32GtSImm16(instr)
+            | Instruction::I32LeSImm16(instr)
+            | Instruction::I32GeSImm16(instr) => {
+                let left = Self::trace_read(pre, instr.reg_in, ValType::I32);
+                let right = IVal::imm(Val::I32(i32::from(instr.imm_in)));
+                Self::trace_compare(pre, instruction, left, right, instr.result)
+            }
+            Instruction::I32LtUImm16(instr)
+            | Instruction::I32GtUImm16(instr)
+            | Instruction::I32LeUImm16(instr)
+            | Instruction::I32GeUImm16(instr) => {
+                let left = Self::trace_read(pre, instr.reg_in, ValType::I32);
+                let right = IVal::imm(Val::I32(u32::from(instr.imm_in) as i32));
+                Self::trace_compare(pre, instruction, left, right, instr.result)
+            }
+            Instruction::I64EqImm16(instr)
+            | Instruction::I64NeImm16(instr)
+            | Instruction::I64LtSImm16(instr)
+            | Instruction::I64GtSImm16(instr)
+            | Instruction::I64LeSImm16(instr)
+            | Instruction::I64GeSImm16(instr) => {
+                let left = Self::trace_read(pre, instr.reg_in, ValType::I64);
+                let right = IVal::imm(Val::I64(i64::from(instr.imm_in)));
+                Self::trace_compare(pre, instruction, left, right, instr.result)
+            }
+            Instruction::I64LtUImm16(instr)
+            | Instruction::I64GtUImm16(instr)
+            | Instruction::I64LeUImm16(instr)
+            | Instruction::I64GeUImm16(instr) => {
+                let left = Self::trace_read(pre, instr.reg_in, ValType::I64);
+                let right = IVal::imm(Val::I64(u64::from(instr.imm_in) as i64));
+                Self::trace_compare(pre, instruction, left, right, instr.result)
+            }
+            Instruction::I32WrapI64(instr) => {
+                let input = Self::trace_read(pre, instr.input, ValType::I64);
+                let high = (input.val.i64().expect("wrapped value must be an i64") >> 32) as u32;
+                StepInfo::I32WrapI64 {
+                    input,
+                    result: Self::trace_write(pre, instr.result, ValType::I32),
+                    discarded_high: (high != 0).then_some(high),
+                }
+            }
+            Instruction::I64ExtendI32S(instr) | Instruction::I64ExtendI32U(instr) => {
+                StepInfo::I64ExtendI32 {
+                    signed: matches!(instruction, Instruction::I64ExtendI32S(_)),
+                    input: Self::trace_read(pre, instr.input, ValType::I32),
+                    result: Self::trace_write(pre, instr.result, ValType::I64),
+                }
+            }
+            Instruction::I32Extend8S(instr)
+            | Instruction::I32Extend16S(instr)
+            | Instruction::I64Extend8S(instr)
+            | Instruction::I64Extend16S(instr)
+            | Instruction::I64Extend32S(instr) => {
+                let (from_bits, ty) = match instruction {
+                    Instruction::I32Extend8S(_) => (8, ValType::I32),
+                    Instruction::I32Extend16S(_) => (16, ValType::I32),
+                    Instruction::I64Extend8S(_) => (8, ValType::I64),
+                    Instruction::I64Extend16S(_) => (16, ValType::I64),
+                    _ => (32, ValType::I64),
+                };
+                StepInfo::SignExtend {
+                    from_bits,
+                    to_bits: if ty == ValType::I32 { 32 } else { 64 },
+                    operand: Self::trace_read(pre, instr.input, ty),
+                    result: Self::trace_write(pre, instr.result, ty),
+                }
+            }
+            Instruction::I32Shl(instr)
+            | Instruction::I32ShrS(instr)
+            | Instruction::I32ShrU(instr)
+            | Instruction::I32Rotl(instr)
+            | Instruction::I32Rotr(instr) => {
+                let (left, right, result) = Self::trace_binary(pre, instr, ValType::I32);
+                StepInfo::I32BinOp {
+                    class: Self::trace_shift_class(instruction),
+                    left,
+                    right,
+                    result,
+                }
+            }
+            Instruction::I32ShlImm(instr)
+            | Instruction::I32ShrSImm(instr)
+            | Instruction::I32ShrUImm(instr)
+            | Instruction::I32RotlImm(instr)
+            | Instruction::I32RotrImm(instr) => StepInfo::I32BinOp {
+                class: Self::trace_shift_class(instruction),
+                left: Self::trace_read(pre, instr.reg_in, ValType::I32),
+                right: IVal::imm(Val::I32(i32::from(instr.imm_in))),
+                result: Self::trace_write(pre, instr.result, ValType::I32),
+            },
+            Instruction::I32ShlImm16Rev(instr)
+            | Instruction::I32ShrSImm16Rev(instr)
+            | Instruction::I32ShrUImm16Rev(instr)
+            | Instruction::I32RotlImm16Rev(instr)
+            | Instruction::I32RotrImm16Rev(instr) => StepInfo::I32BinOp {
+                class: Self::trace_shift_class(instruction),
+                left: IVal::imm(Val::I32(i32::from(instr.imm_in))),
+                right: Self::trace_read(pre, instr.reg_in, ValType::I32),
+                result: Self::trace_write(pre, instr.result, ValType::I32),
+            },
+            Instruction::I64Add(instr)
+            | Instruction::I64Sub(instr)
+            | Instruction::I64Mul(instr)
+            | Instruction::I64DivS(instr)
+            | Instruction::I64DivU(instr)
+            | Instruction::I64RemS(instr)
+            | Instruction::I64RemU(instr)
+            | Instruction::I64And(instr)
+            | Instruction::I64Or(instr)
+            | Instruction::I64Xor(instr) => {
+                let (left, right, result) = Self::trace_binary(pre, instr, ValType::I64);
+                StepInfo::I64BinOp {
+                    class: Self::trace_arith_class(instruction),
+                    left,
+                    right,
+                    result,
+                }
+            }
+            Instruction::I64AddImm16(instr)
+            | Instruction::I64MulImm16(instr)
+            | Instruction::I64AndImm16(instr)
+            | Instruction::I64OrImm16(instr)
+            | Instruction::I64XorImm16(instr) => {
+                let imm = i64::from(instr.imm_in);
+                Self::trace_i64_binary_imm(pre, instruction, instr.reg_in, imm, instr.result, false)
+            }
+            Instruction::I64DivSImm16(instr) | Instruction::I64RemSImm16(instr) => {
+                let imm = NonZeroI64::from(instr.imm_in).get();
+                Self::trace_i64_binary_imm(pre, instruction, instr.reg_in, imm, instr.result, false)
+            }
+            Instruction::I64DivUImm16(instr) | Instruction::I64RemUImm16(instr) => {
+                let imm = NonZeroU64::from(instr.imm_in).get() as i64;
+                Self::trace_i64_binary_imm(pre, instruction, instr.reg_in, imm, instr.result, false)
+            }
+            Instruction::I64SubImm16Rev(instr)
+            | Instruction::I64DivSImm16Rev(instr)
+            | Instruction::I64RemSImm16Rev(instr) => {
+                let imm = i64::from(instr.imm_in);
+                Self::trace_i64_binary_imm(pre, instruction, instr.reg_in, imm, instr.result, true)
+            }
+            Instruction::I64DivUImm16Rev(instr) | Instruction::I64RemUImm16Rev(instr) => {
+                let imm = u64::from(instr.imm_in) as i64;
+                Self::trace_i64_binary_imm(pre, instruction, instr.reg_in, imm, instr.result, true)
+            }
+            Instruction::I64Shl(instr)
+            | Instruction::I64ShrS(instr)
+            | Instruction::I64ShrU(instr)
+            | Instruction::I64Rotl(instr)
+            | Instruction::I64Rotr(instr) => {
+                let (left, right, result) = Self::trace_binary(pre, instr, ValType::I64);
+                StepInfo::I64BinOp {
+                    class: Self::trace_shift_class(instruction),
+                    left,
+                    right,
+                    result,
+                }
+            }
+            Instruction::I64ShlImm(instr)
+            | Instruction::I64ShrSImm(instr)
+            | Instruction::I64ShrUImm(instr)
+            | Instruction::I64RotlImm(instr)
+            | Instruction::I64RotrImm(instr) => StepInfo::I64BinOp {
+                class: Self::trace_shift_class(instruction),
+                left: Self::trace_read(pre, instr.reg_in, ValType::I64),
+                right: IVal::imm(Val::I64(i64::from(instr.imm_in))),
+                result: Self::trace_write(pre, instr.result, ValType::I64),
+            },
+            Instruction::I64ShlImm16Rev(instr)
+            | Instruction::I64ShrSImm16Rev(instr)
+            | Instruction::I64ShrUImm16Rev(instr)
+            | Instruction::I64RotlImm16Rev(instr)
+            | Instruction::I64RotrImm16Rev(instr) => StepInfo::I64BinOp {
+                class: Self::trace_shift_class(instruction),
+                left: IVal::imm(Val::I64(i64::from(instr.imm_in))),
+                right: Self::trace_read(pre, instr.reg_in, ValType::I64),
+                result: Self::trace_write(pre, instr.result, ValType::I64),
+            },
+            Instruction::F32Add(instr)
+            | Instruction::F32Sub(instr)
+            | Instruction::F32Mul(instr)
+            | Instruction::F32Div(instr)
+            | Instruction::F32Min(instr)
+            | Instruction::F32Max(instr)
+            | Instruction::F32Copysign(instr) => {
+                // Division by zero yields an infinity or NaN and does not trap.
+                // NaN results are traced with the exact bits the executor produced.
+                let (left, right, result) = Self::trace_binary(pre, instr, ValType::F32);
+                StepInfo::F32BinOp {
+                    class: Self::trace_float_class(instruction),
+                    left,
+                    right,
+                    result,
+                }
+            }
+            Instruction::F64Add(instr)
+            | Instruction::F64Sub(instr)
+            | Instruction::F64Mul(instr)
+            | Instruction::F64Div(instr)
+            | Instruction::F64Min(instr)
+            | Instruction::F64Max(instr)
+            | Instruction::F64Copysign(instr) => {
+                let (left, right, result) = Self::trace_binary(pre, instr, ValType::F64);
+                StepInfo::F64BinOp {
+                    class: Self::trace_float_class(instruction),
+                    left,
+                    right,
+                    result,
+                }
+            }
+            // The executor copies the sign of `1.0` or `-1.0` for the immediate sign.
+            Instruction::F32CopysignImm(instr) => StepInfo::F32BinOp {
+                class: BinOp::CopySign,
+                left: Self::trace_read(pre, instr.reg_in, ValType::F32),
+                right: IVal::imm(Val::F32(instr.imm_in.to_f32().into())),
+                result: Self::trace_write(pre, instr.result, ValType::F32),
+            },
+            Instruction::F64CopysignImm(instr) => StepInfo::F64BinOp {
+                class: BinOp::CopySign,
+                left: Self::trace_read(pre, instr.reg_in, ValType::F64),
+                right: IVal::imm(Val::F64(instr.imm_in.to_f64().into())),
+                result: Self::trace_write(pre, instr.result, ValType::F64),
+            },
+            Instruction::GlobalGet { result, global } => {
+                let (global_index, address, ty, is_mutable) = self.trace_global(global);
+                StepInfo::GlobalGet {
+                    global_index,
+                    address,
+                    is_mutable,
+                    value: Self::trace_write(pre, result, ty),
+                }
+            }
+            Instruction::GlobalSet { global, input } => {
+                let (global_index, address, ty, _) = self.trace_global(global);
+                StepInfo::GlobalSet {
+                    global_index,
+                    address,
+                    value: Self::trace_read(pre, input, ty),
+                }
+            }
+            Instruction::GlobalSetI32Imm16 { global, input } => {
+                let (global_index, address, _, _) = self.trace_global(global);
+                StepInfo::GlobalSet {
+                    global_index,
+                    address,
+                    value: IVal::imm(Val::I32(i32::from(input))),
+                }
+            }
+            Instruction::GlobalSetI64Imm16 { global, input } => {
+                let (global_index, address, _, _) = self.trace_global(global);
+                StepInfo::GlobalSet {
+                    global_index,
+                    address,
+                    value: IVal::imm(Val::I64(i64::from(input))),
+                }
+            }
+            Instruction::Copy { .. }
+            | Instruction::CopyImm32 { .. }
+            | Instruction::CopyI64Imm32 { .. }
+            | Instruction::CopyF64Imm32 { .. } => {
+                let capture_prev = tracer.is_prev_value_mode();
+                let step =
+                    self.trace_local_op(pre, Self::trace_copy(pre, instruction), capture_prev);
+                Self::trace_const(pre, instruction, step)
+            }
+            Instruction::Copy2 { .. }
+            | Instruction::CopySpan { .. }
+            | Instruction::CopySpanNonOverlapping { .. }
+            | Instruction::CopyMany { .. }
+            | Instruction::CopyManyNonOverlapping { .. } => Self::trace_copy(pre, instruction),
+            Instruction::Branch { offset } => StepInfo::Br {
+                target_pc: self.trace_pc(pre, offset.to_i32()),
+            },
+            Instruction::BranchTable { index, len_targets } => {
+                let index = Self::trace_read(pre, index, ValType::I32);
+                let raw_index = index.val.i32().expect("br_table index must be an i32") as u32;
+                // Out of bounds indices are clamped to the last target which is the default target.
+                let effective_index = raw_index.min(u32::from(len_targets) - 1);
+                StepInfo::BrTable {
+                    index,
+                    raw_index,
+                    effective_index,
+                    len_targets: u32::from(len_targets),
+                    // The selected target instruction is executed next.
+                    target_pc: self.trace_pc(pre, self.trace_ip_delta(pre)),
+                }
+            }
+            Instruction::Select {
+                result,
+                condition,
+                lhs,
+            } => Self::trace_select(
+                pre,
+                condition,
+                Self::trace_untyped_read(pre, lhs),
+                Self::trace_select_param(pre),
+                Self::trace_write(pre, result, ValType::I64),
+            ),
+            Instruction::SelectRev {
+                result,
+                condition,
+                rhs,
+            } => Self::trace_select(
+                pre,
+                condition,
+                Self::trace_select_param(pre),
+                Self::trace_untyped_read(pre, rhs),
+                Self::trace_write(pre, result, ValType::I64),
+            ),
+            Instruction::SelectImm32 {
+                result_or_condition: result,
+                lhs_or_rhs: lhs,
+            } => {
+                let Instruction::SelectImm32 {
+                    result_or_condition: condition,
+                    lhs_or_rhs: rhs,
+                } = Self::trace_next_instr(pre)
+                else {
+                    unreachable!("expected an Instruction::SelectImm32 instruction word")
+                };
+                // The constant may encode an `i32` or an `f32` which are traced by their bits.
+                Self::trace_select(
+                    pre,
+                    condition,
+                    IVal::imm(Val::I32(u32::from(lhs) as i32)),
+                    IVal::imm(Val::I32(u32::from(rhs) as i32)),
+                    Self::trace_write(pre, result, ValType::I32),
+                )
+            }
+            Instruction::SelectI64Imm32 {
+                result_or_condition: result,
+                lhs_or_rhs: lhs,
+            } => {
+                let Instruction::SelectI64Imm32 {
+                    result_or_condition: condition,
+                    lhs_or_rhs: rhs,
+                } = Self::trace_next_instr(pre)
+                else {
+                    unreachable!("expected an Instruction::SelectI64Imm32 instruction word")
+                };
+                Self::trace_select(
+                    pre,
+                    condition,
+                    IVal::imm(Val::I64(i64::from(lhs))),
+                    IVal::imm(Val::I64(i64::from(rhs))),
+                    Self::trace_write(pre, result, ValType::I64),
+                )
+            }
+            Instruction::SelectF64Imm32 {
+                result_or_condition: result,
+                lhs_or_rhs: lhs,
+            } => {
+                let Instruction::SelectF64Imm32 {
+                    result_or_condition: condition,
+                    lhs_or_rhs: rhs,
+                } = Self::trace_next_instr(pre)
+                else {
+                    unreachable!("expected an Instruction::SelectF64Imm32 instruction word")
+                };
+                Self::trace_select(
+                    pre,
+                    condition,
+                    IVal::imm(Val::F64(f64::from(lhs).into())),
+                    IVal::imm(Val::F64(f64::from(rhs).into())),
+                    Self::trace_write(pre, result, ValType::F64),
+                )
+            }
+            Instruction::MemorySize { result } => StepInfo::MemorySize {
+                pages: pre.pages,
+                result: Self::trace_write(pre, result, ValType::I32),
+            },
+            Instruction::MemoryGrow { result, delta } => StepInfo::MemoryGrow {
+                delta: Self::trace_read(pre, delta, ValType::I32),
+                previous_pages: pre.pages,
+                result: Self::trace_write(pre, result, ValType::I32),
+            },
+            Instruction::MemoryGrowBy { result, delta } => StepInfo::MemoryGrow {
+                delta: IVal::imm(Val::I32(u32::from(delta) as i32)),
+                previous_pages: pre.pages,
+                result: Self::trace_write(pre, result, ValType::I32),
+            },
+            Instruction::I32Load(instr)
+            | Instruction::I64Load(instr)
+            | Instruction::F32Load(instr)
+            | Instruction::F64Load(instr)
+            | Instruction::I32Load8s(instr)
+            | Instruction::I32Load8u(instr)
+            | Instruction::I32Load16s(instr)
+            | Instruction::I32Load16u(instr)
+            | Instruction::I64Load8s(instr)
+            | Instruction::I64Load8u(instr)
+            | Instruction::I64Load16s(instr)
+            | Instruction::I64Load16u(instr)
+            | Instruction::I64Load32s(instr)
+            | Instruction::I64Load32u(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let offset = Self::trace_address_offset(pre);
+                self.trace_load(pre, instruction, instr.result, address, offset)
+            }
+            Instruction::I32LoadAt(instr)
+            | Instruction::I64LoadAt(instr)
+            | Instruction::F32LoadAt(instr)
+            | Instruction::F64LoadAt(instr)
+            | Instruction::I32Load8sAt(instr)
+            | Instruction::I32Load8uAt(instr)
+            | Instruction::I32Load16sAt(instr)
+            | Instruction::I32Load16uAt(instr)
+            | Instruction::I64Load8sAt(instr)
+            | Instruction::I64Load8uAt(instr)
+            | Instruction::I64Load16sAt(instr)
+            | Instruction::I64Load16uAt(instr)
+            | Instruction::I64Load32sAt(instr)
+            | Instruction::I64Load32uAt(instr) => {
+                let address = IVal::imm(Val::I32(0));
+                let offset = u32::from(instr.address);
+                self.trace_load(pre, instruction, instr.result, address, offset)
+            }
+            Instruction::I32LoadOffset16(instr)
+            | Instruction::I64LoadOffset16(instr)
+            | Instruction::F32LoadOffset16(instr)
+            | Instruction::F64LoadOffset16(instr)
+            | Instruction::I32Load8sOffset16(instr)
+            | Instruction::I32Load8uOffset16(instr)
+            | Instruction::I32Load16sOffset16(instr)
+            | Instruction::I32Load16uOffset16(instr)
+            | Instruction::I64Load8sOffset16(instr)
+            | Instruction::I64Load8uOffset16(instr)
+            | Instruction::I64Load16sOffset16(instr)
+            | Instruction::I64Load16uOffset16(instr)
+            | Instruction::I64Load32sOffset16(instr)
+            | Instruction::I64Load32uOffset16(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let offset = u32::from(instr.offset);
+                self.trace_load(pre, instruction, instr.result, address, offset)
+            }
+            Instruction::I32Store(instr)
+            | Instruction::I64Store(instr)
+            | Instruction::F32Store(instr)
+            | Instruction::F64Store(instr)
+            | Instruction::I32Store8(instr)
+            | Instruction::I32Store16(instr)
+            | Instruction::I64Store8(instr)
+            | Instruction::I64Store16(instr)
+            | Instruction::I64Store32(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let value = Self::trace_store_value(pre, instruction);
+                self.trace_store(instruction, address, u32::from(instr.offset), value)
+            }
+            Instruction::I32StoreOffset16(instr)
+            | Instruction::I64StoreOffset16(instr)
+            | Instruction::F32StoreOffset16(instr)
+            | Instruction::F64StoreOffset16(instr)
+            | Instruction::I32Store8Offset16(instr)
+            | Instruction::I32Store16Offset16(instr)
+            | Instruction::I64Store8Offset16(instr)
+            | Instruction::I64Store16Offset16(instr)
+            | Instruction::I64Store32Offset16(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let (ty, _) = Self::trace_store_kind(instruction);
+                let value = Self::trace_read(pre, instr.value, ty);
+                self.trace_store(instruction, address, u32::from(instr.offset), value)
+            }
+            Instruction::I32StoreAt(instr)
+            | Instruction::I64StoreAt(instr)
+            | Instruction::F32StoreAt(instr)
+            | Instruction::F64StoreAt(instr)
+            | Instruction::I32Store8At(instr)
+            | Instruction::I32Store16At(instr)
+            | Instruction::I64Store8At(instr)
+            | Instruction::I64Store16At(instr)
+            | Instruction::I64Store32At(instr) => {
+                let address = IVal::imm(Val::I32(0));
+                let (ty, _) = Self::trace_store_kind(instruction);
+                let value = Self::trace_read(pre, instr.value, ty);
+                self.trace_store(instruction, address, u32::from(instr.address), value)
+            }
+            Instruction::I32StoreOffset16Imm16(instr)
+            | Instruction::I64Store32Offset16Imm16(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let value = Self::trace_store_imm(instruction, i64::from(i32::from(instr.value)));
+                self.trace_store(instruction, address, u32::from(instr.offset), value)
+            }
+            Instruction::I64StoreOffset16Imm16(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let value = Self::trace_store_imm(instruction, i64::from(instr.value));
+                self.trace_store(instruction, address, u32::from(instr.offset), value)
+            }
+            Instruction::I32Store8Offset16Imm(instr) | Instruction::I64Store8Offset16Imm(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let value = Self::trace_store_imm(instruction, i64::from(instr.value));
+                self.trace_store(instruction, address, u32::from(instr.offset), value)
+            }
+            Instruction::I32Store16Offset16Imm(instr)
+            | Instruction::I64Store16Offset16Imm(instr) => {
+                let address = Self::trace_read(pre, instr.ptr, ValType::I32);
+                let value = Self::trace_store_imm(instruction, i64::from(instr.value));
+                self.trace_store(instruction, address, u32::from(instr.offset), value)
+            }
+            Instruction::I32StoreAtImm16(instr) | Instruction::I64Store32AtImm16(instr) => {
+                let address = IVal::imm(Val::I32(0));
+                let value = Self::trace_store_imm(instruction, i64::from(i32::from(instr.value)));
+                self.trace_store(instruction, address, u32::from(instr.address), value)
+            }
+            Instruction::I64StoreAtImm16(instr) => {
+                let address = IVal::imm(Val::I32(0));
+                let value = Self::trace_store_imm(instruction, i64::from(instr.value));
+                self.trace_store(instruction, address, u32::from(instr.address), value)
+            }
+            Instruction::I32Store8AtImm(instr) | Instruction::I64Store8AtImm(instr) => {
+                let address = IVal::imm(Val::I32(0));
+                let value = Self::trace_store_imm(instruction, i64::from(instr.value));
+                self.trace_store(instruction, address, u32::from(instr.address), value)
+            }
+            Instruction::I32Store16AtImm(instr) | Instruction::I64Store16AtImm(instr) => {
+                let address = IVal::imm(Val::I32(0));
+                let value = Self::trace_store_imm(instruction, i64::from(instr.value));
+                self.trace_store(instruction, address, u32::from(instr.address), value)
+            }
+            Instruction::TableGet { result, index } => {
+                let (table_index, element) = self.trace_table(pre);
+                StepInfo::TableGet {
+                    table_index,
+                    index: Self::trace_read(pre, index, ValType::I32),
+                    result: Self::trace_write(pre, result, element),
+                }
+            }
+            Instruction::TableGetImm { result, index } => {
+                let (table_index, element) = self.trace_table(pre);
+                StepInfo::TableGet {
+                    table_index,
+                    index: IVal::imm(Val::I32(u32::from(index) as i32)),
+                    result: Self::trace_write(pre, result, element),
+                }
+            }
+            Instruction::TableSet { index, value } => {
+                let (table_index, element) = self.trace_table(pre);
+                StepInfo::TableSet {
+                    table_index,
+                    index: Self::trace_read(pre, index, ValType::I32),
+                    value: Self::trace_read(pre, value, element),
+                }
+            }
+            Instruction::TableSetAt { index, value } => {
+                let (table_index, element) = self.trace_table(pre);
+                StepInfo::TableSet {
+                    table_index,
+                    index: IVal::imm(Val::I32(u32::from(index) as i32)),
+                    value: Self::trace_read(pre, value, element),
+                }
+            }
+            _ => {
+                // TODO: implement me
+                StepInfo::Unimplemented(*instruction)
+            }
+        }
+    }
+
+    /// Returns the `(left, right, result)` operands of the binary `instr` interpreted as `ty`.
+    fn trace_binary(pre: &PreStep, instr: BinInstr, ty: ValType) -> (IVal, IVal, IVal) {
+        (
+            Self::trace_read(pre, instr.lhs, ty),
+            Self::trace_read(pre, instr.rhs, ty),
+            Self::trace_write(pre, instr.result, ty),
+        )
+    }
+
+    /// Returns the [`StepInfo::I64BinOp`] of the `instruction` with the immediate `imm` operand.
+    ///
+    /// The immediate is the `left` operand if `imm_is_lhs` and the `right` operand otherwise.
+    fn trace_i64_binary_imm(
+        pre: &PreStep,
+        instruction: &Instruction,
+        reg_in: Register,
+        imm: i64,
+        result: Register,
+        imm_is_lhs: bool,
+    ) -> StepInfo {
+        let input = Self::trace_read(pre, reg_in, ValType::I64);
+        let imm = IVal::imm(Val::I64(imm));
+        let (left, right) = match imm_is_lhs {
+            true => (imm, input),
+            false => (input, imm),
+        };
+        StepInfo::I64BinOp {
+            class: Self::trace_arith_class(instruction),
+            left,
+            right,
+            result: Self::trace_write(pre, result, ValType::I64),
+        }
+    }
+
+    /// Returns the [`StepInfo::RelOp`] of the integer comparison `instruction`
+    /// of `left` and `right` into the `result` register.
+    fn trace_compare(
+        pre: &PreStep,
+        instruction: &Instruction,
+        left: IVal,
+        right: IVal,
+        result: Register,
+    ) -> StepInfo {
+        StepInfo::RelOp {
+            class: Self::trace_rel_class(instruction),
+            left,
+            right,
+            result: Self::trace_write(pre, result, ValType::I32),
+        }
+    }
+
+    /// Returns the [`RelOp`] of the integer comparison `instruction`.
+    fn trace_rel_class(instruction: &Instruction) -> RelOp {
+        match instruction {
+            Instruction::I32Eq(_)
+            | Instruction::I32EqImm16(_)
+            | Instruction::I64Eq(_)
+            | Instruction::I64EqImm16(_) => RelOp::Eq,
+            Instruction::I32Ne(_)
+            | Instruction::I32NeImm16(_)
+            | Instruction::I64Ne(_)
+            | Instruction::I64NeImm16(_) => RelOp::Ne,
+            Instruction::I32LtS(_)
+            | Instruction::I32LtSImm16(_)
+            | Instruction::I64LtS(_)
+            | Instruction::I64LtSImm16(_) => RelOp::SignedLt,
+            Instruction::I32LtU(_)
+            | Instruction::I32LtUImm16(_)
+            | Instruction::I64LtU(_)
+            | Instruction::I64LtUImm16(_) => RelOp::UnsignedLt,
+            Instruction::I32GtS(_)
+            | Instruction::I32GtSImm16(_)
+            | Instruction::I64GtS(_)
+            | Instruction::I64GtSImm16(_) => RelOp::SignedGt,
+            Instruction::I32GtU(_)
+            | Instruction::I32GtUImm16(_)
+            | Instruction::I64GtU(_)
+            | Instruction::I64GtUImm16(_) => RelOp::UnsignedGt,
+            Instruction::I32LeS(_)
+            | Instruction::I32LeSImm16(_)
+            | Instruction::I64LeS(_)
+            | Instruction::I64LeSImm16(_) => RelOp::SignedLe,
+            Instruction::I32LeU(_)
+            | Instruction::I32LeUImm16(_)
+            | Instruction::I64LeU(_)
+            | Instruction::I64LeUImm16(_) => RelOp::UnsignedLe,
+            Instruction::I32GeS(_)
+            | Instruction::I32GeSImm16(_)
+            | Instruction::I64GeS(_)
+            | Instruction::I64GeSImm16(_) => RelOp::SignedGe,
+            Instruction::I32GeU(_)
+            | Instruction::I32GeUImm16(_)
+            | Instruction::I64GeU(_)
+            | Instruction::I64GeUImm16(_) => RelOp::UnsignedGe,
+            _ => unreachable!(
+                "expected an integer comparison instruction but found: {instruction:?}"
+            ),
+        }
+    }
+
+    /// Returns the [`BinOp`] of the integer arithmetic or bitwise `instruction`.
+    fn trace_arith_class(instruction: &Instruction) -> BinOp {
+        match instruction {
+            Instruction::I64Add(_) | Instruction::I64AddImm16(_) => BinOp::Add,
+            Instruction::I64Sub(_) | Instruction::I64SubImm16Rev(_) => BinOp::Sub,
+            Instruction::I64Mul(_) | Instruction::I64MulImm16(_) => BinOp::Mul,
+            Instruction::I64DivS(_)
+            | Instruction::I64DivSImm16(_)
+            | Instruction::I64DivSImm16Rev(_) => BinOp::SignedDiv,
+            Instruction::I64DivU(_)
+            | Instruction::I64DivUImm16(_)
+            | Instruction::I64DivUImm16Rev(_) => BinOp::UnsignedDiv,
+            Instruction::I64RemS(_)
+            | Instruction::I64RemSImm16(_)
+            | Instruction::I64RemSImm16Rev(_) => BinOp::SignedRem,
+            Instruction::I64RemU(_)
+            | Instruction::I64RemUImm16(_)
+            | Instruction::I64RemUImm16Rev(_) => BinOp::UnsignedRem,
+            Instruction::I64And(_) | Instruction::I64AndImm16(_) => BinOp::And,
+            Instruction::I64Or(_) | Instruction::I64OrImm16(_) => BinOp::Or,
+            Instruction::I64Xor(_) | Instruction::I64XorImm16(_) => BinOp::Xor,
+            _ => unreachable!(
+                "expected an integer arithmetic instruction but found: {instruction:?}"
+            ),
+        }
+    }
+
+    /// Returns the [`BinOp`] of the `f32` or `f64` arithmetic `instruction`.
+    fn trace_float_class(instruction: &Instruction) -> BinOp {
+        match instruction {
+            Instruction::F32Add(_) | Instruction::F64Add(_) => BinOp::Add,
+            Instruction::F32Sub(_) | Instruction::F64Sub(_) => BinOp::Sub,
+            Instruction::F32Mul(_) | Instruction::F64Mul(_) => BinOp::Mul,
+            Instruction::F32Div(_) | Instruction::F64Div(_) => BinOp::Div,
+            Instruction::F32Min(_) | Instruction::F64Min(_) => BinOp::Min,
+            Instruction::F32Max(_) | Instruction::F64Max(_) => BinOp::Max,
+            Instruction::F32Copysign(_) | Instruction::F64Copysign(_) => BinOp::CopySign,
+            _ => unreachable!("expected a float arithmetic instruction but found: {instruction:?}"),
+        }
+    }
+
+    /// Returns the [`FloatUnOp`] of the `f32` or `f64` unary `instruction`.
+    fn trace_float_unop_class(instruction: &Instruction) -> FloatUnOp {
+        match instruction {
+            Instruction::F32Abs(_) | Instruction::F64Abs(_) => FloatUnOp::Abs,
+            Instruction::F32Neg(_) | Instruction::F64Neg(_) => FloatUnOp::Neg,
+            Instruction::F32Sqrt(_) | Instruction::F64Sqrt(_) => FloatUnOp::Sqrt,
+            Instruction::F32Ceil(_) | Instruction::F64Ceil(_) => FloatUnOp::Ceil,
+            Instruction::F32Floor(_) | Instruction::F64Floor(_) => FloatUnOp::Floor,
+            Instruction::F32Trunc(_) | Instruction::F64Trunc(_) => FloatUnOp::Trunc,
+            Instruction::F32Nearest(_) | Instruction::F64Nearest(_) => FloatUnOp::Nearest,
+            _ => unreachable!("expected a float unary instruction but found: {instruction:?}"),
+        }
+    }
+
+    /// Returns the [`BinOp`] of the shift or rotate `instruction`.
+    ///
+    /// # Note
+    ///
+    /// The traced results are read back from the executed instruction and thus
+    /// take the shift amount modulo the bit width of the operands as Wasm demands.
+    fn trace_shift_class(instruction: &Instruction) -> BinOp {
+        match instruction {
+            Instruction::I32Shl(_)
+            | Instruction::I32ShlImm(_)
+            | Instruction::I32ShlImm16Rev(_)
+            | Instruction::I64Shl(_)
+            | Instruction::I64ShlImm(_)
+            | Instruction::I64ShlImm16Rev(_) => BinOp::Shl,
+            Instruction::I32ShrS(_)
+            | Instruction::I32ShrSImm(_)
+            | Instruction::I32ShrSImm16Rev(_)
+            | Instruction::I64ShrS(_)
+            | Instruction::I64ShrSImm(_)
+            | Instruction::I64ShrSImm16Rev(_) => BinOp::ShrS,
+            Instruction::I32ShrU(_)
+            | Instruction::I32ShrUImm(_)
+            | Instruction::I32ShrUImm16Rev(_)
+            | Instruction::I64ShrU(_)
+            | Instruction::I64ShrUImm(_)
+            | Instruction::I64ShrUImm16Rev(_) => BinOp::ShrU,
+            Instruction::I32Rotl(_)
+            | Instruction::I32RotlImm(_)
+            | Instruction::I32RotlImm16Rev(_)
+            | Instruction::I64Rotl(_)
+            | Instruction::I64RotlImm(_)
+            | Instruction::I64RotlImm16Rev(_) => BinOp::Rotl,
+            Instruction::I32Rotr(_)
+            | Instruction::I32RotrImm(_)
+            | Instruction::I32RotrImm16Rev(_)
+            | Instruction::I64Rotr(_)
+            | Instruction::I64RotrImm(_)
+            | Instruction::I64RotrImm16Rev(_) => BinOp::Rotr,
+            _ => unreachable!("expected a shift or rotate instruction but found: {instruction:?}"),
+        }
+    }
+
+    /// Returns the [`StepInfo::Select`] with the `condition` read by the traced instruction.
+    fn trace_select(
+        pre: &PreStep,
+        condition: Register,
+        lhs: IVal,
+        rhs: IVal,
+        result: IVal,
+    ) -> StepInfo {
+        let cond = Self::trace_read(pre, condition, ValType::I32);
+        let cond_is_true = cond.val.i32() != Some(0);
+        let discarded = if cond_is_true {
+            rhs.clone()
+        } else {
+            lhs.clone()
+        };
+        StepInfo::Select {
+            cond,
+            cond_is_true,
+            lhs,
+            rhs,
+            result,
+            discarded,
+        }
+    }
+
+    /// Returns the `select` operand encoded by the instruction word following the traced instruction.
+    ///
+    /// # Note
+    ///
+    /// Operands held in registers are untyped and thus traced by their raw bits.
+    fn trace_select_param(pre: &PreStep) -> IVal {
+        match Self::trace_next_instr(pre) {
+            Instruction::Register(register) => Self::trace_untyped_read(pre, register),
+            Instruction::Const32(value) => IVal::imm(Val::I32(u32::from(value) as i32)),
+            Instruction::I64Const32(value) => IVal::imm(Val::I64(i64::from(value))),
+            Instruction::F64Const32(value) => IVal::imm(Val::F64(f64::from(value).into())),
+            unexpected => unreachable!(
+                "expected a select parameter instruction word but found {unexpected:?}"
+            ),
+        }
+    }
+
+    /// Returns the instruction word following the traced instruction.
+    fn trace_next_instr(pre: &PreStep) -> Instruction {
+        let mut addr = pre.ip;
+        addr.add(1);
+        *addr.get()
+    }
+
+    /// Returns the [`StepInfo::Call`] of the call `instruction` to the function at `func_index`.
+    ///
+    /// # Note
+    ///
+    /// Calls to Wasm functions of other instances have switched the executing
+    /// instance once the call instruction has been executed.
+    fn trace_call(
+        &self,
+        pre: &PreStep,
+        instruction: &Instruction,
+        func_index: u32,
+        host: bool,
+        tracer: &Tracer,
+    ) -> StepInfo {
+        let (args, params) = self.trace_call_args(pre, instruction, host);
+        StepInfo::Call {
+            func_index,
+            name: tracer.func_name(func_index).map(String::from),
+            host,
+            crosses_instance: !host && pre.instance != *self.cache.instance(),
+            args,
+            params,
+            kind: Self::trace_call_kind(instruction),
+        }
+    }
+
+    /// Returns the [`StepInfo::CallIndirect`] of the `call_indirect` `instruction`.
+    ///
+    /// # Note
+    ///
+    /// The called function is looked up in the table of the calling instance
+    /// again since the executed instruction does not expose it.
+    fn trace_call_indirect(
+        &self,
+        pre: &PreStep,
+        instruction: &Instruction,
+        host: bool,
+        tracer: &mut Tracer,
+    ) -> StepInfo {
+        let (table, elem_index) = Self::trace_call_indirect_params(pre);
+        let caller = self.ctx.resolve_instance(&pre.instance);
+        let element = caller
+            .get_table(table.to_u32())
+            .and_then(|table| {
+                let index = elem_index.val.i32().expect("element index must be an i32") as u32;
+                self.ctx.resolve_table(&table).get_untyped(index)
+            })
+            .expect("called table element must be in bounds");
+        let resolved_func_index =
+            tracer
+                .func_indices()
+                .func_ref_index(self.ctx, &pre.instance, element);
+        let (args, params) = self.trace_call_args(pre, instruction, host);
+        StepInfo::CallIndirect {
+            table_index: table.to_u32(),
+            elem_index,
+            resolved_func_index,
+            name: resolved_func_index
+                .and_then(|func_index| tracer.func_name(func_index))
+                .map(String::from),
+            host,
+            crosses_instance: !host && pre.instance != *self.cache.instance(),
+            args,
+            params,
+            kind: Self::trace_call_kind(instruction),
+        }
+    }
+
+    /// Returns the table and the element index of a `call_indirect`.
+    ///
+    /// Both are encoded by the instruction word following the traced instruction.
+    fn trace_call_indirect_params(pre: &PreStep) -> (TableIdx, IVal) {
+        match Self::trace_next_instr(pre) {
+            Instruction::CallIndirectParams(params) => (
+                params.table,
+                Self::trace_read(pre, params.index, ValType::I32),
+            ),
+            Instruction::CallIndirectParamsImm16(params) => (
+                params.table,
+                IVal::imm(Val::I32(u32::from(params.index) as i32)),
+            ),
+            unexpected => unreachable!(
+                "expected `Instruction::CallIndirectParams[Imm16]` but found {unexpected:?}"
+            ),
+        }
+    }
+
+    /// Returns the arguments read by the call `instruction` and the parameters of the callee.
+    ///
+    /// # Note
+    ///
+    /// Calls to Wasm functions have pushed the frame of the callee whose first
+    /// registers hold the copied arguments. Tail calls have replaced the frame
+    /// of the caller. Calls to `host` functions have no parameter registers.
+    fn trace_call_args(
+        &self,
+        pre: &PreStep,
+        instruction: &Instruction,
+        host: bool,
+    ) -> (Vec<IVal>, Vec<IVal>) {
+        let registers = match instruction {
+            Instruction::CallInternal { .. }
+            | Instruction::CallImported { .. }
+            | Instruction::ReturnCallInternal { .. }
+            | Instruction::ReturnCallImported { .. } => Self::trace_register_list(pre, &[]),
+            Instruction::CallIndirect { .. } | Instruction::ReturnCallIndirect { .. } => {
+                // The arguments follow the `Instruction::CallIndirectParams` word.
+                let mut params = pre.ip;
+                params.add(1);
+                Self::trace_register_list_after(params, &[])
+            }
+            _ => Vec::new(),
+        };
+        let args = registers
+            .into_iter()
+            .map(|register| Self::trace_untyped_read(pre, register))
+            .collect::<Vec<_>>();
+        let params = if host {
+            Vec::new()
+        } else {
+            let callee = self
+                .call_stack
+                .peek()
+                .expect("must have the frame of the callee on the call stack");
+            let base = usize::from(callee.base_offset());
+            args.iter()
+                .enumerate()
+                .map(|(index, arg)| IVal::stack(arg.val.clone(), base + index))
+                .collect()
+        };
+        (args, params)
+    }
+
+    /// Returns the [`CallKind`] of the call `instruction`.
+    fn trace_call_kind(instruction: &Instruction) -> CallKind {
+        match instruction {
+            Instruction::ReturnCallInternal0 { .. }
+            | Instruction::ReturnCallInternal { .. }
+            | Instruction::ReturnCallImported0 { .. }
+            | Instruction::ReturnCallImported { .. }
+            | Instruction::ReturnCallIndirect0 { .. }
+            | Instruction::ReturnCallIndirect { .. } => CallKind::Tail,
+            _ => CallKind::Nested,
+        }
+    }
+
+    /// Returns the index of the function with the `compiled_func` body within the current instance.
+    ///
+    /// # Note
+    ///
+    /// Internal calls only refer to the [`CompiledFunc`] of the callee, so the function
+    /// index is looked up in the indexed functions of the instance executing the call.
+    fn trace_internal_func_index(&self, compiled_func: CompiledFunc, tracer: &mut Tracer) -> u32 {
+        tracer
+            .func_indices()
+            .func_index(self.ctx, self.cache.instance(), compiled_func)
+            .expect("internally called function must be defined in the calling instance")
+    }
+
+    /// Returns the [`StepInfo::BrIf`] of the executed conditional branch `instruction`.
+    ///
+    /// Returns `None` if `instruction` is not a conditional branch instruction.
+    fn trace_br_if(&self, pre: &PreStep, instruction: &Instruction) -> Option<StepInfo> {
+        use BranchComparator as Cmp;
+        let reg = |cmp: Cmp, instr: BranchBinOpInstr| {
+            let ty = Self::trace_branch_operand_ty(cmp);
+            let lhs = Self::trace_read(pre, instr.lhs, ty);
+            let rhs = Self::trace_read(pre, instr.rhs, ty);
+            (cmp, lhs, rhs, BranchOffset::from(instr.offset))
+        };
+        let (cmp, lhs, rhs, offset) = match *instruction {
+            Instruction::BranchI32And(instr) => reg(Cmp::I32And, instr),
+            Instruction::BranchI32AndImm(instr) => Self::trace_branch_imm(pre, Cmp::I32And, instr),
+            Instruction::BranchI32Or(instr) => reg(Cmp::I32Or, instr),
+            Instruction::BranchI32OrImm(instr) => Self::trace_branch_imm(pre, Cmp::I32Or, instr),
+            Instruction::BranchI32Xor(instr) => reg(Cmp::I32Xor, instr),
+            Instruction::BranchI32XorImm(instr) => Self::trace_branch_imm(pre, Cmp::I32Xor, instr),
+            Instruction::BranchI32AndEqz(instr) => reg(Cmp::I32AndEqz, instr),
+            Instruction::BranchI32AndEqzImm(instr) => {
+                Self::trace_branch_imm(pre, Cmp::I32AndEqz, instr)
+            }
+            Instruction::BranchI32OrEqz(instr) => reg(Cmp::I32OrEqz, instr),
+            Instruction::BranchI32OrEqzImm(instr) => {
+                Self::trace_branch_imm(pre, Cmp::I32OrEqz, instr)
+            }
+            Instruction::BranchI32XorEqz(instr) => reg(Cmp::I32XorEqz, instr),
+            Instruction::BranchI32XorEqzImm(instr) => {
+                Self::trace_branch_imm(pre, Cmp::I32XorEqz, instr)
+            }
+            Instruction::BranchI32Eq(instr) => reg(Cmp::I32Eq, instr),
+            Instruction::BranchI32EqImm(instr) => Self::trace_branch_imm(pre, Cmp::I32Eq, instr),
+            Instruction::BranchI32Ne(instr) => reg(Cmp::I32Ne, instr),
+            Instruction::BranchI32NeImm(instr) => Self::trace_branch_imm(pre, Cmp::I32Ne, instr),
+            Instruction::BranchI32LtS(instr) => reg(Cmp::I32LtS, instr),
+            Instruction::BranchI32LtSImm(instr) => Self::trace_branch_imm(pre, Cmp::I32LtS, instr),
+            Instruction::BranchI32LtU(instr) => reg(Cmp::I32LtU, instr),
+            Instruction::BranchI32LtUImm(instr) => Self::trace_branch_imm(pre, Cmp::I32LtU, instr),
+            Instruction::BranchI32LeS(instr) => reg(Cmp::I32LeS, instr),
+            Instruction::BranchI32LeSImm(instr) => Self::trace_branch_imm(pre, Cmp::I32LeS, instr),
+            Instruction::BranchI32LeU(instr) => reg(Cmp::I32LeU, instr),
+            Instruction::BranchI32LeUImm(instr) => Self::trace_branch_imm(pre, Cmp::I32LeU, instr),
+            Instruction::BranchI32GtS(instr) => reg(Cmp::I32GtS, instr),
+            Instruction::BranchI32GtSImm(instr) => Self::trace_branch_imm(pre, Cmp::I32GtS, instr),
+            Instruction::BranchI32GtU(instr) => reg(Cmp::I32GtU, instr),
+            Instruction::BranchI32GtUImm(instr) => Self::trace_branch_imm(pre, Cmp::I32GtU, instr),
+            Instruction::BranchI32GeS(instr) => reg(Cmp::I32GeS, instr),
+            Instruction::BranchI32GeSImm(instr) => Self::trace_branch_imm(pre, Cmp::I32GeS, instr),
+            Instruction::BranchI32GeU(instr) => reg(Cmp::I32GeU, instr),
+            Instruction::BranchI32GeUImm(instr) => Self::trace_branch_imm(pre, Cmp::I32GeU, instr),
+            Instruction::BranchI64Eq(instr) => reg(Cmp::I64Eq, instr),
+            Instruction::BranchI64EqImm(instr) => Self::trace_branch_imm(pre, Cmp::I64Eq, instr),
+            Instruction::BranchI64Ne(instr) => reg(Cmp::I64Ne, instr),
+            Instruction::BranchI64NeImm(instr) => Self::trace_branch_imm(pre, Cmp::I64Ne, instr),
+            Instruction::BranchI64LtS(instr) => reg(Cmp::I64LtS, instr),
+            Instruction::BranchI64LtSImm(instr) => Self::trace_branch_imm(pre, Cmp::I64LtS, instr),
+            Instruction::BranchI64LtU(instr) => reg(Cmp::I64LtU, instr),
+            Instruction::BranchI64LtUImm(instr) => Self::trace_branch_imm(pre, Cmp::I64LtU, instr),
+            Instruction::BranchI64LeS(instr) => reg(Cmp::I64LeS, instr),
+            Instruction::BranchI64LeSImm(instr) => Self::trace_branch_imm(pre, Cmp::I64LeS, instr),
+            Instruction::BranchI64LeU(instr) => reg(Cmp::I64LeU, instr),
+            Instruction::BranchI64LeUImm(instr) => Self::trace_branch_imm(pre, Cmp::I64LeU, instr),
+            Instruction::BranchI64GtS(instr) => reg(Cmp::I64GtS, instr),
+            Instruction::BranchI64GtSImm(instr) => Self::trace_branch_imm(pre, Cmp::I64GtS, instr),
+            Instruction::BranchI64GtU(instr) => reg(Cmp::I64GtU, instr),
+            Instruction::BranchI64GtUImm(instr) => Self::trace_branch_imm(pre, Cmp::I64GtU, instr),
+            Instruction::BranchI64GeS(instr) => reg(Cmp::I64GeS, instr),
+            Instruction::BranchI64GeSImm(instr) => Self::trace_branch_imm(pre, Cmp::I64GeS, instr),
+            Instruction::BranchI64GeU(instr) => reg(Cmp::I64GeU, instr),
+            Instruction::BranchI64GeUImm(instr) => Self::trace_branch_imm(pre, Cmp::I64GeU, instr),
+            Instruction::BranchF32Eq(instr) => reg(Cmp::F32Eq, instr),
+            Instruction::BranchF32Ne(instr) => reg(Cmp::F32Ne, instr),
+            Instruction::BranchF32Lt(instr) => reg(Cmp::F32Lt, instr),
+            Instruction::BranchF32Le(instr) => reg(Cmp::F32Le, instr),
+            Instruction::BranchF32Gt(instr) => reg(Cmp::F32Gt, instr),
+            Instruction::BranchF32Ge(instr) => reg(Cmp::F32Ge, instr),
+            Instruction::BranchF64Eq(instr) => reg(Cmp::F64Eq, instr),
+            Instruction::BranchF64Ne(instr) => reg(Cmp::F64Ne, instr),
+            Instruction::BranchF64Lt(instr) => reg(Cmp::F64Lt, instr),
+            Instruction::BranchF64Le(instr) => reg(Cmp::F64Le, instr),
+            Instruction::BranchF64Gt(instr) => reg(Cmp::F64Gt, instr),
+            Instruction::BranchF64Ge(instr) => reg(Cmp::F64Ge, instr),
+            Instruction::BranchCmpFallback { lhs, rhs, params } => {
+                let params = pre.register(Self::trace_stack_addr(pre, params));
+                let params = ComparatorOffsetParam::from_untyped(params)
+                    .expect("branch fallback must encode its comparator and offset");
+                let ty = Self::trace_branch_operand_ty(params.cmp);
+                let lhs = Self::trace_read(pre, lhs, ty);
+                let rhs = Self::trace_read(pre, rhs, ty);
+                (params.cmp, lhs, rhs, params.offset)
+            }
+            _ => return None,
+        };
+        let offset = offset.to_i32();
+        Some(StepInfo::BrIf {
+            cmp,
+            lhs,
+            rhs,
+            taken: self.trace_ip_delta(pre) == offset,
+            target_pc: self.trace_pc(pre, offset),
+        })
+    }
+
+    /// Returns the comparator, operands and offset of the conditional branch
+    /// `instr` comparing against an immediate.
+    fn trace_branch_imm<T>(
+        pre: &PreStep,
+        cmp: BranchComparator,
+        instr: BranchBinOpInstrImm16<T>,
+    ) -> (BranchComparator, IVal, IVal, BranchOffset)
+    where
+        T: From<Const16<T>>,
+        UntypedVal: From<T>,
+    {
+        let ty = Self::trace_branch_operand_ty(cmp);
+        let lhs = Self::trace_read(pre, instr.lhs, ty);
+        let rhs = IVal::imm(UntypedVal::from(T::from(instr.rhs)).with_type(ty));
+        (cmp, lhs, rhs, BranchOffset::from(instr.offset))
+    }
+
+    /// Returns the type of the operands compared by `cmp`.
+    fn trace_branch_operand_ty(cmp: BranchComparator) -> ValType {
+        use BranchComparator as Cmp;
+        // The comparators are ordered by the type of their operands.
+        match cmp as u32 {
+            n if n < Cmp::I64Eq as u32 => ValType::I32,
+            n if n < Cmp::F32Eq as u32 => ValType::I64,
+            n if n < Cmp::F64Eq as u32 => ValType::F32,
+            _ => ValType::F64,
+        }
+    }
+
+    /// Returns the number of instructions the executed instruction advanced the instruction pointer.
+    fn trace_ip_delta(&self, pre: &PreStep) -> i32 {
+        // SAFETY: branches stay within the instructions of the executing function.
+        let delta = unsafe { self.ip.ptr.offset_from(pre.ip.ptr) };
+        i32::try_from(delta).expect("branch offset must fit into i32")
+    }
+
+    /// Returns the offset of the instruction `delta` instructions after the one at `pre.ip`
+    /// within the instructions of its function.
+    fn trace_pc(&self, pre: &PreStep, delta: i32) -> u32 {
+        pre.pc
+            .checked_add_signed(delta)
+            .expect("branch target must be within the function")
+    }
+
+    /// Returns the [`StepInfo::Return`] of the executed `instruction` if it returned.
+    ///
+    /// Returns `None` if `instruction` is not a return instruction or is
+    /// a conditional return instruction that did not return.
+    fn trace_return(pre: &PreStep, instruction: &Instruction) -> Option<StepInfo> {
+        let untyped_imm = |value: UntypedVal| IVal::imm(Val::I64(i64::from(value)));
+        let registers = |registers: &[Register]| {
+            registers
+                .iter()
+                .map(|register| Self::trace_untyped_read(pre, *register))
+                .collect::<Vec<_>>()
+        };
+        let (condition, values) = match *instruction {
+            Instruction::Return => (None, Vec::new()),
+            Instruction::ReturnReg { value } => (None, registers(&[value])),
+            Instruction::ReturnReg2 { values } => (None, registers(&values)),
+            Instruction::ReturnReg3 { values } => (None, registers(&values)),
+            Instruction::ReturnImm32 { value } => {
+                (None, std::vec![untyped_imm(u32::from(value).into())])
+            }
+            Instruction::ReturnI64Imm32 { value } => {
+                (None, std::vec![IVal::imm(Val::I64(i64::from(value)))])
+            }
+            Instruction::ReturnF64Imm32 { value } => (
+                None,
+                std::vec![IVal::imm(Val::F64(f64::from(value).into()))],
+            ),
+            Instruction::ReturnSpan { values } => (None, registers(&values.collect::<Vec<_>>())),
+            Instruction::ReturnMany { values } => {
+                (None, registers(&Self::trace_register_list(pre, &values)))
+            }
+            Instruction::ReturnNez { condition } => (Some(condition), Vec::new()),
+            Instruction::ReturnNezReg { condition, value } => {
+                (Some(condition), registers(&[value]))
+            }
+            Instruction::ReturnNezReg2 { condition, values } => {
+                (Some(condition), registers(&values))
+            }
+            Instruction::ReturnNezImm32 { condition, value } => (
+                Some(condition),
+                std::vec![untyped_imm(u32::from(value).into())],
+            ),
+            Instruction::ReturnNezI64Imm32 { condition, value } => (
+                Some(condition),
+                std::vec![IVal::imm(Val::I64(i64::from(value)))],
+            ),
+            Instruction::ReturnNezF64Imm32 { condition, value } => (
+                Some(condition),
+                std::vec![IVal::imm(Val::F64(f64::from(value).into()))],
+            ),
+            Instruction::ReturnNezSpan { condition, values } => {
+                (Some(condition), registers(&values.collect::<Vec<_>>()))
+            }
+            Instruction::ReturnNezMany { condition, values } => (
+                Some(condition),
+                registers(&Self::trace_register_list(pre, &values)),
+            ),
+            _ => return None,
+        };
+        let condition = condition.map(|condition| Self::trace_read(pre, condition, ValType::I32));
+        if let Some(condition) = &condition {
+            if condition.val.i32() == Some(0) {
+                return None;
+            }
+        }
+        let results = values
+            .iter()
+            .zip(pre.results.iter(values.len()))
+            .map(|(value, result)| {
+                let addr = pre
+                    .caller_base
+                    .checked_add_signed(isize::from(result.to_i16()))
+                    .expect("result register must be within the bounds of the value stack");
+                IVal::stack(value.val.clone(), addr)
+            })
+            .collect();
+        let returned = values
+            .iter()
+            .filter_map(|value| value.addr)
+            .collect::<Vec<_>>();
+        let dropped = (pre.base..pre.height)
+            .filter(|addr| !returned.contains(addr))
+            .map(|addr| {
+                let register = i16::try_from(addr - pre.base)
+                    .map(Register::from_i16)
+                    .expect("function frames have at most i16::MAX registers");
+                Self::trace_untyped_read(pre, register)
+            })
+            .collect();
+        Some(StepInfo::Return {
+            condition,
+            values,
+            results,
+            dropped,
+        })
+    }
+
+    /// Returns the [`StepInfo::Copy`] of the executed copy `instruction`.
+    ///
+    /// # Note
+    ///
+    /// The copied values are read from the registers as they were before the
+    /// execution since the copied spans may overlap.
+    fn trace_copy(pre: &PreStep, instruction: &Instruction) -> StepInfo {
+        let untyped_imm = |value: UntypedVal| IVal::imm(Val::I64(i64::from(value)));
+        let registers = |registers: &[Register]| {
+            registers
+                .iter()
+                .map(|register| Self::trace_untyped_read(pre, *register))
+                .collect::<Vec<_>>()
+        };
+        let (results, values) = match *instruction {
+            Instruction::Copy { result, value } => (std::vec![result], registers(&[value])),
+            Instruction::Copy2 { results, values } => {
+                (results.iter(2).collect(), registers(&values))
+            }
+            Instruction::CopyImm32 { result, value } => (
+                std::vec![result],
+                std::vec![untyped_imm(u32::from(value).into())],
+            ),
+            Instruction::CopyI64Imm32 { result, value } => (
+                std::vec![result],
+                std::vec![untyped_imm(i64::from(value).into())],
+            ),
+            Instruction::CopyF64Imm32 { result, value } => (
+                std::vec![result],
+                std::vec![untyped_imm(f64::from(value).into())],
+            ),
+            Instruction::CopySpan {
+                results,
+                values,
+                len,
+            }
+            | Instruction::CopySpanNonOverlapping {
+                results,
+                values,
+                len,
+            } => (
+                results.iter_u16(len).collect(),
+                registers(&values.iter_u16(len).collect::<Vec<_>>()),
+            ),
+            Instruction::CopyMany { results, values }
+            | Instruction::CopyManyNonOverlapping { results, values } => {
+                let values = Self::trace_register_list(pre, &values);
+                (results.iter(values.len()).collect(), registers(&values))
+            }
+            _ => unreachable!("expected a copy instruction but found: {instruction:?}"),
+        };
+        let results = results
+            .into_iter()
+            .map(|result| Self::trace_write(pre, result, ValType::I64))
+            .collect();
+        StepInfo::Copy { values, results }
+    }
+
+    /// Returns the [`StepInfo::LocalOp`] of the single register `copy` if it accesses a local.
+    ///
+    /// Returns the `copy` unchanged otherwise. The overwritten value of a local
+    /// is captured as the `prev_value` of a [`LocalOpKind::Set`] if `capture_prev`.
+    ///
+    /// # Note
+    ///
+    /// A `local.tee` is traced as a [`LocalOpKind::Set`] since the value it leaves
+    /// on the stack is the register of the local itself and thus written only once.
+    fn trace_local_op(&self, pre: &PreStep, copy: StepInfo, capture_prev: bool) -> StepInfo {
+        let StepInfo::Copy { values, results } = &copy else {
+            return copy;
+        };
+        let ([source], [dest]) = (&values[..], &results[..]) else {
+            return copy;
+        };
+        let locals = pre.base..pre.base + usize::from(pre.len_locals);
+        let is_local = |ival: &IVal| ival.addr.is_some_and(|addr| locals.contains(&addr));
+        let kind = match (is_local(source), is_local(dest)) {
+            (_, true) => LocalOpKind::Set,
+            (true, false) => LocalOpKind::Get,
+            (false, false) => return copy,
+        };
+        let prev_value = match (kind, dest.addr) {
+            (LocalOpKind::Set, Some(addr)) if capture_prev => {
+                let prev = pre.register(addr);
+                Some(IVal::stack(Val::I64(i64::from(prev)), addr))
+            }
+            _ => None,
+        };
+        StepInfo::LocalOp {
+            kind,
+            source: source.clone(),
+            dest: dest.clone(),
+            prev_value,
+        }
+    }
+
+    /// Returns the [`StepInfo::Const`] of the immediate copy `instruction` if its `step` is a copy.
+    ///
+    /// Returns the `step` unchanged otherwise, e.g. if the constant was copied into a local.
+    fn trace_const(pre: &PreStep, instruction: &Instruction, step: StepInfo) -> StepInfo {
+        if !matches!(step, StepInfo::Copy { .. }) {
+            return step;
+        }
+        let (result, ty) = match *instruction {
+            Instruction::CopyImm32 { result, .. } => (result, ValType::I32),
+            Instruction::CopyI64Imm32 { result, .. } => (result, ValType::I64),
+            Instruction::CopyF64Imm32 { result, .. } => (result, ValType::F64),
+            _ => return step,
+        };
+        StepInfo::Const {
+            value: Self::trace_write(pre, result, ty),
+        }
+    }
+
+    /// Returns the [`Register`]s of an instruction followed by [`Instruction::RegisterList`] words.
+    ///
+    /// The list is terminated by an [`Instruction::Register`], [`Instruction::Register2`]
+    /// or [`Instruction::Register3`] instruction word.
+    fn trace_register_list(pre: &PreStep, head: &[Register]) -> Vec<Register> {
+        Self::trace_register_list_after(pre.ip, head)
+    }
+
+    /// Like [`Self::trace_register_list`] but for a list following the instruction word at `ip`.
+    fn trace_register_list_after(mut ip: InstructionPtr, head: &[Register]) -> Vec<Register> {
+        let mut registers = head.to_vec();
+        ip.add(1);
+        while let Instruction::RegisterList(values) = ip.get() {
+            registers.extend_from_slice(values);
+            ip.add(1);
+        }
+        let tail = match ip.get() {
+            Instruction::Register(value) => slice::from_ref(value),
+            Instruction::Register2(values) => values,
+            Instruction::Register3(values) => values,
+            unexpected => {
+                unreachable!("expected a register list terminator but found: {unexpected:?}")
+            }
+        };
+        registers.extend_from_slice(tail);
+        registers
+    }
+
+    /// Returns the operands the trapping `instruction` read before it trapped.
+    fn trace_trap_reads(&mut self, pre: &PreStep, instruction: &Instruction) -> Vec<IVal> {
+        match *instruction {
+            Instruction::CallIndirect0 { .. }
+            | Instruction::CallIndirect { .. }
+            | Instruction::ReturnCallIndirect0 { .. }
+            | Instruction::ReturnCallIndirect { .. } => {
+                std::vec![Self::trace_call_indirect_params(pre).1]
+            }
+            Instruction::TableGet { index, .. } => {
+                std::vec![Self::trace_read(pre, index, ValType::I32)]
+            }
+            Instruction::TableGetImm { index, .. } => {
+                std::vec![IVal::imm(Val::I32(u32::from(index) as i32))]
+            }
+            Instruction::TableSet { index, value } => {
+                let (_, element) = self.trace_table(pre);
+                std::vec![
+                    Self::trace_read(pre, index, ValType::I32),
+                    Self::trace_read(pre, value, element),
+                ]
+            }
+            Instruction::TableSetAt { index, value } => {
+                let (_, element) = self.trace_table(pre);
+                std::vec![
+                    IVal::imm(Val::I32(u32::from(index) as i32)),
+                    Self::trace_read(pre, value, element),
+                ]
+            }
+            // Integer divisions trap on a zero divisor and `div_s` also on `MIN / -1`.
+            Instruction::I32DivS(instr)
+            | Instruction::I32DivU(instr)
+            | Instruction::I32RemS(instr)
+            | Instruction::I32RemU(instr) => {
+                let (left, right, _) = Self::trace_binary(pre, instr, ValType::I32);
+                std::vec![left, right]
+            }
+            Instruction::I64DivS(instr)
+            | Instruction::I64DivU(instr)
+            | Instruction::I64RemS(instr)
+            | Instruction::I64RemU(instr) => {
+                let (left, right, _) = Self::trace_binary(pre, instr, ValType::I64);
+                std::vec![left, right]
+            }
+            Instruction::I32DivSImm16(instr) => std::vec![
+                Self::trace_read(pre, instr.reg_in, ValType::I32),
+                IVal::imm(Val::I32(NonZeroI32::from(instr.imm_in).get())),
+            ],
+            Instruction::I64DivSImm16(instr) => std::vec![
+                Self::trace_read(pre, instr.reg_in, ValType::I64),
+                IVal::imm(Val::I64(NonZeroI64::from(instr.imm_in).get())),
+            ],
+            Instruction::I32DivSImm16Rev(instr) | Instruction::I32RemSImm16Rev(instr) => std::vec![
+                IVal::imm(Val::I32(i32::from(instr.imm_in))),
+                Self::trace_read(pre, instr.reg_in, ValType::I32),
+            ],
+            Instruction::I32DivUImm16Rev(instr) | Instruction::I32RemUImm16Rev(instr) => std::vec![
+                IVal::imm(Val::I32(u32::from(instr.imm_in) as i32)),
+                Self::trace_read(pre, instr.reg_in, ValType::I32),
+            ],
+            Instruction::I64DivSImm16Rev(instr) | Instruction::I64RemSImm16Rev(instr) => std::vec![
+                IVal::imm(Val::I64(i64::from(instr.imm_in))),
+                Self::trace_read(pre, instr.reg_in, ValType::I64),
+            ],
+            Instruction::I64DivUImm16Rev(instr) | Instruction::I64RemUImm16Rev(instr) => std::vec![
+                IVal::imm(Val::I64(u64::from(instr.imm_in) as i64)),
+                Self::trace_read(pre, instr.reg_in, ValType::I64),
+            ],
+            _ => match Self::trace_memory_init_operands(pre, instruction) {
+                Some(operands) => operands.to_vec(),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// Returns the words of the passive data segment the trapping `instruction` read.
+    ///
+    /// A `memory.init` checks its destination range before its source range.
+    /// If the destination fits into the memory but the source range overruns
+    /// the data segment, the words of the source range within the segment are read.
+    ///
+    /// Returns no words if the data segment is not recorded by the `tracer`.
+    fn trace_trap_data(
+        &mut self,
+        pre: &PreStep,
+        instruction: &Instruction,
+        tracer: &Tracer,
+    ) -> Vec<MemoryWord> {
+        let Some([dst, src, len]) = Self::trace_memory_init_operands(pre, instruction) else {
+            return Vec::new();
+        };
+        let mut addr = pre.ip;
+        addr.add(1);
+        let segment_index = match *addr.get() {
+            Instruction::DataSegmentIdx(segment_index) => segment_index.to_u32(),
+            _ => unreachable!("expected an Instruction::DataSegmentIdx instruction word"),
+        };
+        let Some(base) = tracer.passive_data_address(segment_index) else {
+            return Vec::new();
+        };
+        let [dst, src, len] = [dst, src, len].map(|operand| {
+            u64::from(operand.val.i32().expect("memory.init operands must be i32") as u32)
+        });
+        if dst + len > u64::from(pre.pages) * 65536 {
+            return Vec::new();
+        }
+        let segment = self.cache.get_data_segment(self.ctx, segment_index);
+        let bytes = self.ctx.resolve_data_segment_mut(&segment).bytes();
+        let end = (src + len).min(bytes.len() as u64);
+        if src >= end {
+            return Vec::new();
+        }
+        // The recorded words of the segment start at its `Data` address `base`.
+        (src / 8..=(end - 1) / 8)
+            .map(|word| {
+                let start = word as usize * 8;
+                let chunk = &bytes[start..bytes.len().min(start + 8)];
+                let mut value = [0x00_u8; 8];
+                value[..chunk.len()].copy_from_slice(chunk);
+                MemoryWord {
+                    index: base as usize + word as usize,
+                    value: u64::from_le_bytes(value),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the `dst`, `src` and `len` operands of a `memory.init` `instruction`.
+    ///
+    /// Returns `None` if `instruction` is not a `memory.init` instruction.
+    fn trace_memory_init_operands(pre: &PreStep, instruction: &Instruction) -> Option<[IVal; 3]> {
+        let reg = |register: Register| Self::trace_read(pre, register, ValType::I32);
+        let imm = |value: Const16<u32>| IVal::imm(Val::I32(u32::from(value) as i32));
+        let operands = match *instruction {
+            Instruction::MemoryInit { dst, src, len } => [reg(dst), reg(src), reg(len)],
+            Instruction::MemoryInitTo { dst, src, len } => [imm(dst), reg(src), reg(len)],
+            Instruction::MemoryInitFrom { dst, src, len } => [reg(dst), imm(src), reg(len)],
+            Instruction::MemoryInitFromTo { dst, src, len } => [imm(dst), imm(src), reg(len)],
+            Instruction::MemoryInitExact { dst, src, len } => [reg(dst), reg(src), imm(len)],
+            Instruction::MemoryInitToExact { dst, src, len } => [imm(dst), reg(src), imm(len)],
+            Instruction::MemoryInitFromExact { dst, src, len } => [reg(dst), imm(src), imm(len)],
+            Instruction::MemoryInitFromToExact { dst, src, len } => [imm(dst), imm(src), imm(len)],
+            _ => return None,
+        };
+        Some(operands)
+    }
+
+    /// Returns the [`IVal`] of the `register` read by the executed instruction interpreted as `ty`.
+    fn trace_read(pre: &PreStep, register: Register, ty: ValType) -> IVal {
+        let addr = Self::trace_stack_addr(pre, register);
+        IVal::stack(pre.register(addr).with_type(ty), addr)
+    }
+
+    /// Returns the [`IVal`] of the `register` read by the executed instruction
+    /// whose value type is unknown.
+    ///
+    /// # Note
+    ///
+    /// Registers are untyped, so their raw 64-bit contents are traced as [`Val::I64`].
+    fn trace_untyped_read(pre: &PreStep, register: Register) -> IVal {
+        let addr = Self::trace_stack_addr(pre, register);
+        IVal::stack(Val::I64(i64::from(pre.register(addr))), addr)
+    }
+
+    /// Returns the [`IVal`] of the `register` written by the executed instruction interpreted as `ty`.
+    fn trace_write(pre: &PreStep, register: Register, ty: ValType) -> IVal {
+        // Safety: the register has just been written by the executed instruction.
+        let val = unsafe { pre.sp.get(register) };
+        IVal::stack(val.with_type(ty), Self::trace_stack_addr(pre, register))
+    }
+
+    /// Returns the value stack address of the `register` of the traced function frame.
+    ///
+    /// # Note
+    ///
+    /// Function local constants are stored in front of the frame's base offset
+    /// and are accessed via negative [`Register`] indices.
+    fn trace_stack_addr(pre: &PreStep, register: Register) -> usize {
+        pre.base
+            .checked_add_signed(isize::from(register.to_i16()))
+            .expect("register must be within the bounds of the value stack")
+    }
+
+    /// Returns the [`StepInfo::Load`] of the load `instruction` at `address + offset`
+    /// into the `result` register.
+    fn trace_load(
+        &mut self,
+        pre: &PreStep,
+        instruction: &Instruction,
+        result: Register,
+        address: IVal,
+        offset: u32,
+    ) -> StepInfo {
+        let (ty, width, signed) = Self::trace_load_kind(instruction);
+        let base = address.val.i32().expect("load address must be an i32") as u32;
+        let effective_address = u64::from(base) + u64::from(offset);
+        StepInfo::Load {
+            ty,
+            width,
+            signed,
+            address,
+            offset,
+            effective_address,
+            value: Self::trace_write(pre, result, ty),
+            words: self.trace_memory_words(effective_address, width),
+        }
+    }
+
+    /// Returns the result type, the number of loaded bytes and
+    /// the signedness of the load `instruction`.
+    fn trace_load_kind(instruction: &Instruction) -> (ValType, u32, bool) {
+        match instruction {
+            Instruction::I32Load(_)
+            | Instruction::I32LoadAt(_)
+            | Instruction::I32LoadOffset16(_) => (ValType::I32, 4, false),
+            Instruction::I64Load(_)
+            | Instruction::I64LoadAt(_)
+            | Instruction::I64LoadOffset16(_) => (ValType::I64, 8, false),
+            Instruction::F32Load(_)
+            | Instruction::F32LoadAt(_)
+            | Instruction::F32LoadOffset16(_) => (ValType::F32, 4, false),
+            Instruction::F64Load(_)
+            | Instruction::F64LoadAt(_)
+            | Instruction::F64LoadOffset16(_) => (ValType::F64, 8, false),
+            Instruction::I32Load8s(_)
+            | Instruction::I32Load8sAt(_)
+            | Instruction::I32Load8sOffset16(_) => (ValType::I32, 1, true),
+            Instruction::I32Load8u(_)
+            | Instruction::I32Load8uAt(_)
+            | Instruction::I32Load8uOffset16(_) => (ValType::I32, 1, false),
+            Instruction::I32Load16s(_)
+            | Instruction::I32Load16sAt(_)
+            | Instruction::I32Load16sOffset16(_) => (ValType::I32, 2, true),
+            Instruction::I32Load16u(_)
+            | Instruction::I32Load16uAt(_)
+            | Instruction::I32Load16uOffset16(_) => (ValType::I32, 2, false),
+            Instruction::I64Load8s(_)
+            | Instruction::I64Load8sAt(_)
+            | Instruction::I64Load8sOffset16(_) => (ValType::I64, 1, true),
+            Instruction::I64Load8u(_)
+            | Instruction::I64Load8uAt(_)
+            | Instruction::I64Load8uOffset16(_) => (ValType::I64, 1, false),
+            Instruction::I64Load16s(_)
+            | Instruction::I64Load16sAt(_)
+            | Instruction::I64Load16sOffset16(_) => (ValType::I64, 2, true),
+            Instruction::I64Load16u(_)
+            | Instruction::I64Load16uAt(_)
+            | Instruction::I64Load16uOffset16(_) => (ValType::I64, 2, false),
+            Instruction::I64Load32s(_)
+            | Instruction::I64Load32sAt(_)
+            | Instruction::I64Load32sOffset16(_) => (ValType::I64, 4, true),
+            Instruction::I64Load32u(_)
+            | Instruction::I64Load32uAt(_)
+            | Instruction::I64Load32uOffset16(_) => (ValType::I64, 4, false),
+            _ => unreachable!("expected a load instruction but found: {instruction:?}"),
+        }
+    }
+
+    /// Returns the [`StepInfo::Store`] of the store `instruction` of `value`
+    /// at `address + offset`.
+    fn trace_store(
+        &mut self,
+        instruction: &Instruction,
+        address: IVal,
+        offset: u32,
+        value: IVal,
+    ) -> StepInfo {
+        let (ty, width) = Self::trace_store_kind(instruction);
+        let base = address.val.i32().expect("store address must be an i32") as u32;
+        let effective_address = u64::from(base) + u64::from(offset);
+        StepInfo::Store {
+            ty,
+            width,
+            address,
+            offset,
+            effective_address,
+            value,
+            words: self.trace_memory_words(effective_address, width),
+        }
+    }
+
+    /// Returns the [`IVal`] of the value register encoded in the [`Instruction::Register`]
+    /// following the traced store `instruction`.
+    fn trace_store_value(pre: &PreStep, instruction: &Instruction) -> IVal {
+        let (ty, _) = Self::trace_store_kind(instruction);
+        let mut addr = pre.ip;
+        addr.add(1);
+        match *addr.get() {
+            Instruction::Register(register) => Self::trace_read(pre, register, ty),
+            _ => unreachable!("expected an Instruction::Register instruction word"),
+        }
+    }
+
+    /// Returns the immediate value `imm` of the store `instruction` as an [`IVal`].
+    ///
+    /// The immediate is sign extended to the type of the stored value.
+    fn trace_store_imm(instruction: &Instruction, imm: i64) -> IVal {
+        match Self::trace_store_kind(instruction) {
+            (ValType::I32, _) => IVal::imm(Val::I32(imm as i32)),
+            _ => IVal::imm(Val::I64(imm)),
+        }
+    }
+
+    /// Returns the type of the stored value and the number of stored bytes
+    /// of the store `instruction`.
+    fn trace_store_kind(instruction: &Instruction) -> (ValType, u32) {
+        match instruction {
+            Instruction::I32Store(_)
+            | Instruction::I32StoreOffset16(_)
+            | Instruction::I32StoreOffset16Imm16(_)
+            | Instruction::I32StoreAt(_)
+            | Instruction::I32StoreAtImm16(_) => (ValType::I32, 4),
+            Instruction::I64Store(_)
+            | Instruction::I64StoreOffset16(_)
+            | Instruction::I64StoreOffset16Imm16(_)
+            | Instruction::I64StoreAt(_)
+            | Instruction::I64StoreAtImm16(_) => (ValType::I64, 8),
+            Instruction::F32Store(_)
+            | Instruction::F32StoreOffset16(_)
+            | Instruction::F32StoreAt(_) => (ValType::F32, 4),
+            Instruction::F64Store(_)
+            | Instruction::F64StoreOffset16(_)
+            | Instruction::F64StoreAt(_) => (ValType::F64, 8),
+            Instruction::I32Store8(_)
+            | Instruction::I32Store8Offset16(_)
+            | Instruction::I32Store8Offset16Imm(_)
+            | Instruction::I32Store8At(_)
+            | Instruction::I32Store8AtImm(_) => (ValType::I32, 1),
+            Instruction::I32Store16(_)
+            | Instruction::I32Store16Offset16(_)
+            | Instruction::I32Store16Offset16Imm(_)
+            | Instruction::I32Store16At(_)
+            | Instruction::I32Store16AtImm(_) => (ValType::I32, 2),
+            Instruction::I64Store8(_)
+            | Instruction::I64Store8Offset16(_)
+            | Instruction::I64Store8Offset16Imm(_)
+            | Instruction::I64Store8At(_)
+            | Instruction::I64Store8AtImm(_) => (ValType::I64, 1),
+            Instruction::I64Store16(_)
+            | Instruction::I64Store16Offset16(_)
+            | Instruction::I64Store16Offset16Imm(_)
+            | Instruction::I64Store16At(_)
+            | Instruction::I64Store16AtImm(_) => (ValType::I64, 2),
+            Instruction::I64Store32(_)
+            | Instruction::I64Store32Offset16(_)
+            | Instruction::I64Store32Offset16Imm16(_)
+            | Instruction::I64Store32At(_)
+            | Instruction::I64Store32AtImm16(_) => (ValType::I64, 4),
+            _ => unreachable!("expected a store instruction but found: {instruction:?}"),
+        }
+    }
+
+    /// Returns the 64-bit words of the default linear memory overlapping `width` bytes at `address`.
+    ///
+    /// # Note
+    ///
+    /// This must only be called for successfully accessed memory regions.
+    fn trace_memory_words(&mut self, address: u64, width: u32) -> Vec<MemoryWord> {
+        let memory = self.cache.default_memory_bytes(self.ctx);
+        let first = address / 8;
+        let last = (address + u64::from(width) - 1) / 8;
+        (first..=last)
+            .map(|index| {
+                let index =
+                    usize::try_from(index).expect("accessed memory word must be addressable");
+                let mut bytes = [0x00_u8; 8];
+                bytes.copy_from_slice(&memory[index * 8..index * 8 + 8]);
+                MemoryWord {
+                    index,
+                    value: u64::from_le_bytes(bytes),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the 32-bit address offset stored in the [`Instruction::Const32`] following the traced instruction.
+    fn trace_address_offset(pre: &PreStep) -> u32 {
+        let mut addr = pre.ip;
+        addr.add(1);
+        match *addr.get() {
+            Instruction::Const32(value) => u32::from(value),
+            _ => unreachable!("expected an Instruction::Const32 instruction word"),
+        }
+    }
+
+    /// Returns the module local index, the store address, the value type and
+    /// the mutability of the `global`.
+    fn trace_global(&mut self, global: GlobalIdx) -> (u32, usize, ValType, bool) {
+        let global_index = global.to_u32();
+        let global = self
+            .ctx
+            .resolve_instance(self.cache.instance())
+            .get_global(global_index)
+            .expect("accessed global must exist in the executing instance");
+        let address = self.ctx.global_address(&global);
+        let ty = self.ctx.resolve_global(&global).ty();
+        (
+            global_index,
+            address,
+            ty.content(),
+            ty.mutability().is_mut(),
+        )
+    }
+
+    /// Returns the index and element type of the table accessed by a table instruction.
+    ///
+    /// # Note
+    ///
+    /// Table instructions store their [`TableIdx`] in the instruction word that follows them.
+    fn trace_table(&mut self, pre: &PreStep) -> (u32, ValType) {
+        let mut addr = pre.ip;
+        addr.add(1);
+        let table_index: TableIdx = match *addr.get() {
+            Instruction::TableIdx(table_index) => table_index,
+            _ => unreachable!("expected an Instruction::TableIdx instruction word"),
+        };
+        let table = self.cache.get_table(self.ctx, table_index);
+        let element = self.ctx.resolve_table(&table).ty().element();
+        (table_index.to_u32(), element)
+    }
+}
+
+/// The store state of an [`Instance`] that a called host function may mutate.
+///
+/// Host functions are not instrumented, so their mutations of the default
+/// linear memory and the globals of the calling instance are recovered by
+/// diffing against a snapshot taken before the call.
+#[derive(Debug)]
+pub struct HostSnapshot {
+    /// The number of pages of the default linear memory.
+    pages: u32,
+    /// The bytes of the default linear memory.
+    memory: Vec<u8>,
+    /// The store addresses and raw values of the globals.
+    globals: Vec<(usize, u64)>,
+}
+
+impl HostSnapshot {
+    /// Captures the store state of `instance` before calling a host function.
+    pub fn new(store: &StoreInner, instance: &Instance) -> Self {
+        let (pages, memory) = Self::memory(store, instance);
+        let memory = memory.to_vec();
+        let globals = Self::globals(store, instance)
+            .map(|(address, global)| {
+                (
+                    address,
+                    store.resolve_global(&global).get_untyped().to_bits(),
+                )
+            })
+            .collect();
+        Self {
+            pages,
+            memory,
+            globals,
+        }
+    }
+
+    /// Records the mutations of the host function call since the snapshot was taken.
+    ///
+    /// Words of grown memory pages are compared against their zero initialization.
+    pub fn push_mutations(self, store: &StoreInner, instance: &Instance, tracer: &mut Tracer) {
+        let (pages, memory) = Self::memory(store, instance);
+        if pages > self.pages {
+            tracer.push_grown_memory(self.pages, pages);
+        }
+        let words: Vec<MemoryWord> = memory
+            .chunks_exact(8)
+            .enumerate()
+            .filter_map(|(index, bytes)| {
+                let old = self
+                    .memory
+                    .get(index * 8..index * 8 + 8)
+                    .unwrap_or(&[0x00; 8]);
+                (old != bytes).then(|| MemoryWord {
+                    index,
+                    value: u64::from_le_bytes(bytes.try_into().expect("word must have 8 bytes")),
+                })
+            })
+            .collect();
+        let globals: Vec<(usize, Val)> = Self::globals(store, instance)
+            .zip(self.globals)
+            .filter_map(|((address, global), (_, old))| {
+                let global = store.resolve_global(&global);
+                (global.get_untyped().to_bits() != old).then(|| (address, global.get()))
+            })
+            .collect();
+        if words.is_empty() && globals.is_empty() {
+            return;
+        }
+        let (func_index, pc) = tracer.etable.last_location();
+        tracer.push_step(
+            func_index,
+            pc,
+            pages,
+            StepInfo::HostMutation { words, globals },
+        );
+    }
+
+    /// Returns the number of pages and the bytes of the default linear memory of `instance`.
+    ///
+    /// Returns no pages and bytes if `instance` has no linear memory.
+    fn memory<'a>(store: &'a StoreInner, instance: &Instance) -> (u32, &'a [u8]) {
+        match store
+            .resolve_instance(instance)
+            .get_memory(DEFAULT_MEMORY_INDEX)
+        {
+            Some(memory) => {
+                let memory = store.resolve_memory(&memory);
+                (u32::from(memory.current_pages()), memory.data())
+            }
+            None => (0, &[]),
+        }
+    }
+
+    /// Returns the store addresses and handles of the globals of `instance`.
+    fn globals<'a>(
+        store: &'a StoreInner,
+        instance: &'a Instance,
+    ) -> impl Iterator<Item = (usize, Global)> + 'a {
+        let entity = store.resolve_instance(instance);
+        (0..)
+            .map_while(|index| entity.get_global(index))
+            .map(|global| (store.global_address(&global), global))
+    }
+}
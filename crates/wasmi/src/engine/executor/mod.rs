@@ -1,6 +1,6 @@
-pub(crate) use self::stack::Stack;
+pub(crate) use self::{instrs::CallKind, stack::Stack};
 use self::{
-    instrs::{execute_instrs, execute_instrs_with_trace, CallKind, WasmOutcome},
+    instrs::{execute_instrs, execute_instrs_with_trace, HostSnapshot, WasmOutcome},
     stack::CallFrame,
     trap::TaggedTrap,
 };
@@ -25,11 +25,9 @@ use crate::{
     FuncEntity,
     Instance,
     StoreContextMut,
-    Tracer,
+    TracerHandle,
 };
 
-use std::{cell::RefCell, rc::Rc};
-
 #[cfg(doc)]
 use crate::{engine::StackLimits, Store};
 
@@ -77,7 +75,7 @@ impl EngineInner {
         func: &Func,
         params: impl CallParams,
         results: Results,
-        tracer: Rc<RefCell<Tracer>>,
+        tracer: TracerHandle,
     ) -> Result<<Results as CallResults>::Results, Error>
     where
         Results: CallResults,
@@ -298,7 +296,7 @@ impl<'engine> EngineExecutor<'engine> {
         func: &Func,
         params: impl CallParams,
         results: Results,
-        tracer: Rc<RefCell<Tracer>>,
+        tracer: TracerHandle,
     ) -> Result<<Results as CallResults>::Results, TaggedTrap>
     where
         Results: CallResults,
@@ -313,7 +311,7 @@ impl<'engine> EngineExecutor<'engine> {
                 unsafe { self.stack.values.extend_zeros(len_results) };
                 let instance = *wasm_func.instance();
                 let compiled_func = wasm_func.func_body();
-                let ctx = ctx.as_context_mut();
+                let mut ctx = ctx.as_context_mut();
                 let compiled_func = self
                     .res
                     .code_map
@@ -332,7 +330,13 @@ impl<'engine> EngineExecutor<'engine> {
                     RegisterSpan::new(Register::from_i16(0)),
                     instance,
                 ))?;
-                self.execute_func_with_trace(ctx, tracer)?;
+                if tracer.lock().is_output_snapshot_mode() {
+                    // Only the final state is traced, so the function executes untraced.
+                    self.execute_func(ctx.as_context_mut())?;
+                    tracer.lock().push_final_state(&instance, ctx);
+                } else {
+                    self.execute_func_with_trace(ctx, tracer)?;
+                }
             }
             // TODO: implement host call trace
             FuncEntity::Host(host_func) => {
@@ -446,7 +450,7 @@ impl<'engine> EngineExecutor<'engine> {
     fn execute_func_with_trace<T>(
         &mut self,
         mut ctx: StoreContextMut<T>,
-        tracer: Rc<RefCell<Tracer>>,
+        tracer: TracerHandle,
     ) -> Result<(), TaggedTrap> {
         let mut cache = self
             .stack
@@ -477,7 +481,13 @@ impl<'engine> EngineExecutor<'engine> {
                         .peek()
                         .expect("caller must be on the stack")
                         .instance();
+                    let snapshot = HostSnapshot::new(&ctx.as_context().store.inner, &instance);
                     self.execute_host_func(&mut ctx, results, host_func, &instance, call_kind)?;
+                    snapshot.push_mutations(
+                        &ctx.as_context().store.inner,
+                        &instance,
+                        &mut tracer.lock(),
+                    );
                 }
             }
         }
@@ -683,7 +693,7 @@ impl<'engine> EngineExecutor<'engine> {
         &mut self,
         ctx: StoreContextMut<T>,
         cache: &mut InstanceCache,
-        tracer: Rc<RefCell<Tracer>>,
+        tracer: TracerHandle,
     ) -> Result<WasmOutcome, Error> {
         let (store_inner, mut resource_limiter) = ctx.store.store_inner_and_resource_limiter_ref();
         let value_stack = &mut self.stack.values;
@@ -141,7 +141,7 @@ impl ValueStack {
     }
 
     /// Returns the current length of the [`ValueStack`].
-    fn len(&self) -> usize {
+    pub fn len(&self) -> usize {
         debug_assert!(self.values.len() <= self.max_len);
         self.values.len()
     }
@@ -437,8 +437,4 @@ impl FrameRegisters {
     unsafe fn register_offset(&self, register: Register) -> *mut UntypedVal {
         unsafe { self.ptr.offset(register.to_i16() as isize) }
     }
-
-    pub unsafe fn get_addr(&self, register: Register) -> usize {
-        self.register_offset(register) as usize
-    }
 }
@@ -21,6 +21,7 @@ use self::bytecode::RegisterSpan;
 
 pub(crate) use self::{
     block_type::BlockType,
+    code_map::CodeMap,
     config::FuelCosts,
     executor::Stack,
     func_args::{FuncFinished, FuncParams, FuncResults},
@@ -31,6 +32,7 @@ pub(crate) use self::{
         FuncTranslatorAllocations,
         LazyFuncTranslator,
         ValidatingFuncTranslator,
+        VisitInputRegisters,
         WasmTranslator,
     },
 };
@@ -43,7 +45,7 @@ pub use self::{
     translator::{Instr, TranslationError},
 };
 use self::{
-    code_map::{CodeMap, CompiledFuncEntity},
+    code_map::CompiledFuncEntity,
     func_types::FuncTypeRegistry,
     resumable::ResumableCallBase,
 };
@@ -54,13 +56,11 @@ use crate::{
     Func,
     FuncType,
     StoreContextMut,
-    Tracer,
+    TracerHandle,
 };
 use core::sync::atomic::{AtomicU32, Ordering};
 use spin::{Mutex, RwLock};
 use std::{
-    cell::RefCell,
-    rc::Rc,
     sync::{Arc, Weak},
     vec::Vec,
 };
@@ -419,7 +419,7 @@ impl Engine {
         func: &Func,
         params: impl CallParams,
         results: Results,
-        tracer: Rc<RefCell<Tracer>>,
+        tracer: TracerHandle,
     ) -> Result<<Results as CallResults>::Results, Error>
     where
         Results: CallResults,
@@ -37,6 +37,7 @@ pub use self::{
     error::TranslationError,
     instr_encoder::{Instr, InstrEncoder},
     stack::TypedProvider,
+    visit_register::VisitInputRegisters,
 };
 use super::code_map::CompiledFuncEntity;
 use crate::{
@@ -526,6 +527,7 @@ impl<'parser> WasmTranslator<'parser> for FuncTranslator {
             .instr_encoder
             .update_branch_offsets(&mut self.alloc.stack)?;
         let len_registers = self.alloc.stack.len_registers();
+        let len_locals = self.alloc.stack.len_locals();
         if let Some(fuel_costs) = self.fuel_costs() {
             // Note: Fuel metering is enabled so we need to bump the fuel
             //       of the function enclosing Wasm `block` by an amount
@@ -543,7 +545,12 @@ impl<'parser> WasmTranslator<'parser> for FuncTranslator {
         }
         let func_consts = self.alloc.stack.func_local_consts();
         let instrs = self.alloc.instr_encoder.drain_instrs();
-        finalize(CompiledFuncEntity::new(len_registers, instrs, func_consts));
+        finalize(CompiledFuncEntity::new(
+            len_registers,
+            len_locals,
+            instrs,
+            func_consts,
+        ));
         Ok(self.into_allocations())
     }
 }
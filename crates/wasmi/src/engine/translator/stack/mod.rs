@@ -145,6 +145,11 @@ impl ValueStack {
         self.consts.len_consts() + self.reg_alloc.len_registers()
     }
 
+    /// Returns the number of registers allocated as function parameters or locals.
+    pub fn len_locals(&self) -> u16 {
+        self.reg_alloc.len_locals()
+    }
+
     /// Registers an `amount` of function inputs or local variables.
     ///
     /// # Errors
@@ -12,6 +12,7 @@ use crate::{
     core::{HostError, TrapCode},
     engine::TranslationError,
     module::ReadError,
+    tracer::TraceError,
 };
 use core::{fmt, fmt::Display};
 use std::{boxed::Box, string::String};
@@ -170,6 +171,8 @@ pub enum ErrorKind {
     Translation(TranslationError),
     /// Encountered when an enforced limit is exceeded.
     Limits(EnforcedLimitsError),
+    /// Encountered when a traced execution exceeds a limit of its [`Tracer`](crate::Tracer).
+    Trace(TraceError),
 }
 
 impl ErrorKind {
@@ -235,6 +238,7 @@ impl Display for ErrorKind {
             Self::Wasm(error) => Display::fmt(error, f),
             Self::Translation(error) => Display::fmt(error, f),
             Self::Limits(error) => Display::fmt(error, f),
+            Self::Trace(error) => Display::fmt(error, f),
         }
     }
 }
@@ -265,6 +269,7 @@ impl_from! {
     impl From<FuelError> for Error::Fuel;
     impl From<FuncError> for Error::Func;
     impl From<EnforcedLimitsError> for Error::Limits;
+    impl From<TraceError> for Error::Trace;
 }
 
 /// An error that can occur upon `memory.grow` or `table.grow`.
@@ -22,12 +22,12 @@ use super::{
     StoreContext,
     Stored,
 };
-use crate::{collections::arena::ArenaIndex, engine::ResumableCall, Error, Tracer, Val};
+use crate::{collections::arena::ArenaIndex, engine::ResumableCall, Error, TracerHandle, Val};
 use core::{
     fmt::{self, Debug},
     num::NonZeroU32,
 };
-use std::{boxed::Box, cell::RefCell, rc::Rc, sync::Arc};
+use std::{boxed::Box, sync::Arc};
 
 /// A raw index to a function entity.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -408,10 +408,23 @@ impl Func {
         Ok(())
     }
 
-    /// Calls the Wasm or host function with the given inputs.
+    /// Calls the Wasm or host function with the given inputs and traces its execution.
     ///
     /// The result is written back into the `outputs` buffer.
     ///
+    /// Behaves like [`Func::call`] but records the executed steps into the
+    /// `tracer`. The `tracer` is filled in place during the call and holds the
+    /// trace once the call returns, also if the call returned an [`Error`].
+    ///
+    /// The `tracer` is a [`TracerHandle`] created from an `Rc<RefCell<Tracer>>`
+    /// or, for calls executing on other threads, from an `Arc<Mutex<Tracer>>`.
+    ///
+    /// Calls sharing a `tracer` accumulate into a single trace whose eids
+    /// continue across the calls, so its init memory only needs to be recorded
+    /// before the first call. Use [`Tracer::reset`] to start a new trace instead.
+    ///
+    /// [`Tracer::reset`]: crate::Tracer::reset
+    ///
     /// # Errors
     ///
     /// - If the function returned a [`Error`].
@@ -426,9 +439,11 @@ impl Func {
         mut ctx: impl AsContextMut<Data = T>,
         inputs: &[Val],
         outputs: &mut [Val],
-        tracer: Rc<RefCell<Tracer>>,
+        tracer: impl Into<TracerHandle>,
     ) -> Result<(), Error> {
         self.verify_and_prepare_inputs_outputs(ctx.as_context(), inputs, outputs)?;
+        let tracer = tracer.into();
+        tracer.lock().set_root_call(inputs, outputs);
         // Note: Cloning an [`Engine`] is intentionally a cheap operation.
         ctx.as_context()
             .store
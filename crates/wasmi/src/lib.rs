@@ -126,6 +126,8 @@ pub mod errors {
     };
 }
 
+#[cfg(feature = "fuzzing")]
+pub use self::tracer::fuzzing;
 pub use self::{
     engine::{
         CompilationMode,
@@ -170,7 +172,21 @@ pub use self::{
     },
     store::{AsContext, AsContextMut, Store, StoreContext, StoreContextMut},
     table::{Table, TableType},
-    tracer::{etable, imtable, mtable, Tracer},
+    tracer::{
+        compact,
+        etable,
+        format,
+        imtable,
+        itable,
+        mtable,
+        CallbackSink,
+        TraceConfig,
+        TraceError,
+        TraceSink,
+        Tracer,
+        TracerHandle,
+        VecSink,
+    },
     value::Val,
 };
 use self::{
@@ -32,6 +32,7 @@ use std::{boxed::Box, collections::BTreeMap, sync::Arc, vec::Vec};
 pub struct ModuleBuilder {
     pub header: ModuleHeader,
     pub data_segments: Vec<DataSegment>,
+    pub func_names: BTreeMap<u32, Box<str>>,
 }
 
 /// A builder for a WebAssembly [`Module`] header.
@@ -135,6 +136,7 @@ impl ModuleBuilder {
         Self {
             header,
             data_segments: Vec::new(),
+            func_names: BTreeMap::new(),
         }
     }
 }
@@ -397,6 +399,7 @@ impl ModuleBuilder {
             engine: engine.clone(),
             header: self.header,
             data_segments: self.data_segments.into(),
+            func_names: self.func_names,
         }
     }
 }
@@ -51,6 +51,8 @@ pub struct Module {
     engine: Engine,
     header: ModuleHeader,
     data_segments: Box<[DataSegment]>,
+    /// The function names of the `name` custom section if any.
+    func_names: BTreeMap<u32, Box<str>>,
 }
 
 /// A parsed and validated WebAssembly module header.
@@ -285,6 +287,14 @@ impl Module {
     pub(crate) fn len_globals(&self) -> usize {
         self.header.inner.globals.len()
     }
+    /// Returns the element segments of the [`Module`].
+    pub(crate) fn element_segments(&self) -> &[ElementSegment] {
+        &self.header.inner.element_segments[..]
+    }
+    /// Returns the data segments of the [`Module`].
+    pub(crate) fn data_segments(&self) -> &[DataSegment] {
+        &self.data_segments[..]
+    }
 
     /// Returns a slice to the function types of the [`Module`].
     ///
@@ -376,6 +386,20 @@ impl Module {
         Some(ty)
     }
 
+    /// Returns the name of the function at `func_idx` as defined by the `name` custom section.
+    ///
+    /// Returns `None` if the [`Module`] has no `name` custom section or it does not name the function.
+    pub fn get_func_name(&self, func_idx: u32) -> Option<&str> {
+        self.func_names.get(&func_idx).map(AsRef::as_ref)
+    }
+
+    /// Returns an iterator over the function indices and names defined by the `name` custom section.
+    pub fn func_names(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.func_names
+            .iter()
+            .map(|(func_idx, name)| (*func_idx, name.as_ref()))
+    }
+
     /// Returns the [`ExternType`] for a given [`ExternIdx`].
     ///
     /// # Note
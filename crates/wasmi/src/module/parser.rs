@@ -19,10 +19,11 @@ use crate::{
     MemoryType,
     TableType,
 };
-use core::ops::Range;
-use std::{boxed::Box, vec::Vec};
+use core::{mem, ops::Range};
+use std::{boxed::Box, collections::BTreeMap, vec::Vec};
 use wasmparser::{
     Chunk,
+    CustomSectionReader,
     DataSectionReader,
     ElementSectionReader,
     Encoding,
@@ -32,6 +33,8 @@ use wasmparser::{
     GlobalSectionReader,
     ImportSectionReader,
     MemorySectionReader,
+    Name,
+    NameSectionReader,
     Parser as WasmParser,
     Payload,
     TableSectionReader,
@@ -75,6 +78,8 @@ pub struct ModuleParser {
     compiled_funcs: u32,
     /// Flag, `true` when `stream` is at the end.
     eof: bool,
+    /// The function names of the `name` custom section.
+    func_names: BTreeMap<u32, Box<str>>,
 }
 
 /// The mode of Wasm validation when parsing a Wasm module.
@@ -97,6 +102,7 @@ impl ModuleParser {
             parser,
             compiled_funcs: 0,
             eof: false,
+            func_names: BTreeMap::new(),
         }
     }
 
@@ -208,7 +214,10 @@ impl ModuleParser {
                         }
                         Payload::DataSection(_) => break,
                         Payload::End(_) => break,
-                        Payload::CustomSection { .. } => Ok(()),
+                        Payload::CustomSection(reader) => {
+                            self.process_custom(reader);
+                            Ok(())
+                        }
                         Payload::UnknownSection { id, range, .. } => {
                             self.process_unknown(id, range)
                         }
@@ -257,7 +266,7 @@ impl ModuleParser {
                             let bytes = &buffer[start..consumed];
                             self.process_code_entry(func_body, validation_mode, bytes, &header)?;
                         }
-                        Payload::CustomSection { .. } => {}
+                        Payload::CustomSection(reader) => self.process_custom(reader),
                         Payload::UnknownSection { id, range, .. } => {
                             self.process_unknown(id, range)?
                         }
@@ -292,7 +301,7 @@ impl ModuleParser {
                             buffer.drain(..consumed);
                             break;
                         }
-                        Payload::CustomSection { .. } => {}
+                        Payload::CustomSection(reader) => self.process_custom(reader),
                         Payload::UnknownSection { id, range, .. } => {
                             self.process_unknown(id, range)?
                         }
@@ -305,6 +314,7 @@ impl ModuleParser {
                 }
             }
         }
+        builder.func_names = mem::take(&mut self.func_names);
         Ok(builder.finish(&self.engine))
     }
 
@@ -329,6 +339,27 @@ impl ModuleParser {
         Ok(reached_end)
     }
 
+    /// Processes a Wasm custom section.
+    ///
+    /// # Note
+    ///
+    /// Only the function names of the `name` custom section are extracted.
+    /// Malformed custom sections are ignored since they must not affect validation.
+    fn process_custom(&mut self, section: CustomSectionReader) {
+        if section.name() != "name" {
+            return;
+        }
+        let names = NameSectionReader::new(section.data(), section.data_offset());
+        for name in names {
+            let Ok(Name::Function(names)) = name else {
+                continue;
+            };
+            for naming in names.into_iter().flatten() {
+                self.func_names.insert(naming.index, naming.name.into());
+            }
+        }
+    }
+
     /// Processes the end of the Wasm binary.
     fn process_end(&mut self, offset: usize) -> Result<(), Error> {
         self.validator.end(offset)?;
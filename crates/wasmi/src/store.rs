@@ -531,6 +531,20 @@ impl StoreInner {
         self.engine.resolve_func_type(func_type, f)
     }
 
+    /// Returns the index of the [`Global`] within all globals of the [`Store`].
+    ///
+    /// # Note
+    ///
+    /// Unlike module local global indices this identifies the [`Global`]
+    /// independent of the instance that accesses it.
+    ///
+    /// # Panics
+    ///
+    /// If the [`Global`] does not originate from this [`Store`].
+    pub fn global_address(&self, global: &Global) -> usize {
+        self.unwrap_stored(global.as_inner()).into_usize()
+    }
+
     /// Returns a shared reference to the [`GlobalEntity`] associated to the given [`Global`].
     ///
     /// # Panics
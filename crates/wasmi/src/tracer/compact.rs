@@ -0,0 +1,289 @@
+use std::boxed::Box;
+
+use super::etable::{BinOp, FloatUnOp, IVal, RelOp, StepInfo, TestOp, UnOp};
+
+/// The kind of a [`CompactStep::Unary`].
+#[derive(Debug, Copy, Clone)]
+pub enum UnaryStep {
+    /// A [`StepInfo::I32UnOp`].
+    I32(UnOp),
+    /// A [`StepInfo::FloatUnOp`].
+    Float(FloatUnOp),
+    /// A [`StepInfo::TestOp`].
+    Test(TestOp),
+    /// A [`StepInfo::ConvertInt`].
+    ConvertInt { signed: bool },
+    /// A [`StepInfo::I32WrapI64`].
+    I32WrapI64 { discarded_high: Option<u32> },
+    /// A [`StepInfo::I64ExtendI32`].
+    I64ExtendI32 { signed: bool },
+    /// A [`StepInfo::SignExtend`].
+    SignExtend { from_bits: u8, to_bits: u8 },
+    /// A [`StepInfo::MemoryGrow`] from `previous_pages`.
+    MemoryGrow { previous_pages: u32 },
+    /// A [`StepInfo::TableGet`] of the table at `table_index`.
+    TableGet { table_index: u32 },
+}
+
+/// The kind of a [`CompactStep::Binary`].
+#[derive(Debug, Copy, Clone)]
+pub enum BinaryStep {
+    I32(BinOp),
+    I64(BinOp),
+    F32(BinOp),
+    F64(BinOp),
+    /// A [`StepInfo::RelOp`].
+    Rel(RelOp),
+}
+
+/// A [`StepInfo`] with its operands stored by arity.
+///
+/// Steps with a fixed number of operands keep them in a fixed-size array
+/// while all other steps, e.g. calls and returns, are boxed. This keeps a
+/// [`CompactStep`] smaller than a [`StepInfo`] for the most common steps.
+#[derive(Debug, Clone)]
+pub enum CompactStep {
+    /// A step with the operands `[input, result]`.
+    Unary {
+        step: UnaryStep,
+        operands: [IVal; 2],
+    },
+    /// A step with the operands `[left, right, result]`.
+    Binary {
+        step: BinaryStep,
+        operands: [IVal; 3],
+    },
+    /// A step with a variable number of operands.
+    Variadic(Box<StepInfo>),
+}
+
+impl From<StepInfo> for CompactStep {
+    fn from(step: StepInfo) -> Self {
+        match step {
+            StepInfo::I32BinOp {
+                class,
+                left,
+                right,
+                result,
+            } => CompactStep::Binary {
+                step: BinaryStep::I32(class),
+                operands: [left, right, result],
+            },
+            StepInfo::I64BinOp {
+                class,
+                left,
+                right,
+                result,
+            } => CompactStep::Binary {
+                step: BinaryStep::I64(class),
+                operands: [left, right, result],
+            },
+            StepInfo::F32BinOp {
+                class,
+                left,
+                right,
+                result,
+            } => CompactStep::Binary {
+                step: BinaryStep::F32(class),
+                operands: [left, right, result],
+            },
+            StepInfo::F64BinOp {
+                class,
+                left,
+                right,
+                result,
+            } => CompactStep::Binary {
+                step: BinaryStep::F64(class),
+                operands: [left, right, result],
+            },
+            StepInfo::I32UnOp {
+                class,
+                operand,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::I32(class),
+                operands: [operand, result],
+            },
+            StepInfo::FloatUnOp {
+                class,
+                operand,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::Float(class),
+                operands: [operand, result],
+            },
+            StepInfo::RelOp {
+                class,
+                left,
+                right,
+                result,
+            } => CompactStep::Binary {
+                step: BinaryStep::Rel(class),
+                operands: [left, right, result],
+            },
+            StepInfo::TestOp {
+                class,
+                operand,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::Test(class),
+                operands: [operand, result],
+            },
+            StepInfo::ConvertInt {
+                signed,
+                input,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::ConvertInt { signed },
+                operands: [input, result],
+            },
+            StepInfo::I32WrapI64 {
+                input,
+                result,
+                discarded_high,
+            } => CompactStep::Unary {
+                step: UnaryStep::I32WrapI64 { discarded_high },
+                operands: [input, result],
+            },
+            StepInfo::I64ExtendI32 {
+                signed,
+                input,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::I64ExtendI32 { signed },
+                operands: [input, result],
+            },
+            StepInfo::SignExtend {
+                from_bits,
+                to_bits,
+                operand,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::SignExtend { from_bits, to_bits },
+                operands: [operand, result],
+            },
+            StepInfo::MemoryGrow {
+                delta,
+                previous_pages,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::MemoryGrow { previous_pages },
+                operands: [delta, result],
+            },
+            StepInfo::TableGet {
+                table_index,
+                index,
+                result,
+            } => CompactStep::Unary {
+                step: UnaryStep::TableGet { table_index },
+                operands: [index, result],
+            },
+            step => CompactStep::Variadic(Box::new(step)),
+        }
+    }
+}
+
+impl From<CompactStep> for StepInfo {
+    fn from(step: CompactStep) -> Self {
+        match step {
+            CompactStep::Unary {
+                step,
+                operands: [input, result],
+            } => match step {
+                UnaryStep::I32(class) => StepInfo::I32UnOp {
+                    class,
+                    operand: input,
+                    result,
+                },
+                UnaryStep::Float(class) => StepInfo::FloatUnOp {
+                    class,
+                    operand: input,
+                    result,
+                },
+                UnaryStep::Test(class) => StepInfo::TestOp {
+                    class,
+                    operand: input,
+                    result,
+                },
+                UnaryStep::ConvertInt { signed } => StepInfo::ConvertInt {
+                    signed,
+                    input,
+                    result,
+                },
+                UnaryStep::I32WrapI64 { discarded_high } => StepInfo::I32WrapI64 {
+                    input,
+                    result,
+                    discarded_high,
+                },
+                UnaryStep::I64ExtendI32 { signed } => StepInfo::I64ExtendI32 {
+                    signed,
+                    input,
+                    result,
+                },
+                UnaryStep::SignExtend { from_bits, to_bits } => StepInfo::SignExtend {
+                    from_bits,
+                    to_bits,
+                    operand: input,
+                    result,
+                },
+                UnaryStep::MemoryGrow { previous_pages } => StepInfo::MemoryGrow {
+                    delta: input,
+                    previous_pages,
+                    result,
+                },
+                UnaryStep::TableGet { table_index } => StepInfo::TableGet {
+                    table_index,
+                    index: input,
+                    result,
+                },
+            },
+            CompactStep::Binary {
+                step,
+                operands: [left, right, result],
+            } => match step {
+                BinaryStep::I32(class) => StepInfo::I32BinOp {
+                    class,
+                    left,
+                    right,
+                    result,
+                },
+                BinaryStep::I64(class) => StepInfo::I64BinOp {
+                    class,
+                    left,
+                    right,
+                    result,
+                },
+                BinaryStep::F32(class) => StepInfo::F32BinOp {
+                    class,
+                    left,
+                    right,
+                    result,
+                },
+                BinaryStep::F64(class) => StepInfo::F64BinOp {
+                    class,
+                    left,
+                    right,
+                    result,
+                },
+                BinaryStep::Rel(class) => StepInfo::RelOp {
+                    class,
+                    left,
+                    right,
+                    result,
+                },
+            },
+            CompactStep::Variadic(step) => *step,
+        }
+    }
+}
+
+impl CompactStep {
+    /// Returns the operands of the [`CompactStep`] if it has a fixed arity.
+    pub fn operands(&self) -> Option<&[IVal]> {
+        match self {
+            CompactStep::Unary { operands, .. } => Some(operands),
+            CompactStep::Binary { operands, .. } => Some(operands),
+            CompactStep::Variadic(_) => None,
+        }
+    }
+}
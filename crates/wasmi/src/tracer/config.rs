@@ -0,0 +1,288 @@
+use crate::engine::bytecode::Instruction;
+
+/// Selects the categories of steps recorded by a [`Tracer`](super::Tracer).
+///
+/// Instructions of disabled categories execute without capturing their
+/// operands, so that disabled categories add almost no tracing overhead.
+///
+/// # Note
+///
+/// The [`MTable`](super::MTable) of a partial trace misses the accesses of the
+/// skipped steps and thus generally does not pass
+/// [`MTable::validate`](super::MTable::validate).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TraceConfig {
+    /// Whether steps operating only on registers are recorded.
+    ///
+    /// This includes arithmetic, conversions, comparisons, copies and selects.
+    pub record_stack: bool,
+    /// Whether steps accessing linear memories, tables or segments are recorded.
+    pub record_heap: bool,
+    /// Whether `global.get` and `global.set` steps are recorded.
+    pub record_globals: bool,
+    /// Whether branches, calls, returns and traps are recorded.
+    pub record_control_flow: bool,
+}
+
+impl Default for TraceConfig {
+    /// Records steps of every category.
+    fn default() -> Self {
+        Self {
+            record_stack: true,
+            record_heap: true,
+            record_globals: true,
+            record_control_flow: true,
+        }
+    }
+}
+
+impl TraceConfig {
+    /// Returns `true` if the step of the executed `instruction` is recorded.
+    pub(crate) fn records(&self, instruction: &Instruction) -> bool {
+        use Instruction as Instr;
+        match instruction {
+            Instr::Trap(_)
+            | Instr::ConsumeFuel(_)
+            | Instr::Return
+            | Instr::ReturnReg { .. }
+            | Instr::ReturnReg2 { .. }
+            | Instr::ReturnReg3 { .. }
+            | Instr::ReturnImm32 { .. }
+            | Instr::ReturnI64Imm32 { .. }
+            | Instr::ReturnF64Imm32 { .. }
+            | Instr::ReturnSpan { .. }
+            | Instr::ReturnMany { .. }
+            | Instr::ReturnNez { .. }
+            | Instr::ReturnNezReg { .. }
+            | Instr::ReturnNezReg2 { .. }
+            | Instr::ReturnNezImm32 { .. }
+            | Instr::ReturnNezI64Imm32 { .. }
+            | Instr::ReturnNezF64Imm32 { .. }
+            | Instr::ReturnNezSpan { .. }
+            | Instr::ReturnNezMany { .. }
+            | Instr::Branch { .. }
+            | Instr::BranchCmpFallback { .. }
+            | Instr::BranchI32And(_)
+            | Instr::BranchI32AndImm(_)
+            | Instr::BranchI32Or(_)
+            | Instr::BranchI32OrImm(_)
+            | Instr::BranchI32Xor(_)
+            | Instr::BranchI32XorImm(_)
+            | Instr::BranchI32AndEqz(_)
+            | Instr::BranchI32AndEqzImm(_)
+            | Instr::BranchI32OrEqz(_)
+            | Instr::BranchI32OrEqzImm(_)
+            | Instr::BranchI32XorEqz(_)
+            | Instr::BranchI32XorEqzImm(_)
+            | Instr::BranchI32Eq(_)
+            | Instr::BranchI32EqImm(_)
+            | Instr::BranchI32Ne(_)
+            | Instr::BranchI32NeImm(_)
+            | Instr::BranchI32LtS(_)
+            | Instr::BranchI32LtSImm(_)
+            | Instr::BranchI32LtU(_)
+            | Instr::BranchI32LtUImm(_)
+            | Instr::BranchI32LeS(_)
+            | Instr::BranchI32LeSImm(_)
+            | Instr::BranchI32LeU(_)
+            | Instr::BranchI32LeUImm(_)
+            | Instr::BranchI32GtS(_)
+            | Instr::BranchI32GtSImm(_)
+            | Instr::BranchI32GtU(_)
+            | Instr::BranchI32GtUImm(_)
+            | Instr::BranchI32GeS(_)
+            | Instr::BranchI32GeSImm(_)
+            | Instr::BranchI32GeU(_)
+            | Instr::BranchI32GeUImm(_)
+            | Instr::BranchI64Eq(_)
+            | Instr::BranchI64EqImm(_)
+            | Instr::BranchI64Ne(_)
+            | Instr::BranchI64NeImm(_)
+            | Instr::BranchI64LtS(_)
+            | Instr::BranchI64LtSImm(_)
+            | Instr::BranchI64LtU(_)
+            | Instr::BranchI64LtUImm(_)
+            | Instr::BranchI64LeS(_)
+            | Instr::BranchI64LeSImm(_)
+            | Instr::BranchI64LeU(_)
+            | Instr::BranchI64LeUImm(_)
+            | Instr::BranchI64GtS(_)
+            | Instr::BranchI64GtSImm(_)
+            | Instr::BranchI64GtU(_)
+            | Instr::BranchI64GtUImm(_)
+            | Instr::BranchI64GeS(_)
+            | Instr::BranchI64GeSImm(_)
+            | Instr::BranchI64GeU(_)
+            | Instr::BranchI64GeUImm(_)
+            | Instr::BranchF32Eq(_)
+            | Instr::BranchF32Ne(_)
+            | Instr::BranchF32Lt(_)
+            | Instr::BranchF32Le(_)
+            | Instr::BranchF32Gt(_)
+            | Instr::BranchF32Ge(_)
+            | Instr::BranchF64Eq(_)
+            | Instr::BranchF64Ne(_)
+            | Instr::BranchF64Lt(_)
+            | Instr::BranchF64Le(_)
+            | Instr::BranchF64Gt(_)
+            | Instr::BranchF64Ge(_)
+            | Instr::BranchTable { .. }
+            | Instr::ReturnCallInternal0 { .. }
+            | Instr::ReturnCallInternal { .. }
+            | Instr::ReturnCallImported0 { .. }
+            | Instr::ReturnCallImported { .. }
+            | Instr::ReturnCallIndirect0 { .. }
+            | Instr::ReturnCallIndirect { .. }
+            | Instr::CallInternal0 { .. }
+            | Instr::CallInternal { .. }
+            | Instr::CallImported0 { .. }
+            | Instr::CallImported { .. }
+            | Instr::CallIndirect0 { .. }
+            | Instr::CallIndirect { .. } => self.record_control_flow,
+            Instr::GlobalGet { .. }
+            | Instr::GlobalSet { .. }
+            | Instr::GlobalSetI32Imm16 { .. }
+            | Instr::GlobalSetI64Imm16 { .. } => self.record_globals,
+            Instr::I32Load(_)
+            | Instr::I32LoadAt(_)
+            | Instr::I32LoadOffset16(_)
+            | Instr::I64Load(_)
+            | Instr::I64LoadAt(_)
+            | Instr::I64LoadOffset16(_)
+            | Instr::F32Load(_)
+            | Instr::F32LoadAt(_)
+            | Instr::F32LoadOffset16(_)
+            | Instr::F64Load(_)
+            | Instr::F64LoadAt(_)
+            | Instr::F64LoadOffset16(_)
+            | Instr::I32Load8s(_)
+            | Instr::I32Load8sAt(_)
+            | Instr::I32Load8sOffset16(_)
+            | Instr::I32Load8u(_)
+            | Instr::I32Load8uAt(_)
+            | Instr::I32Load8uOffset16(_)
+            | Instr::I32Load16s(_)
+            | Instr::I32Load16sAt(_)
+            | Instr::I32Load16sOffset16(_)
+            | Instr::I32Load16u(_)
+            | Instr::I32Load16uAt(_)
+            | Instr::I32Load16uOffset16(_)
+            | Instr::I64Load8s(_)
+            | Instr::I64Load8sAt(_)
+            | Instr::I64Load8sOffset16(_)
+            | Instr::I64Load8u(_)
+            | Instr::I64Load8uAt(_)
+            | Instr::I64Load8uOffset16(_)
+            | Instr::I64Load16s(_)
+            | Instr::I64Load16sAt(_)
+            | Instr::I64Load16sOffset16(_)
+            | Instr::I64Load16u(_)
+            | Instr::I64Load16uAt(_)
+            | Instr::I64Load16uOffset16(_)
+            | Instr::I64Load32s(_)
+            | Instr::I64Load32sAt(_)
+            | Instr::I64Load32sOffset16(_)
+            | Instr::I64Load32u(_)
+            | Instr::I64Load32uAt(_)
+            | Instr::I64Load32uOffset16(_)
+            | Instr::I32Store(_)
+            | Instr::I32StoreOffset16(_)
+            | Instr::I32StoreOffset16Imm16(_)
+            | Instr::I32StoreAt(_)
+            | Instr::I32StoreAtImm16(_)
+            | Instr::I32Store8(_)
+            | Instr::I32Store8Offset16(_)
+            | Instr::I32Store8Offset16Imm(_)
+            | Instr::I32Store8At(_)
+            | Instr::I32Store8AtImm(_)
+            | Instr::I32Store16(_)
+            | Instr::I32Store16Offset16(_)
+            | Instr::I32Store16Offset16Imm(_)
+            | Instr::I32Store16At(_)
+            | Instr::I32Store16AtImm(_)
+            | Instr::I64Store(_)
+            | Instr::I64StoreOffset16(_)
+            | Instr::I64StoreOffset16Imm16(_)
+            | Instr::I64StoreAt(_)
+            | Instr::I64StoreAtImm16(_)
+            | Instr::I64Store8(_)
+            | Instr::I64Store8Offset16(_)
+            | Instr::I64Store8Offset16Imm(_)
+            | Instr::I64Store8At(_)
+            | Instr::I64Store8AtImm(_)
+            | Instr::I64Store16(_)
+            | Instr::I64Store16Offset16(_)
+            | Instr::I64Store16Offset16Imm(_)
+            | Instr::I64Store16At(_)
+            | Instr::I64Store16AtImm(_)
+            | Instr::I64Store32(_)
+            | Instr::I64Store32Offset16(_)
+            | Instr::I64Store32Offset16Imm16(_)
+            | Instr::I64Store32At(_)
+            | Instr::I64Store32AtImm16(_)
+            | Instr::F32Store(_)
+            | Instr::F32StoreOffset16(_)
+            | Instr::F32StoreAt(_)
+            | Instr::F64Store(_)
+            | Instr::F64StoreOffset16(_)
+            | Instr::F64StoreAt(_)
+            | Instr::TableGet { .. }
+            | Instr::TableGetImm { .. }
+            | Instr::TableSize { .. }
+            | Instr::TableSet { .. }
+            | Instr::TableSetAt { .. }
+            | Instr::TableCopy { .. }
+            | Instr::TableCopyTo { .. }
+            | Instr::TableCopyFrom { .. }
+            | Instr::TableCopyFromTo { .. }
+            | Instr::TableCopyExact { .. }
+            | Instr::TableCopyToExact { .. }
+            | Instr::TableCopyFromExact { .. }
+            | Instr::TableCopyFromToExact { .. }
+            | Instr::TableInit { .. }
+            | Instr::TableInitTo { .. }
+            | Instr::TableInitFrom { .. }
+            | Instr::TableInitFromTo { .. }
+            | Instr::TableInitExact { .. }
+            | Instr::TableInitToExact { .. }
+            | Instr::TableInitFromExact { .. }
+            | Instr::TableInitFromToExact { .. }
+            | Instr::TableFill { .. }
+            | Instr::TableFillAt { .. }
+            | Instr::TableFillExact { .. }
+            | Instr::TableFillAtExact { .. }
+            | Instr::TableGrow { .. }
+            | Instr::TableGrowImm { .. }
+            | Instr::ElemDrop(_)
+            | Instr::DataDrop(_)
+            | Instr::MemorySize { .. }
+            | Instr::MemoryGrow { .. }
+            | Instr::MemoryGrowBy { .. }
+            | Instr::MemoryCopy { .. }
+            | Instr::MemoryCopyTo { .. }
+            | Instr::MemoryCopyFrom { .. }
+            | Instr::MemoryCopyFromTo { .. }
+            | Instr::MemoryCopyExact { .. }
+            | Instr::MemoryCopyToExact { .. }
+            | Instr::MemoryCopyFromExact { .. }
+            | Instr::MemoryCopyFromToExact { .. }
+            | Instr::MemoryFill { .. }
+            | Instr::MemoryFillAt { .. }
+            | Instr::MemoryFillImm { .. }
+            | Instr::MemoryFillExact { .. }
+            | Instr::MemoryFillAtImm { .. }
+            | Instr::MemoryFillAtExact { .. }
+            | Instr::MemoryFillImmExact { .. }
+            | Instr::MemoryFillAtImmExact { .. }
+            | Instr::MemoryInit { .. }
+            | Instr::MemoryInitTo { .. }
+            | Instr::MemoryInitFrom { .. }
+            | Instr::MemoryInitFromTo { .. }
+            | Instr::MemoryInitExact { .. }
+            | Instr::MemoryInitToExact { .. }
+            | Instr::MemoryInitFromExact { .. }
+            | Instr::MemoryInitFromToExact { .. } => self.record_heap,
+            _ => self.record_stack,
+        }
+    }
+}
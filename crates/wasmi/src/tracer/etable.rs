@@ -1,20 +1,101 @@
-use crate::{engine::bytecode::Instruction, Val};
-use core::fmt::{Display, Formatter};
-use std::vec::Vec;
+use crate::{
+    compact::CompactStep,
+    core::{TrapCode, UntypedVal, ValType},
+    engine::{
+        bytecode::{BranchComparator, Instruction},
+        executor::CallKind,
+    },
+    mtable::MemoryOp,
+    TraceSink,
+    Val,
+};
+use core::fmt::{Debug, Display, Formatter, Write};
+use std::{
+    boxed::Box,
+    collections::BTreeMap,
+    format,
+    println,
+    string::String,
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
 
+/// A value read or written by a traced step.
 #[derive(Debug, Clone)]
-pub(crate) struct IVal {
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IVal {
+    #[cfg_attr(feature = "serde", serde(serialize_with = "ser::val::serialize"))]
     pub val: Val,
-    pub addr: usize,
+    /// The stack address of the register holding `val`.
+    ///
+    /// This is `None` for immediate operands encoded within the instruction.
+    pub addr: Option<usize>,
+}
+
+impl IVal {
+    /// Creates an [`IVal`] for a value stored at the stack address `addr`.
+    pub fn stack(val: Val, addr: usize) -> Self {
+        Self {
+            val,
+            addr: Some(addr),
+        }
+    }
+
+    /// Creates an [`IVal`] for an immediate operand.
+    pub fn imm(val: Val) -> Self {
+        Self { val, addr: None }
+    }
+
+    /// Returns the id of the host object referenced by the `externref` held by this [`IVal`].
+    ///
+    /// The id is the 64-bit encoding of the engine's store index of the object,
+    /// so equal ids refer to the same host object. Untyped [`Val::I64`] register
+    /// contents are interpreted as such an encoding. Returns `None` for `null`
+    /// references and values of other types.
+    pub fn externref_id(&self) -> Option<u64> {
+        match &self.val {
+            Val::ExternRef(_) | Val::I64(_) => {
+                Some(UntypedVal::from(self.val.clone()).to_bits()).filter(|bits| *bits != 0)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Display for IVal {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{:?} {:10}", self.val, self.addr)
+        let val = match &self.val {
+            Val::ExternRef(_) => match self.externref_id() {
+                Some(id) => format!("externref#{}", id),
+                None => String::from("externref#null"),
+            },
+            Val::FuncRef(funcref) if funcref.is_null() => String::from("funcref#null"),
+            val => format!("{:?}", val),
+        };
+        match self.addr {
+            Some(addr) => write!(f, "{} {:10}", val, addr),
+            None => write!(f, "{} {:>10}", val, "imm"),
+        }
     }
 }
 
+/// A 64-bit word of linear memory accessed by a traced step.
+///
+/// Linear memory is traced at the word granularity of the [`IMTable`].
+///
+/// [`IMTable`]: super::imtable::IMTable
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemoryWord {
+    /// The index of the word, i.e. its byte address divided by 8.
+    pub index: usize,
+    /// The little-endian value of the word.
+    pub value: u64,
+}
+
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BinOp {
     Add,
     Sub,
@@ -27,6 +108,14 @@ pub enum BinOp {
     UnsignedRem,
     SignedDiv,
     SignedRem,
+    And,
+    Or,
+    Xor,
+    Shl,
+    ShrS,
+    ShrU,
+    Rotl,
+    Rotr,
 }
 
 impl Display for BinOp {
@@ -43,11 +132,149 @@ impl Display for BinOp {
             BinOp::UnsignedRem => write!(f, "urem"),
             BinOp::SignedDiv => write!(f, "sdiv"),
             BinOp::SignedRem => write!(f, "srem"),
+            BinOp::And => write!(f, "and"),
+            BinOp::Or => write!(f, "or"),
+            BinOp::Xor => write!(f, "xor"),
+            BinOp::Shl => write!(f, "shl"),
+            BinOp::ShrS => write!(f, "shr_s"),
+            BinOp::ShrU => write!(f, "shr_u"),
+            BinOp::Rotl => write!(f, "rotl"),
+            BinOp::Rotr => write!(f, "rotr"),
+        }
+    }
+}
+
+/// The kind of a [`StepInfo::LocalOp`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum LocalOpKind {
+    /// The value of a local was copied into another register.
+    Get,
+    /// A value was copied into the register of a local.
+    Set,
+}
+
+impl Display for LocalOpKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LocalOpKind::Get => write!(f, "get"),
+            LocalOpKind::Set => write!(f, "set"),
+        }
+    }
+}
+
+/// The class of a traced integer unary operation.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum UnOp {
+    Clz,
+    Ctz,
+    Popcnt,
+}
+
+impl Display for UnOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            UnOp::Clz => write!(f, "clz"),
+            UnOp::Ctz => write!(f, "ctz"),
+            UnOp::Popcnt => write!(f, "popcnt"),
+        }
+    }
+}
+
+/// The class of a traced `f32` or `f64` unary operation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FloatUnOp {
+    Abs,
+    Neg,
+    Sqrt,
+    Ceil,
+    Floor,
+    Trunc,
+    Nearest,
+}
+
+impl Display for FloatUnOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FloatUnOp::Abs => write!(f, "abs"),
+            FloatUnOp::Neg => write!(f, "neg"),
+            FloatUnOp::Sqrt => write!(f, "sqrt"),
+            FloatUnOp::Ceil => write!(f, "ceil"),
+            FloatUnOp::Floor => write!(f, "floor"),
+            FloatUnOp::Trunc => write!(f, "trunc"),
+            FloatUnOp::Nearest => write!(f, "nearest"),
+        }
+    }
+}
+
+/// The class of a traced integer comparison.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum RelOp {
+    Eq,
+    Ne,
+    SignedLt,
+    UnsignedLt,
+    SignedGt,
+    UnsignedGt,
+    SignedLe,
+    UnsignedLe,
+    SignedGe,
+    UnsignedGe,
+}
+
+impl Display for RelOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RelOp::Eq => write!(f, "eq"),
+            RelOp::Ne => write!(f, "ne"),
+            RelOp::SignedLt => write!(f, "lt_s"),
+            RelOp::UnsignedLt => write!(f, "lt_u"),
+            RelOp::SignedGt => write!(f, "gt_s"),
+            RelOp::UnsignedGt => write!(f, "gt_u"),
+            RelOp::SignedLe => write!(f, "le_s"),
+            RelOp::UnsignedLe => write!(f, "le_u"),
+            RelOp::SignedGe => write!(f, "ge_s"),
+            RelOp::UnsignedGe => write!(f, "ge_u"),
+        }
+    }
+}
+
+/// The class of a traced integer test.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TestOp {
+    Eqz,
+}
+
+impl Display for TestOp {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TestOp::Eqz => write!(f, "eqz"),
         }
     }
 }
 
+/// A step contributed by code outside of the executor, e.g. a host trampoline.
+///
+/// Custom steps are recorded via [`Tracer::push_custom_step`](crate::Tracer::push_custom_step)
+/// and displayed using their [`Display`] implementation.
+pub trait CustomStepInfo: Debug + Display + Send + Sync {
+    /// Returns the name of the opcode of the step.
+    fn opcode(&self) -> String;
+
+    /// Returns the memory accesses of the step in the order they happened.
+    ///
+    /// The accesses may target any [`LocationType`](super::mtable::LocationType).
+    /// [`MTable::partition`](super::mtable::MTable::partition) keeps accesses to
+    /// segments and tables apart from the stack, heap and global accesses.
+    fn memory_ops(&self) -> Vec<MemoryOp>;
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum StepInfo {
     I32BinOp {
         class: BinOp,
@@ -55,7 +282,600 @@ pub enum StepInfo {
         right: IVal,
         result: IVal,
     },
-    Unimplemented(Instruction),
+    I64BinOp {
+        class: BinOp,
+        left: IVal,
+        right: IVal,
+        result: IVal,
+    },
+    I32UnOp {
+        class: UnOp,
+        operand: IVal,
+        result: IVal,
+    },
+    /// An `f32` or `f64` unary operation on `operand`.
+    ///
+    /// Both values keep the exact bits seen by the executor, so the sign of
+    /// a zero or NaN flipped or cleared by `neg` and `abs` is preserved.
+    FloatUnOp {
+        class: FloatUnOp,
+        operand: IVal,
+        result: IVal,
+    },
+    /// A conversion of the integer `input` into the float `result`.
+    ///
+    /// The `result` is the rounded value as computed by the executor.
+    ConvertInt {
+        /// Whether `input` is interpreted as a signed integer.
+        signed: bool,
+        input: IVal,
+        result: IVal,
+    },
+    /// An integer comparison of `left` and `right`.
+    RelOp {
+        class: RelOp,
+        left: IVal,
+        right: IVal,
+        /// The `i32` boolean result, i.e. `0` or `1`.
+        result: IVal,
+    },
+    /// An integer test of `operand`.
+    ///
+    /// # Note
+    ///
+    /// The executor has no dedicated `eqz` instruction but compares with an
+    /// immediate zero instead, so `x == 0` comparisons are traced as `eqz`.
+    TestOp {
+        class: TestOp,
+        operand: IVal,
+        /// The `i32` boolean result, i.e. `0` or `1`.
+        result: IVal,
+    },
+    /// An `i32.wrap_i64` of `input` keeping only its low 32 bits.
+    I32WrapI64 {
+        input: IVal,
+        result: IVal,
+        /// The high 32 bits of `input` discarded by the wrap.
+        ///
+        /// This is `None` if all discarded bits are zero.
+        discarded_high: Option<u32>,
+    },
+    /// An `i64.extend_i32_s` or `i64.extend_i32_u` of the `i32` `input` into the `i64` `result`.
+    I64ExtendI32 {
+        /// Whether `input` is sign extended.
+        signed: bool,
+        input: IVal,
+        result: IVal,
+    },
+    /// A sign extension of the low `from_bits` bits of `operand` to `to_bits` bits,
+    /// e.g. `i32.extend8_s`.
+    ///
+    /// `operand` and `result` have the same type and the `result` is the
+    /// sign extended value as computed by the executor.
+    SignExtend {
+        from_bits: u8,
+        to_bits: u8,
+        operand: IVal,
+        result: IVal,
+    },
+    F32BinOp {
+        class: BinOp,
+        left: IVal,
+        right: IVal,
+        result: IVal,
+    },
+    F64BinOp {
+        class: BinOp,
+        left: IVal,
+        right: IVal,
+        result: IVal,
+    },
+    /// A linear memory load of `width` bytes at `effective_address`.
+    Load {
+        /// The type of the loaded value.
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::val_type"))]
+        ty: ValType,
+        /// The number of loaded bytes.
+        width: u32,
+        /// Whether the loaded bytes are sign extended to `ty`.
+        ///
+        /// This is always `false` for loads of the full width of `ty`.
+        signed: bool,
+        /// The base address operand.
+        address: IVal,
+        /// The constant offset added to `address`.
+        offset: u32,
+        /// The byte address of the first loaded byte.
+        effective_address: u64,
+        /// The register receiving the loaded value.
+        value: IVal,
+        /// The memory words overlapping the loaded bytes.
+        words: Vec<MemoryWord>,
+    },
+    /// A linear memory store of `width` bytes at `effective_address`.
+    Store {
+        /// The type of the stored value.
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::val_type"))]
+        ty: ValType,
+        /// The number of stored bytes.
+        ///
+        /// Narrowing stores only write the low `width` bytes of `value`.
+        width: u32,
+        /// The base address operand.
+        address: IVal,
+        /// The constant offset added to `address`.
+        offset: u32,
+        /// The byte address of the first stored byte.
+        effective_address: u64,
+        /// The stored value.
+        value: IVal,
+        /// The memory words overlapping the stored bytes after the store.
+        words: Vec<MemoryWord>,
+    },
+    /// A `memory.grow` by `delta` pages.
+    ///
+    /// The number of pages after the step is the `allocated_memory_pages`
+    /// of the following [`ETableEntry`].
+    MemoryGrow {
+        /// The number of pages to grow by.
+        delta: IVal,
+        /// The number of pages before the step.
+        ///
+        /// Unlike `result` this is also known if the memory could not grow.
+        previous_pages: u32,
+        /// The previous number of pages or `-1` if the memory could not grow.
+        result: IVal,
+    },
+    /// A `memory.size` returning the current number of `pages`.
+    MemorySize {
+        pages: u32,
+        result: IVal,
+    },
+    /// A `global.get` of `value` from the global at `address`.
+    GlobalGet {
+        /// The module local index of the global.
+        global_index: u32,
+        /// The address of the global within the store.
+        ///
+        /// This refers to the defining instance's global for imported globals.
+        address: usize,
+        /// Whether the global is mutable.
+        is_mutable: bool,
+        /// The register receiving the value of the global.
+        value: IVal,
+    },
+    /// A `global.set` of `value` to the global at `address`.
+    GlobalSet {
+        /// The module local index of the global.
+        global_index: u32,
+        /// The address of the global within the store.
+        ///
+        /// This refers to the defining instance's global for imported globals.
+        address: usize,
+        /// The stored value.
+        value: IVal,
+    },
+    /// A `br` branched to `target_pc`.
+    Br {
+        /// The offset of the branch target within the function's instructions.
+        target_pc: u32,
+    },
+    /// A conditional branch to `target_pc` that branched if `lhs` and `rhs` compared `true`.
+    ///
+    /// Comparisons followed by a `br_if` are fused into a single branch,
+    /// so a plain `br_if` on a condition is traced as comparing it against
+    /// an immediate zero. This also traces the branches of `if` blocks.
+    BrIf {
+        /// The comparison deciding the branch.
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::debug"))]
+        cmp: BranchComparator,
+        /// The left-hand side operand of `cmp`.
+        lhs: IVal,
+        /// The right-hand side operand of `cmp`.
+        rhs: IVal,
+        /// Whether the branch was taken.
+        taken: bool,
+        /// The offset of the branch target within the function's instructions.
+        target_pc: u32,
+    },
+    /// A `br_table` branched to the target at `effective_index`.
+    BrTable {
+        /// The register holding the branch index.
+        index: IVal,
+        /// The branch index as read from `index`.
+        raw_index: u32,
+        /// The branch index clamped to the default target if out of bounds.
+        effective_index: u32,
+        /// The number of branch targets including the default target.
+        len_targets: u32,
+        /// The offset of the selected branch target within the function's instructions.
+        target_pc: u32,
+    },
+    /// A constant was materialized into a register that is not a parameter or local.
+    ///
+    /// 32-bit constants are traced as [`Val::I32`] since their instructions
+    /// do not tell apart `i32.const`, `f32.const` and a `ref.null`.
+    Const {
+        /// The constant and the register it was written to.
+        value: IVal,
+    },
+    /// Registers were copied, e.g. the results of a block into the registers of its enclosing block.
+    ///
+    /// Copied values are traced untyped as [`Val::I64`].
+    Copy {
+        /// The copied values in the order of `results`.
+        values: Vec<IVal>,
+        /// The registers receiving the copied `values`.
+        results: Vec<IVal>,
+    },
+    /// A register copy from or into the register of a function parameter or local.
+    ///
+    /// The copied value is traced untyped as [`Val::I64`].
+    LocalOp {
+        kind: LocalOpKind,
+        /// The copied value.
+        source: IVal,
+        /// The register receiving the copied value.
+        dest: IVal,
+        /// The value of `dest` overwritten by a [`LocalOpKind::Set`].
+        ///
+        /// Only captured in [`Tracer::prev_value_mode`](crate::Tracer::prev_value_mode).
+        prev_value: Option<IVal>,
+    },
+    /// A `select` of `lhs` if `cond` is non-zero and of `rhs` otherwise.
+    Select {
+        /// The raw `i32` condition.
+        cond: IVal,
+        /// Whether `cond` is non-zero and thus `lhs` was selected.
+        cond_is_true: bool,
+        lhs: IVal,
+        rhs: IVal,
+        result: IVal,
+        /// The operand that was not selected, i.e. `rhs` if `cond_is_true` and `lhs` otherwise.
+        ///
+        /// Selects of references may drop the last reference to the discarded object,
+        /// which allows GC-aware consumers to track its liveness. This is a copy of
+        /// the operand read as `lhs` or `rhs` and thus not an additional memory access.
+        discarded: IVal,
+    },
+    TableGet {
+        table_index: u32,
+        index: IVal,
+        result: IVal,
+    },
+    TableSet {
+        table_index: u32,
+        index: IVal,
+        value: IVal,
+    },
+    /// A `drop` consumed the `value` without writing a result.
+    ///
+    /// The translator of the register machine elides a `drop` by freeing the
+    /// register of its operand, so only drops surviving translation are traced.
+    /// Currently none survive and the operands of elided drops are never read.
+    Drop {
+        value: IVal,
+    },
+    /// A function was called.
+    ///
+    /// A [`CallKind::Tail`] call replaces the function frame of the caller,
+    /// so the callee returns directly to the caller's caller.
+    Call {
+        /// The index of the called function within the calling instance.
+        func_index: u32,
+        /// The name of the called function if known.
+        name: Option<String>,
+        /// Whether the called function is a host function.
+        ///
+        /// Host functions are not traced and thus have no [`StepInfo::Return`].
+        host: bool,
+        /// Whether the called Wasm function belongs to another instance than the caller.
+        ///
+        /// If so, the memory and globals accessed by the callee are those of its own instance.
+        crosses_instance: bool,
+        /// The arguments read from the caller registers.
+        args: Vec<IVal>,
+        /// The parameter registers of the called Wasm function receiving the `args`.
+        ///
+        /// This is empty for calls to host functions.
+        params: Vec<IVal>,
+        /// Whether the call is a nested `call` or a `return_call`.
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::debug"))]
+        kind: CallKind,
+    },
+    /// A function was called through a table.
+    ///
+    /// Only calls that pass the bounds and signature checks are traced this way,
+    /// failed checks are traced as a [`StepInfo::Trap`]. The lookup of the table
+    /// element is not traced as a memory access, like a [`StepInfo::TableGet`].
+    /// The other fields are those of a [`StepInfo::Call`].
+    CallIndirect {
+        /// The index of the table holding the called function.
+        table_index: u32,
+        /// The index of the table element holding the called function.
+        elem_index: IVal,
+        /// The index of the called function within the calling instance.
+        ///
+        /// This is `None` if the calling instance neither defines nor imports it.
+        resolved_func_index: Option<u32>,
+        name: Option<String>,
+        host: bool,
+        crosses_instance: bool,
+        args: Vec<IVal>,
+        params: Vec<IVal>,
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::debug"))]
+        kind: CallKind,
+    },
+    /// A host function mutated the store state observable by its caller.
+    ///
+    /// Host functions are not traced instruction by instruction, so this step
+    /// records the net effect of the call instead.
+    HostMutation {
+        /// The words of the default linear memory whose values changed.
+        words: Vec<MemoryWord>,
+        /// The store addresses and new values of the globals whose values changed.
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::globals"))]
+        globals: Vec<(usize, Val)>,
+    },
+    /// The function frame returned to its caller.
+    Return {
+        /// The condition of a conditional return.
+        condition: Option<IVal>,
+        /// The values returned by the function frame.
+        values: Vec<IVal>,
+        /// The caller registers receiving the returned `values`.
+        results: Vec<IVal>,
+        /// The remaining registers of the function frame discarded by the return.
+        dropped: Vec<IVal>,
+    },
+    /// The instruction trapped after reading `reads`.
+    Trap {
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::debug"))]
+        code: TrapCode,
+        reads: Vec<IVal>,
+        /// The words of a passive data segment read before trapping.
+        ///
+        /// The `index` of each word is its `Data` address.
+        data: Vec<MemoryWord>,
+        /// The index of the function containing the trapping instruction.
+        func_index: u32,
+        /// The offset of the trapping instruction within the instructions of its function.
+        pc: u32,
+    },
+    Unimplemented(#[cfg_attr(feature = "serde", serde(serialize_with = "ser::debug"))] Instruction),
+    /// A step of a [`CustomStepInfo`].
+    Custom(
+        #[cfg_attr(feature = "serde", serde(serialize_with = "ser::debug"))]
+        Arc<dyn CustomStepInfo>,
+    ),
+}
+
+impl StepInfo {
+    /// Returns the name of the opcode that produced this step.
+    ///
+    /// Steps of instructions that are not yet traced are named
+    /// after their [`Instruction`] variant.
+    pub fn opcode(&self) -> String {
+        match self {
+            StepInfo::I32BinOp { class, .. } => format!("i32.{}", class),
+            StepInfo::I64BinOp { class, .. } => format!("i64.{}", class),
+            StepInfo::I32UnOp { class, .. } => format!("i32.{}", class),
+            StepInfo::FloatUnOp { class, operand, .. } => {
+                format!("{}.{}", type_name(operand.val.ty()), class)
+            }
+            StepInfo::ConvertInt {
+                signed,
+                input,
+                result,
+            } => format!(
+                "{}.convert_{}_{}",
+                type_name(result.val.ty()),
+                type_name(input.val.ty()),
+                if *signed { "s" } else { "u" }
+            ),
+            StepInfo::RelOp { class, left, .. } => {
+                format!("{}.{}", type_name(left.val.ty()), class)
+            }
+            StepInfo::TestOp { class, operand, .. } => {
+                format!("{}.{}", type_name(operand.val.ty()), class)
+            }
+            StepInfo::I32WrapI64 { .. } => String::from("i32.wrap_i64"),
+            StepInfo::I64ExtendI32 { signed, .. } => {
+                format!("i64.extend_i32_{}", if *signed { "s" } else { "u" })
+            }
+            StepInfo::SignExtend {
+                from_bits, to_bits, ..
+            } => format!("i{to_bits}.extend{from_bits}_s"),
+            StepInfo::F32BinOp { class, .. } => format!("f32.{}", class),
+            StepInfo::F64BinOp { class, .. } => format!("f64.{}", class),
+            StepInfo::Load {
+                ty, width, signed, ..
+            } => match (ty, width) {
+                (ValType::I32, 4) | (ValType::I64, 8) | (ValType::F32, 4) | (ValType::F64, 8) => {
+                    format!("{}.load", type_name(*ty))
+                }
+                _ => format!(
+                    "{}.load{}_{}",
+                    type_name(*ty),
+                    width * 8,
+                    if *signed { "s" } else { "u" }
+                ),
+            },
+            StepInfo::Store { ty, width, .. } => match (ty, width) {
+                (ValType::I32, 4) | (ValType::I64, 8) | (ValType::F32, 4) | (ValType::F64, 8) => {
+                    format!("{}.store", type_name(*ty))
+                }
+                _ => format!("{}.store{}", type_name(*ty), width * 8),
+            },
+            StepInfo::MemoryGrow { .. } => String::from("memory.grow"),
+            StepInfo::MemorySize { .. } => String::from("memory.size"),
+            StepInfo::GlobalGet { .. } => String::from("global.get"),
+            StepInfo::GlobalSet { .. } => String::from("global.set"),
+            StepInfo::Br { .. } => String::from("br"),
+            StepInfo::BrIf { .. } => String::from("br_if"),
+            StepInfo::BrTable { .. } => String::from("br_table"),
+            StepInfo::Const { value } => format!("{}.const", type_name(value.val.ty())),
+            StepInfo::Copy { .. } => String::from("copy"),
+            StepInfo::LocalOp { kind, .. } => format!("local.{}", kind),
+            StepInfo::Select { .. } => String::from("select"),
+            StepInfo::TableGet { .. } => String::from("table.get"),
+            StepInfo::TableSet { .. } => String::from("table.set"),
+            StepInfo::Drop { .. } => String::from("drop"),
+            StepInfo::Call {
+                kind: CallKind::Nested,
+                ..
+            } => String::from("call"),
+            StepInfo::Call {
+                kind: CallKind::Tail,
+                ..
+            } => String::from("return_call"),
+            StepInfo::CallIndirect {
+                kind: CallKind::Nested,
+                ..
+            } => String::from("call_indirect"),
+            StepInfo::CallIndirect {
+                kind: CallKind::Tail,
+                ..
+            } => String::from("return_call_indirect"),
+            StepInfo::HostMutation { .. } => String::from("host"),
+            StepInfo::Return { .. } => String::from("return"),
+            StepInfo::Trap { .. } => String::from("trap"),
+            StepInfo::Unimplemented(instr) => format!("{:?}", instr)
+                .chars()
+                .take_while(char::is_ascii_alphanumeric)
+                .collect(),
+            StepInfo::Custom(step) => step.opcode(),
+        }
+    }
+
+    /// Returns the number of memory table rows of this step.
+    ///
+    /// This equals the number of entries produced for this step by
+    /// [`memory_event_of_step`](super::mtable::memory_event_of_step).
+    pub fn memory_rows(&self) -> usize {
+        match self {
+            StepInfo::I32BinOp {
+                left,
+                right,
+                result,
+                ..
+            }
+            | StepInfo::I64BinOp {
+                left,
+                right,
+                result,
+                ..
+            }
+            | StepInfo::F32BinOp {
+                left,
+                right,
+                result,
+                ..
+            }
+            | StepInfo::F64BinOp {
+                left,
+                right,
+                result,
+                ..
+            } => stack_rows([left, right, result]),
+            StepInfo::I32UnOp {
+                operand, result, ..
+            }
+            | StepInfo::FloatUnOp {
+                operand, result, ..
+            }
+            | StepInfo::SignExtend {
+                operand, result, ..
+            } => stack_rows([operand, result]),
+            StepInfo::ConvertInt { input, result, .. }
+            | StepInfo::I32WrapI64 { input, result, .. }
+            | StepInfo::I64ExtendI32 { input, result, .. } => stack_rows([input, result]),
+            StepInfo::RelOp {
+                left,
+                right,
+                result,
+                ..
+            } => stack_rows([left, right, result]),
+            StepInfo::TestOp {
+                operand, result, ..
+            } => stack_rows([operand, result]),
+            StepInfo::Load {
+                address,
+                value,
+                words,
+                ..
+            }
+            | StepInfo::Store {
+                address,
+                value,
+                words,
+                ..
+            } => stack_rows([address, value]) + words.len(),
+            StepInfo::MemoryGrow { delta, result, .. } => stack_rows([delta, result]),
+            StepInfo::MemorySize { result, .. } => stack_rows([result]),
+            StepInfo::GlobalGet { value, .. } | StepInfo::GlobalSet { value, .. } => {
+                stack_rows([value]) + 1
+            }
+            StepInfo::Br { .. } => 0,
+            StepInfo::BrIf { lhs, rhs, .. } => stack_rows([lhs, rhs]),
+            StepInfo::BrTable { index, .. } => stack_rows([index]),
+            StepInfo::Const { value } => stack_rows([value]),
+            StepInfo::Copy { values, results } => stack_rows(values.iter().chain(results)),
+            StepInfo::LocalOp {
+                source,
+                dest,
+                prev_value,
+                ..
+            } => stack_rows([source, dest].into_iter().chain(prev_value)),
+            StepInfo::Select {
+                cond,
+                lhs,
+                rhs,
+                result,
+                ..
+            } => stack_rows([cond, lhs, rhs, result]),
+            StepInfo::TableGet { index, result, .. } => stack_rows([index, result]),
+            StepInfo::TableSet { index, value, .. } => stack_rows([index, value]),
+            StepInfo::Drop { value } => stack_rows([value]),
+            StepInfo::Call { args, params, .. } => stack_rows(args.iter().chain(params)),
+            StepInfo::CallIndirect {
+                elem_index,
+                args,
+                params,
+                ..
+            } => stack_rows([elem_index].into_iter().chain(args).chain(params)),
+            StepInfo::Unimplemented(_) => 0,
+            StepInfo::HostMutation { words, globals } => words.len() + globals.len(),
+            StepInfo::Return {
+                condition,
+                values,
+                results,
+                dropped,
+            } => stack_rows(condition.iter().chain(values).chain(results).chain(dropped)),
+            StepInfo::Trap { reads, data, .. } => stack_rows(reads) + data.len(),
+            StepInfo::Custom(step) => step.memory_ops().len(),
+        }
+    }
+}
+
+/// Returns the number of `operands` that are located on the stack.
+fn stack_rows<'a>(operands: impl IntoIterator<Item = &'a IVal>) -> usize {
+    operands
+        .into_iter()
+        .filter(|operand| operand.addr.is_some())
+        .count()
+}
+
+/// Returns the Wasm text format name of the value type `ty`.
+pub(crate) fn type_name(ty: ValType) -> &'static str {
+    match ty {
+        ValType::I32 => "i32",
+        ValType::I64 => "i64",
+        ValType::F32 => "f32",
+        ValType::F64 => "f64",
+        ValType::FuncRef => "funcref",
+        ValType::ExternRef => "externref",
+    }
 }
 
 impl Display for StepInfo {
@@ -69,16 +889,300 @@ impl Display for StepInfo {
             } => {
                 write!(f, "{:?} {:10} {:10} {:10} ", class, left, right, result)
             }
+            StepInfo::I64BinOp {
+                left,
+                right,
+                result,
+                ..
+            }
+            | StepInfo::F32BinOp {
+                left,
+                right,
+                result,
+                ..
+            }
+            | StepInfo::F64BinOp {
+                left,
+                right,
+                result,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} {:10} {:10} {:10} ",
+                    self.opcode(),
+                    left,
+                    right,
+                    result
+                )
+            }
+            StepInfo::I32UnOp {
+                operand, result, ..
+            }
+            | StepInfo::FloatUnOp {
+                operand, result, ..
+            }
+            | StepInfo::SignExtend {
+                operand, result, ..
+            } => {
+                write!(f, "{} {:10} {:10}", self.opcode(), operand, result)
+            }
+            StepInfo::ConvertInt { input, result, .. }
+            | StepInfo::I32WrapI64 { input, result, .. }
+            | StepInfo::I64ExtendI32 { input, result, .. } => {
+                write!(f, "{} {:10} {:10}", self.opcode(), input, result)
+            }
+            StepInfo::RelOp {
+                left,
+                right,
+                result,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} {:10} {:10} {:10}",
+                    self.opcode(),
+                    left,
+                    right,
+                    result
+                )
+            }
+            StepInfo::TestOp {
+                operand, result, ..
+            } => {
+                write!(f, "{} {:10} {:10}", self.opcode(), operand, result)
+            }
+            StepInfo::Load {
+                address,
+                offset,
+                effective_address,
+                value,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} {:10} {:5} {:10} {:10}",
+                    self.opcode(),
+                    address,
+                    offset,
+                    effective_address,
+                    value
+                )
+            }
+            StepInfo::Store {
+                address,
+                offset,
+                effective_address,
+                value,
+                ..
+            } => {
+                write!(
+                    f,
+                    "{} {:10} {:5} {:10} {:10}",
+                    self.opcode(),
+                    address,
+                    offset,
+                    effective_address,
+                    value
+                )
+            }
+            StepInfo::MemoryGrow {
+                delta,
+                previous_pages,
+                result,
+            } => {
+                write!(
+                    f,
+                    "memory.grow {:10} {:10} {:10}",
+                    delta, previous_pages, result
+                )
+            }
+            StepInfo::MemorySize { pages, result } => {
+                write!(f, "memory.size {:10} {:10}", pages, result)
+            }
+            StepInfo::GlobalGet {
+                global_index,
+                address,
+                value,
+                ..
+            } => {
+                write!(
+                    f,
+                    "global.get {:5} {:10} {:10}",
+                    global_index, address, value
+                )
+            }
+            StepInfo::GlobalSet {
+                global_index,
+                address,
+                value,
+            } => {
+                write!(
+                    f,
+                    "global.set {:5} {:10} {:10}",
+                    global_index, address, value
+                )
+            }
+            StepInfo::Br { target_pc } => write!(f, "br -> {}", target_pc),
+            StepInfo::BrIf {
+                cmp,
+                lhs,
+                rhs,
+                taken,
+                target_pc,
+            } => {
+                write!(
+                    f,
+                    "br_if {:?} {:10} {:10} taken: {} -> {}",
+                    cmp, lhs, rhs, taken, target_pc
+                )
+            }
+            StepInfo::BrTable {
+                index,
+                raw_index,
+                effective_index,
+                len_targets,
+                target_pc,
+            } => {
+                write!(
+                    f,
+                    "br_table {:10} {} -> {} of {} -> {}",
+                    index, raw_index, effective_index, len_targets, target_pc
+                )
+            }
+            StepInfo::LocalOp { source, dest, .. } => {
+                write!(f, "{} {:10} -> {:10}", self.opcode(), source, dest)
+            }
+            StepInfo::Const { value } => write!(f, "{} {:10}", self.opcode(), value),
+            StepInfo::Copy { values, results } => {
+                write!(f, "copy")?;
+                for (value, result) in values.iter().zip(results) {
+                    write!(f, " {:10} -> {:10}", value, result)?;
+                }
+                Ok(())
+            }
+            StepInfo::Select {
+                cond,
+                cond_is_true,
+                lhs,
+                rhs,
+                result,
+                ..
+            } => {
+                write!(
+                    f,
+                    "select {:10} ({}) {:10} {:10} {:10}",
+                    cond, cond_is_true, lhs, rhs, result
+                )
+            }
+            StepInfo::TableGet {
+                table_index,
+                index,
+                result,
+            } => {
+                write!(f, "table.get {:5} {:10} {:10}", table_index, index, result)
+            }
+            StepInfo::TableSet {
+                table_index,
+                index,
+                value,
+            } => {
+                write!(f, "table.set {:5} {:10} {:10}", table_index, index, value)
+            }
+            StepInfo::Drop { value } => write!(f, "drop {:10}", value),
+            StepInfo::Call {
+                func_index,
+                name,
+                args,
+                ..
+            } => {
+                write!(f, "{} {}", self.opcode(), func_index)?;
+                if let Some(name) = name {
+                    write!(f, " <{}>", name)?;
+                }
+                for arg in args {
+                    write!(f, " {:10}", arg)?;
+                }
+                Ok(())
+            }
+            StepInfo::CallIndirect {
+                table_index,
+                elem_index,
+                resolved_func_index,
+                name,
+                args,
+                ..
+            } => {
+                write!(f, "{} {:5} {:10}", self.opcode(), table_index, elem_index)?;
+                if let Some(func_index) = resolved_func_index {
+                    write!(f, " -> {}", func_index)?;
+                }
+                if let Some(name) = name {
+                    write!(f, " <{}>", name)?;
+                }
+                for arg in args {
+                    write!(f, " {:10}", arg)?;
+                }
+                Ok(())
+            }
+            StepInfo::HostMutation { words, globals } => {
+                write!(f, "host {} words {} globals", words.len(), globals.len())
+            }
+            StepInfo::Return {
+                condition,
+                values,
+                results,
+                dropped,
+            } => {
+                write!(f, "return")?;
+                if let Some(condition) = condition {
+                    write!(f, " if {:10}", condition)?;
+                }
+                for (value, result) in values.iter().zip(results) {
+                    write!(f, " {:10} -> {:10}", value, result)?;
+                }
+                write!(f, " dropped {}", dropped.len())
+            }
+            StepInfo::Trap {
+                code,
+                reads,
+                data,
+                func_index,
+                pc,
+            } => {
+                write!(f, "trap {:?} at {}:{}", code, func_index, pc)?;
+                for read in reads {
+                    write!(f, " {:10}", read)?;
+                }
+                for word in data {
+                    write!(f, " data[{}] {:#018x}", word.index, word.value)?;
+                }
+                Ok(())
+            }
             StepInfo::Unimplemented(instr) => {
                 write!(f, "unimplemented {:?}", instr)
             }
+            StepInfo::Custom(step) => Display::fmt(step, f),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ETableEntry {
-    pub eid: u32,
+    pub eid: u64,
+    /// The index of the function executing the step.
+    pub func_index: u32,
+    /// The offset of the executed instruction within the compiled
+    /// instructions of the function at `func_index`.
+    ///
+    /// Steps recorded outside of the executor, e.g. [`StepInfo::HostMutation`],
+    /// share the location of the previous step.
+    pub pc: u32,
+    /// The number of linear memory pages before the step is executed.
+    ///
+    /// Wasmi backs every allocated page with zeroed bytes as soon as the memory grows,
+    /// so this is also the number of committed pages.
     pub allocated_memory_pages: u32,
     pub step_info: StepInfo,
 }
@@ -87,38 +1191,466 @@ impl Display for ETableEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{:10} {:10} {}",
-            self.eid, self.allocated_memory_pages, self.step_info
+            "{:20} {:10} {:10} {:10} {}",
+            self.eid, self.func_index, self.pc, self.allocated_memory_pages, self.step_info
         )
     }
 }
 
-#[derive(Debug, Default)]
-pub struct ETable(Vec<ETableEntry>);
+/// An error found by [`ETable::validate_eids`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EidError {
+    /// The `eid` does not follow its predecessor `prev` in strictly increasing order.
+    NotIncreasing { prev: u64, eid: u64 },
+    /// The `eid` skips over the `expected` eid of a dense table.
+    Gap { expected: u64, eid: u64 },
+}
+
+impl Display for EidError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            EidError::NotIncreasing { prev, eid } => {
+                write!(
+                    f,
+                    "eid {} does not increase over its predecessor {}",
+                    eid, prev
+                )
+            }
+            EidError::Gap { expected, eid } => {
+                write!(f, "expected eid {} but found {}", expected, eid)
+            }
+        }
+    }
+}
+
+/// A callback invoked when the [`ETableEntry`] with a given eid is pushed.
+pub type Breakpoint = Box<dyn FnMut(&ETableEntry) + Send>;
+
+#[derive(Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ETable {
+    entries: Vec<ETableEntry>,
+    /// The breakpoints keyed by the eid at which they fire.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    breakpoints: BTreeMap<u64, Vec<Breakpoint>>,
+    /// The sink set by [`ETable::set_sink`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    sink: Option<Box<dyn TraceSink>>,
+    /// The number of steps passed to the `sink` that are no longer retained.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    streamed: u64,
+}
+
+impl Debug for ETable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ETable")
+            .field("entries", &self.entries)
+            .field("breakpoints", &self.breakpoints.keys())
+            .field("sink", &self.sink.is_some())
+            .field("streamed", &self.streamed)
+            .finish()
+    }
+}
 
 impl ETable {
+    pub fn new(entries: Vec<ETableEntry>) -> ETable {
+        ETable {
+            entries,
+            breakpoints: BTreeMap::new(),
+            sink: None,
+            streamed: 0,
+        }
+    }
+
     pub fn entries(&self) -> &Vec<ETableEntry> {
-        &self.0
+        &self.entries
+    }
+
+    /// Returns the number of pushed steps.
+    ///
+    /// This includes the steps passed to the [`ETable::set_sink`] sink
+    /// that are no longer retained.
+    pub fn steps(&self) -> u64 {
+        self.streamed + self.entries.len() as u64
+    }
+
+    /// Removes all entries while keeping the allocated capacity, the breakpoints and the sink.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.streamed = 0;
     }
 
+    /// Passes all further pushed steps to the `sink` instead of buffering them.
+    ///
+    /// Only the last step is retained so that eids and locations continue after it.
+    /// Replaces the previously set sink.
+    pub fn set_sink(&mut self, sink: impl TraceSink + 'static) {
+        self.sink = Some(Box::new(sink));
+    }
+
+    /// Returns the function index and pc of the last step.
+    ///
+    /// Returns `(0, 0)` if the table is empty.
+    pub(crate) fn last_location(&self) -> (u32, u32) {
+        self.entries
+            .last()
+            .map_or((0, 0), |entry| (entry.func_index, entry.pc))
+    }
+
+    /// Appends the `step_info` at the location of the previous step.
+    ///
+    /// See [`ETable::push_at`].
     pub fn push(&mut self, allocated_memory_pages: u32, step_info: StepInfo) {
+        let (func_index, pc) = self.last_location();
+        self.push_at(func_index, pc, allocated_memory_pages, step_info)
+    }
+
+    /// Appends the `step_info` of the instruction at `pc` of the function at `func_index`.
+    pub fn push_at(
+        &mut self,
+        func_index: u32,
+        pc: u32,
+        allocated_memory_pages: u32,
+        step_info: StepInfo,
+    ) {
+        // Continue after the last eid since loaded tables may have gaps.
+        let eid = self.entries.last().map_or(1, |entry| entry.eid + 1);
         let entry = ETableEntry {
-            eid: (self.entries().len() + 1).try_into().unwrap(),
+            eid,
+            func_index,
+            pc,
             allocated_memory_pages,
             step_info,
         };
+        if let Some(breakpoints) = self.breakpoints.get_mut(&entry.eid) {
+            for breakpoint in breakpoints {
+                breakpoint(&entry);
+            }
+        }
+        if let Some(sink) = &mut self.sink {
+            sink.on_step(&entry);
+            self.streamed += self.entries.len() as u64;
+            self.entries.clear();
+        }
+
+        self.entries.push(entry);
+    }
+
+    /// Registers the `breakpoint` to be called with the entry of the step at `eid`.
+    ///
+    /// The `breakpoint` is called before the entry is appended to the [`ETable`].
+    /// Multiple breakpoints at the same `eid` are called in registration order.
+    pub fn set_breakpoint(
+        &mut self,
+        eid: u64,
+        breakpoint: impl FnMut(&ETableEntry) + Send + 'static,
+    ) {
+        self.breakpoints
+            .entry(eid)
+            .or_default()
+            .push(Box::new(breakpoint));
+    }
+
+    /// Checks that the eids are dense, 1-based and strictly increasing.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`EidError`] found.
+    pub fn validate_eids(&self) -> Result<(), EidError> {
+        self.validate_eids_impl(false)
+    }
+
+    /// Checks that the eids are strictly increasing but allows gaps between them.
+    ///
+    /// This is the invariant of filtered tables that only retain a subset of steps.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`EidError`] found.
+    pub fn validate_sparse_eids(&self) -> Result<(), EidError> {
+        self.validate_eids_impl(true)
+    }
+
+    fn validate_eids_impl(&self, allow_sparse: bool) -> Result<(), EidError> {
+        let mut prev = 0;
+        for entry in self.entries() {
+            let eid = entry.eid;
+            if eid <= prev {
+                return Err(EidError::NotIncreasing { prev, eid });
+            }
+            let expected = prev + 1;
+            if !allow_sparse && eid != expected {
+                return Err(EidError::Gap { expected, eid });
+            }
+            prev = eid;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of executed steps per opcode.
+    pub fn histogram(&self) -> BTreeMap<String, usize> {
+        let mut histogram = BTreeMap::new();
+        for entry in self.entries() {
+            *histogram.entry(entry.step_info.opcode()).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Returns the `top_n` most frequently executed opcodes sorted by descending count.
+    ///
+    /// Opcodes with the same count are sorted by name.
+    pub fn hot_opcodes(&self, top_n: usize) -> Vec<(String, usize)> {
+        let mut opcodes = self.histogram().into_iter().collect::<Vec<_>>();
+        opcodes.sort_by(|(_, a), (_, b)| b.cmp(a));
+        opcodes.truncate(top_n);
+        opcodes
+    }
+
+    /// Returns the number of executed steps per function index.
+    ///
+    /// Each step is attributed to the function executing it, see [`ETableEntry::func_index`].
+    /// Calls are attributed to the caller and returns to the callee.
+    pub fn steps_per_function(&self) -> BTreeMap<u32, usize> {
+        let mut steps = BTreeMap::new();
+        for entry in self.entries() {
+            *steps.entry(entry.func_index).or_insert(0) += 1;
+        }
+        steps
+    }
+
+    /// Returns the number of rows of the memory table of the [`ETable`].
+    ///
+    /// The rows are counted per step without building the memory table.
+    /// This includes the `Read` rows that a write-only [`Tracer`](super::Tracer) omits.
+    pub fn estimate_mtable_rows(&self) -> usize {
+        self.entries()
+            .iter()
+            .map(|entry| entry.step_info.memory_rows())
+            .sum()
+    }
 
-        self.0.push(entry);
+    /// Returns the steps of the [`ETable`] in their [`CompactStep`] encoding.
+    pub fn compact_steps(&self) -> impl Iterator<Item = CompactStep> + '_ {
+        self.entries()
+            .iter()
+            .map(|entry| CompactStep::from(entry.step_info.clone()))
+    }
+
+    /// Writes the steps of the [`ETable`] as folded stacks into `out`.
+    ///
+    /// Each line holds the `;` separated stack of function frames and the number
+    /// of steps executed while the last of them was on top, e.g. `root;f;g 42`,
+    /// as consumed by flame graph tools. Frames are named after the called
+    /// function if known or after its index otherwise. The frame of the traced
+    /// function itself is named `root`.
+    ///
+    /// # Errors
+    ///
+    /// If writing to `out` fails.
+    pub fn to_folded_stacks(&self, mut out: impl Write) -> core::fmt::Result {
+        let mut stack = vec![String::from("root")];
+        let mut counts = BTreeMap::<String, usize>::new();
+        for entry in self.entries() {
+            *counts.entry(stack.join(";")).or_default() += 1;
+            let (func_index, name, host, kind) = match &entry.step_info {
+                StepInfo::Call {
+                    func_index,
+                    name,
+                    host,
+                    kind,
+                    ..
+                } => (Some(*func_index), name, *host, *kind),
+                StepInfo::CallIndirect {
+                    resolved_func_index,
+                    name,
+                    host,
+                    kind,
+                    ..
+                } => (*resolved_func_index, name, *host, *kind),
+                StepInfo::Return { .. } if stack.len() > 1 => {
+                    stack.pop();
+                    continue;
+                }
+                _ => continue,
+            };
+            if kind == CallKind::Tail && stack.len() > 1 {
+                stack.pop();
+            }
+            if !host {
+                stack.push(match (name, func_index) {
+                    (Some(name), _) => name.clone(),
+                    (None, Some(func_index)) => format!("func[{}]", func_index),
+                    (None, None) => String::from("func[?]"),
+                });
+            }
+        }
+        for (stack, count) in counts {
+            writeln!(out, "{} {}", stack, count)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the JSON encoding of the entries of the [`ETable`].
+    ///
+    /// Values are encoded as their type and their 64-bit pattern so that
+    /// NaN payloads and references are preserved. Breakpoints are skipped.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("execution tables always serialize to JSON")
+    }
+
+    /// Returns the entries of the [`ETable`] as an Arrow record batch with one row per step.
+    ///
+    /// Besides the `eid`, `opcode` and call `depth` of a step, its row holds
+    /// the memory table accesses of the step as the nested `values`,
+    /// `addresses`, `location_types` and `access_types` lists in emid order.
+    /// Values are encoded as their 64-bit pattern.
+    #[cfg(feature = "arrow")]
+    pub fn to_arrow(&self) -> arrow_array::RecordBatch {
+        use arrow_array::{
+            builder::{ListBuilder, StringBuilder, UInt64Builder},
+            ArrayRef,
+            RecordBatch,
+            StringArray,
+            UInt32Array,
+            UInt64Array,
+        };
+
+        let mut events = super::mtable::MemoryEvents::default();
+        let mut eids = Vec::with_capacity(self.entries.len());
+        let mut opcodes = Vec::with_capacity(self.entries.len());
+        let mut depths = Vec::with_capacity(self.entries.len());
+        let mut values = ListBuilder::new(UInt64Builder::new());
+        let mut addresses = ListBuilder::new(UInt64Builder::new());
+        let mut ltypes = ListBuilder::new(StringBuilder::new());
+        let mut atypes = ListBuilder::new(StringBuilder::new());
+        for entry in &self.entries {
+            eids.push(entry.eid);
+            opcodes.push(entry.step_info.opcode());
+            depths.push(events.depth());
+            for access in events.of_step(entry) {
+                values
+                    .values()
+                    .append_value(UntypedVal::from(access.value).to_bits());
+                addresses.values().append_value(access.addr as u64);
+                ltypes.values().append_value(format!("{}", access.ltype));
+                atypes.values().append_value(format!("{}", access.atype));
+            }
+            values.append(true);
+            addresses.append(true);
+            ltypes.append(true);
+            atypes.append(true);
+        }
+        RecordBatch::try_from_iter([
+            ("eid", Arc::new(UInt64Array::from(eids)) as ArrayRef),
+            ("opcode", Arc::new(StringArray::from(opcodes))),
+            ("depth", Arc::new(UInt32Array::from(depths))),
+            ("values", Arc::new(values.finish())),
+            ("addresses", Arc::new(addresses.finish())),
+            ("location_types", Arc::new(ltypes.finish())),
+            ("access_types", Arc::new(atypes.finish())),
+        ])
+        .expect("the columns of an execution table have equal lengths")
     }
 
     pub fn show(&self) {
-        println!(
-            "{:10} {:10} {}",
-            "eid", "allocated_memory_pages", "step_info"
-        );
+        println!("                 eid func_index pc         allocated_memory_pages step_info");
 
         for entry in self.entries() {
             println!("{}", entry);
         }
     }
 }
+
+/// Serde helpers for the foreign types held by the tables of the tracer.
+#[cfg(feature = "serde")]
+pub(super) mod ser {
+    use super::type_name;
+    use core::fmt::Debug;
+    use serde::{de, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+    use std::string::String;
+    use wasmi_core::ValType;
+
+    /// Serializes and deserializes a [`Val`](crate::Val) as its type and its 64-bit pattern.
+    pub mod val {
+        use super::*;
+        use crate::{core::UntypedVal, value::WithType, Val};
+
+        #[derive(Deserialize)]
+        struct Repr {
+            ty: String,
+            bits: u64,
+        }
+
+        pub fn serialize<S: Serializer>(val: &Val, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("Val", 2)?;
+            state.serialize_field("ty", type_name(val.ty()))?;
+            state.serialize_field("bits", &UntypedVal::from(val.clone()).to_bits())?;
+            state.end()
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Val, D::Error> {
+            let repr = Repr::deserialize(deserializer)?;
+            let ty = [
+                ValType::I32,
+                ValType::I64,
+                ValType::F32,
+                ValType::F64,
+                ValType::FuncRef,
+                ValType::ExternRef,
+            ]
+            .into_iter()
+            .find(|ty| type_name(*ty) == repr.ty)
+            .ok_or_else(|| {
+                de::Error::invalid_value(de::Unexpected::Str(&repr.ty), &"a value type")
+            })?;
+            Ok(UntypedVal::from(repr.bits).with_type(ty))
+        }
+    }
+
+    /// Serializes and deserializes a `usize` address as a `u64`.
+    ///
+    /// This keeps serialized tables portable between 32-bit and 64-bit hosts.
+    pub mod addr {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(addr: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_u64(*addr as u64)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+            let addr = u64::deserialize(deserializer)?;
+            usize::try_from(addr).map_err(|_| {
+                de::Error::invalid_value(de::Unexpected::Unsigned(addr), &"an address of this host")
+            })
+        }
+    }
+
+    /// Serializes `ty` as its Wasm text format name.
+    pub fn val_type<S: Serializer>(ty: &ValType, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(type_name(*ty))
+    }
+
+    /// Serializes `value` as its [`Debug`] representation.
+    pub fn debug<T: Debug, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{:?}", value))
+    }
+
+    /// Serializes the store addresses and values of changed globals.
+    pub fn globals<S: Serializer>(
+        globals: &[(usize, crate::Val)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Global<'a> {
+            address: usize,
+            #[serde(serialize_with = "val::serialize")]
+            value: &'a crate::Val,
+        }
+
+        serializer.collect_seq(globals.iter().map(|(address, value)| Global {
+            address: *address,
+            value,
+        }))
+    }
+}
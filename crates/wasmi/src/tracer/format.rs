@@ -0,0 +1,231 @@
+//! The versioned binary encoding of recorded trace tables.
+//!
+//! Every encoding starts with a header of the [`MAGIC`] bytes followed by the
+//! major and minor [`FormatVersion`] as little endian `u16` values.
+//! All integers of the encoded tables are little endian.
+
+use crate::{
+    core::{UntypedVal, ValType},
+    value::WithType,
+    Val,
+};
+use core::fmt::{self, Display};
+use std::vec::Vec;
+
+/// The bytes every encoded trace table starts with.
+pub const MAGIC: [u8; 4] = *b"WTRC";
+
+/// The version of the encoding written by this version of the tracer.
+///
+/// Version `2.0` widened the encoded eids from 32 to 64 bits.
+pub const TRACE_FORMAT_VERSION: FormatVersion = FormatVersion { major: 2, minor: 0 };
+
+/// The version of an encoded trace table.
+///
+/// Encodings of a newer minor version only append fields to the encoded
+/// entries of an older one. Encodings of other major versions cannot be decoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FormatVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl FormatVersion {
+    /// Returns `true` if encodings of this version can be decoded.
+    ///
+    /// This is the case for the [`TRACE_FORMAT_VERSION`] and all older minor
+    /// versions of its major version.
+    pub fn is_supported(&self) -> bool {
+        // Compared via `cmp` since `<=` against a minor version of `0` is an extreme comparison.
+        self.major == TRACE_FORMAT_VERSION.major
+            && self.minor.cmp(&TRACE_FORMAT_VERSION.minor).is_le()
+    }
+}
+
+impl Display for FormatVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// An error encountered while decoding an encoded trace table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The bytes do not start with the [`MAGIC`] bytes.
+    BadMagic,
+    /// The bytes were encoded with an unsupported format version.
+    UnsupportedVersion { found: FormatVersion },
+    /// The bytes end in the middle of an encoded value.
+    UnexpectedEnd,
+    /// An encoded enum of the given `kind` has an unknown `tag`.
+    InvalidTag { kind: &'static str, tag: u8 },
+    /// There are `len` bytes left after the encoded table.
+    TrailingBytes { len: usize },
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::BadMagic => write!(f, "not an encoded trace table"),
+            FormatError::UnsupportedVersion { found } => write!(
+                f,
+                "unsupported trace format version {} (supported: {}.0 to {})",
+                found, TRACE_FORMAT_VERSION.major, TRACE_FORMAT_VERSION
+            ),
+            FormatError::UnexpectedEnd => write!(f, "unexpected end of the encoded trace table"),
+            FormatError::InvalidTag { kind, tag } => write!(f, "invalid {} tag {}", kind, tag),
+            FormatError::TrailingBytes { len } => {
+                write!(f, "{} trailing bytes after the encoded trace table", len)
+            }
+        }
+    }
+}
+
+/// Encodes a trace table starting with the header of the [`TRACE_FORMAT_VERSION`].
+#[derive(Debug)]
+pub(crate) struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    /// Creates an [`Encoder`] that has written the header.
+    pub fn new() -> Self {
+        let mut encoder = Self { bytes: Vec::new() };
+        encoder.bytes.extend_from_slice(&MAGIC);
+        encoder.u16(TRACE_FORMAT_VERSION.major);
+        encoder.u16(TRACE_FORMAT_VERSION.minor);
+        encoder
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Encodes the `value` as its type tag followed by its 64-bit untyped bits.
+    ///
+    /// # Note
+    ///
+    /// References are encoded by the bits of their store index and are
+    /// thus only meaningful within the store that recorded the trace.
+    pub fn val(&mut self, value: &Val) {
+        let tag = match value.ty() {
+            ValType::I32 => 0,
+            ValType::I64 => 1,
+            ValType::F32 => 2,
+            ValType::F64 => 3,
+            ValType::FuncRef => 4,
+            ValType::ExternRef => 5,
+        };
+        self.u8(tag);
+        self.u64(UntypedVal::from(value.clone()).to_bits());
+    }
+
+    /// Returns the encoded bytes.
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Decodes a trace table encoded by an [`Encoder`].
+#[derive(Debug)]
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+    version: FormatVersion,
+}
+
+impl<'a> Decoder<'a> {
+    /// Creates a [`Decoder`] of the `bytes` after checking their header.
+    ///
+    /// # Errors
+    ///
+    /// If the header is malformed or of an unsupported [`FormatVersion`].
+    pub fn new(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        let bytes = bytes.strip_prefix(&MAGIC).ok_or(FormatError::BadMagic)?;
+        let mut decoder = Self {
+            bytes,
+            version: TRACE_FORMAT_VERSION,
+        };
+        let found = FormatVersion {
+            major: decoder.u16()?,
+            minor: decoder.u16()?,
+        };
+        if !found.is_supported() {
+            return Err(FormatError::UnsupportedVersion { found });
+        }
+        decoder.version = found;
+        Ok(decoder)
+    }
+
+    /// Returns the [`FormatVersion`] of the decoded bytes.
+    pub fn version(&self) -> FormatVersion {
+        self.version
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], FormatError> {
+        let (head, rest) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .ok_or(FormatError::UnexpectedEnd)?;
+        self.bytes = rest;
+        Ok(*head)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, FormatError> {
+        self.take::<1>().map(|[value]| value)
+    }
+
+    pub fn u16(&mut self) -> Result<u16, FormatError> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, FormatError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, FormatError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    /// Decodes a value encoded by [`Encoder::val`].
+    pub fn val(&mut self) -> Result<Val, FormatError> {
+        let ty = match self.u8()? {
+            0 => ValType::I32,
+            1 => ValType::I64,
+            2 => ValType::F32,
+            3 => ValType::F64,
+            4 => ValType::FuncRef,
+            5 => ValType::ExternRef,
+            tag => {
+                return Err(FormatError::InvalidTag {
+                    kind: "value type",
+                    tag,
+                })
+            }
+        };
+        Ok(UntypedVal::from(self.u64()?).with_type(ty))
+    }
+
+    /// Checks that all encoded bytes have been decoded.
+    ///
+    /// # Errors
+    ///
+    /// If there are bytes left after the decoded table.
+    pub fn finish(self) -> Result<(), FormatError> {
+        match self.bytes.len() {
+            0 => Ok(()),
+            len => Err(FormatError::TrailingBytes { len }),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{
+    engine::{bytecode::Instruction, CodeMap, CompiledFunc},
+    func::{FuncEntity, FuncRef},
+    Instance,
+    StoreInner,
+};
+use core::mem;
+use std::{collections::BTreeMap, vec::Vec};
+use wasmi_core::UntypedVal;
+
+/// The function indices of the functions of the traced instances.
+///
+/// The functions of an instance are indexed once when the instance executes
+/// its first traced instruction so that tracing a step neither scans nor
+/// compiles the functions of the instance.
+#[derive(Debug, Default)]
+pub(crate) struct FuncIndices {
+    /// The indexed functions of each traced instance.
+    instances: Vec<InstanceFuncs>,
+    /// The end address and [`CompiledFunc`] of the instructions of compiled functions
+    /// by the address of their first instruction.
+    instrs: BTreeMap<usize, (usize, CompiledFunc)>,
+}
+
+/// The function indices of the functions of a single [`Instance`].
+#[derive(Debug)]
+struct InstanceFuncs {
+    /// The indexed instance.
+    instance: Instance,
+    /// The function index of each function by its untyped function reference.
+    ///
+    /// A function imported multiple times maps to its first function index.
+    funcs: BTreeMap<u64, u32>,
+    /// The function index of each Wasm function by its [`CompiledFunc`].
+    ///
+    /// Functions defined by the instance take precedence over imported functions
+    /// of other instances of the same module which share their [`CompiledFunc`].
+    bodies: BTreeMap<CompiledFunc, u32>,
+}
+
+impl InstanceFuncs {
+    /// Indexes the functions of the `instance`.
+    fn new(store: &StoreInner, instance: &Instance) -> Self {
+        let entity = store.resolve_instance(instance);
+        let mut funcs = BTreeMap::new();
+        let mut bodies = BTreeMap::new();
+        let instance_funcs = (0..).map_while(|index| Some((index, entity.get_func(index)?)));
+        for (func_index, func) in instance_funcs {
+            funcs
+                .entry(u64::from(UntypedVal::from(FuncRef::new(func))))
+                .or_insert(func_index);
+            if let FuncEntity::Wasm(func) = store.resolve_func(&func) {
+                bodies.insert(func.func_body(), func_index);
+            }
+        }
+        Self {
+            instance: *instance,
+            funcs,
+            bodies,
+        }
+    }
+}
+
+impl FuncIndices {
+    /// Returns the indexed functions of the `instance`, indexing them if necessary.
+    fn instance(&mut self, store: &StoreInner, instance: &Instance) -> &InstanceFuncs {
+        let position = self
+            .instances
+            .iter()
+            .position(|funcs| funcs.instance == *instance);
+        let position = position.unwrap_or_else(|| {
+            self.instances.push(InstanceFuncs::new(store, instance));
+            self.instances.len() - 1
+        });
+        &self.instances[position]
+    }
+
+    /// Returns the function index of the Wasm function `func` within the `instance`.
+    ///
+    /// Returns `None` if `func` is not a function of the `instance`.
+    pub fn func_index(
+        &mut self,
+        store: &StoreInner,
+        instance: &Instance,
+        func: CompiledFunc,
+    ) -> Option<u32> {
+        self.instance(store, instance).bodies.get(&func).copied()
+    }
+
+    /// Returns the function index of the function referenced by the table `element`
+    /// within the `instance`.
+    ///
+    /// Returns `None` if the referenced function is not a function of the `instance`.
+    pub fn func_ref_index(
+        &mut self,
+        store: &StoreInner,
+        instance: &Instance,
+        element: UntypedVal,
+    ) -> Option<u32> {
+        let funcs = &self.instance(store, instance).funcs;
+        funcs.get(&u64::from(element)).copied()
+    }
+
+    /// Returns the function index, [`CompiledFunc`] and instruction offset of the
+    /// instruction at `ip` executed by the `instance`.
+    ///
+    /// Only the already compiled functions of the `instance` are looked up so that
+    /// locating an instruction never compiles a function. The instructions of
+    /// lazily compiled functions are indexed the first time one of them executes.
+    ///
+    /// Returns `None` if `ip` does not point into a function of the `instance`.
+    pub fn locate(
+        &mut self,
+        store: &StoreInner,
+        code_map: &CodeMap,
+        instance: &Instance,
+        ip: *const Instruction,
+    ) -> Option<(u32, CompiledFunc, u32)> {
+        let addr = ip as usize;
+        let (start, func) = match self.compiled_func_at(addr) {
+            Some(located) => located,
+            None => {
+                let funcs = &self.instance(store, instance).bodies;
+                let compiled = funcs.keys().filter_map(|&func| {
+                    let instrs = code_map.get_compiled(func)?.instrs().as_ptr_range();
+                    Some((instrs.start as usize, (instrs.end as usize, func)))
+                });
+                let compiled: Vec<_> = compiled.collect();
+                self.instrs.extend(compiled);
+                self.compiled_func_at(addr)?
+            }
+        };
+        let func_index = self.func_index(store, instance, func)?;
+        let pc = (addr - start) / mem::size_of::<Instruction>();
+        let pc = u32::try_from(pc).ok()?;
+        Some((func_index, func, pc))
+    }
+
+    /// Returns the address of the first instruction and the [`CompiledFunc`]
+    /// of the indexed instructions containing `addr`.
+    fn compiled_func_at(&self, addr: usize) -> Option<(usize, CompiledFunc)> {
+        let (&start, &(end, func)) = self.instrs.range(..=addr).next_back()?;
+        (addr < end).then_some((start, func))
+    }
+}
@@ -0,0 +1,64 @@
+//! Oracles for fuzzing the [`Tracer`] against the executor.
+
+use super::Tracer;
+use crate::{Engine, Linker, Module, Store, Val};
+use core::cell::RefCell;
+use std::{rc::Rc, vec::Vec};
+
+/// Calls the exported `func` of the Wasm module `wasm` with `params` and tracing
+/// and checks that the recorded trace is consistent.
+///
+/// Modules that fail to compile or instantiate as well as calls that trap are
+/// accepted since only the trace of the executed steps is checked.
+///
+/// # Panics
+///
+/// - If `func` is not an exported function of the module.
+/// - If the eids of the traced [`ETable`] are not consecutive.
+/// - If a read of the traced [`MTable`] does not observe the latest write
+///   of its function frame to the same location.
+///
+/// [`ETable`]: super::etable::ETable
+/// [`MTable`]: super::mtable::MTable
+pub fn assert_trace_consistent(wasm: &[u8], func: &str, params: &[Val]) {
+    let engine = Engine::default();
+    let Ok(module) = Module::new(&engine, &mut &wasm[..]) else {
+        return;
+    };
+    let mut store = Store::new(&engine, ());
+    let Ok(instance) = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .and_then(|instance| instance.start(&mut store))
+    else {
+        return;
+    };
+    let Some(func_ref) = instance.get_func(&store, func) else {
+        panic!("missing exported function: {func}")
+    };
+    let mut results = func_ref
+        .ty(&store)
+        .results()
+        .iter()
+        .copied()
+        .map(Val::default)
+        .collect::<Vec<_>>();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    // Traps are part of the traced behavior and thus are not an inconsistency.
+    _ = func_ref.call_with_trace(&mut store, params, &mut results, tracer.clone());
+    let tracer = tracer.take();
+    if let Err(error) = tracer.etable.validate_eids() {
+        panic!("inconsistent trace of {func}{params:?}: {error}");
+    }
+    if let Err(error) = tracer.get_mtable().validate() {
+        let step = tracer
+            .etable
+            .entries()
+            .iter()
+            .find(|entry| entry.eid == error.read.eid)
+            .expect("the inconsistent read must belong to a traced step");
+        panic!(
+            "inconsistent trace of {func}{params:?}: {error}\n    in step {}: {}",
+            step.eid, step.step_info
+        );
+    }
+}
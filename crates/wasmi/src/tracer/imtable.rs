@@ -1,9 +1,12 @@
+use crate::{core::UntypedVal, value::WithType, Val};
+use core::ops::Range;
 use std::vec::Vec;
 use wasmi_core::ValType;
 
 use super::mtable::LocationType;
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ValueType {
     I64,
     I32,
@@ -13,6 +16,80 @@ pub enum ValueType {
     ExternRef,
 }
 
+/// The width of the words of a linear memory recorded into an [`IMTable`].
+///
+/// The `Heap` accesses of the [`MTable`](super::mtable::MTable) address 8-byte words.
+/// Lookups of these accesses in an [`IMTable`] split each 8-byte word into the
+/// words of the granularity of the snapshot, or join them back respectively.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum MemoryGranularity {
+    /// Single bytes recorded as `i32` values.
+    Byte,
+    /// Little-endian 4-byte words recorded as `i32` values.
+    Word32,
+    /// Little-endian 8-byte words recorded as `i64` values.
+    #[default]
+    Word64,
+}
+
+impl MemoryGranularity {
+    /// Returns the number of bytes of a word.
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::Byte => 1,
+            Self::Word32 => 4,
+            Self::Word64 => 8,
+        }
+    }
+
+    /// Returns the number of words of a linear memory page.
+    pub fn words_per_page(self) -> u64 {
+        65536 / self.bytes() as u64
+    }
+
+    /// Returns the [`ValueType`] of the recorded words.
+    pub fn value_type(self) -> ValueType {
+        match self {
+            Self::Byte | Self::Word32 => ValueType::I32,
+            Self::Word64 => ValueType::I64,
+        }
+    }
+
+    /// Returns the offsets of the words within the 8-byte `Heap` word at `index`.
+    pub(crate) fn heap_word_offsets(self, index: usize) -> Range<usize> {
+        let words = 8 / self.bytes();
+        index * words..(index + 1) * words
+    }
+
+    /// Splits the `value` of an 8-byte `Heap` word into the values of its words.
+    ///
+    /// The values are in the order of [`MemoryGranularity::heap_word_offsets`].
+    pub(crate) fn split_heap_word(self, value: u64) -> impl Iterator<Item = u64> {
+        let bits = self.bytes() * 8;
+        let mask = u64::MAX >> (64 - bits);
+        (0..8 / self.bytes()).map(move |i| (value >> (i * bits)) & mask)
+    }
+
+    /// Joins the `values` of the words of an 8-byte `Heap` word into its value.
+    ///
+    /// This is the inverse of [`MemoryGranularity::split_heap_word`].
+    pub(crate) fn join_heap_word(self, values: impl IntoIterator<Item = u64>) -> u64 {
+        let bits = self.bytes() * 8;
+        let mask = u64::MAX >> (64 - bits);
+        values
+            .into_iter()
+            .enumerate()
+            .fold(0, |word, (i, value)| word | ((value & mask) << (i * bits)))
+    }
+
+    /// Returns the zero-extended little-endian value of the `word`.
+    pub(crate) fn word_value(word: &[u8]) -> u64 {
+        let mut bytes = [0x00_u8; 8];
+        bytes[..word.len()].copy_from_slice(word);
+        u64::from_le_bytes(bytes)
+    }
+}
+
 impl From<ValType> for ValueType {
     fn from(v: ValType) -> Self {
         match v {
@@ -27,6 +104,7 @@ impl From<ValType> for ValueType {
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IMTableEntry {
     pub ltype: LocationType,
     pub is_mutable: bool,
@@ -36,10 +114,55 @@ pub struct IMTableEntry {
     pub value: u64,
 }
 
+impl From<&ValueType> for ValType {
+    fn from(v: &ValueType) -> Self {
+        match v {
+            ValueType::I32 => Self::I32,
+            ValueType::I64 => Self::I64,
+            ValueType::F32 => Self::F32,
+            ValueType::F64 => Self::F64,
+            ValueType::FuncRef => Self::FuncRef,
+            ValueType::ExternRef => Self::ExternRef,
+        }
+    }
+}
+
+impl IMTableEntry {
+    /// Returns the initial value of the entry's locations interpreted as its `vtype`.
+    ///
+    /// A `value` of `0` is a `null` reference for the reference value types.
+    pub fn val(&self) -> Val {
+        UntypedVal::from(self.value).with_type(ValType::from(&self.vtype))
+    }
+}
+
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IMTable(Vec<IMTableEntry>);
 
 impl IMTable {
+    pub fn entries(&self) -> &Vec<IMTableEntry> {
+        &self.0
+    }
+
+    /// Returns the JSON encoding of the entries of the [`IMTable`].
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> std::string::String {
+        serde_json::to_string(self).expect("init memory tables always serialize to JSON")
+    }
+
+    /// Returns the entry initializing the `ltype` location at `offset` if any.
+    pub(crate) fn entry_at(&self, ltype: LocationType, offset: u32) -> Option<&IMTableEntry> {
+        self.0.iter().find(|init| {
+            init.ltype == ltype && (init.start_offset..=init.end_offset).contains(&offset)
+        })
+    }
+
+    /// Removes all entries while keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
     pub(crate) fn push(
         &mut self,
         is_global: bool,
@@ -48,13 +171,27 @@ impl IMTable {
         end_offset: u32,
         vtype: ValueType,
         value: u64,
+    ) {
+        let ltype = if is_global {
+            LocationType::Global
+        } else {
+            LocationType::Heap
+        };
+        self.push_location(ltype, is_mutable, start_offset, end_offset, vtype, value)
+    }
+
+    /// Pushes an entry initializing the `ltype` locations `start_offset..=end_offset` to `value`.
+    pub(crate) fn push_location(
+        &mut self,
+        ltype: LocationType,
+        is_mutable: bool,
+        start_offset: u32,
+        end_offset: u32,
+        vtype: ValueType,
+        value: u64,
     ) {
         self.0.push(IMTableEntry {
-            ltype: if is_global {
-                LocationType::Global
-            } else {
-                LocationType::Heap
-            },
+            ltype,
             is_mutable,
             start_offset,
             end_offset,
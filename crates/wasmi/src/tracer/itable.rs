@@ -0,0 +1,38 @@
+use std::vec::Vec;
+
+use crate::Val;
+
+/// The evaluation of the offset expression of an active element segment.
+#[derive(Debug, Clone)]
+pub struct ElemOffsetStep {
+    /// The index of the element segment within the module.
+    pub segment_index: u32,
+    /// The module local index of the table initialized by the element segment.
+    pub table_index: u32,
+    /// The store addresses and values of the globals read by the offset expression.
+    pub globals: Vec<(usize, Val)>,
+    /// The evaluated offset of the first element within the table.
+    pub offset: u32,
+}
+
+/// The evaluations of the offset expressions of the active element segments of a module.
+#[derive(Debug, Default)]
+pub struct ITable {
+    steps: Vec<ElemOffsetStep>,
+}
+
+impl ITable {
+    /// Returns the traced evaluations of the element segment offsets.
+    pub fn steps(&self) -> &Vec<ElemOffsetStep> {
+        &self.steps
+    }
+
+    /// Removes all steps while keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.steps.clear();
+    }
+
+    pub(crate) fn push_step(&mut self, step: ElemOffsetStep) {
+        self.steps.push(step);
+    }
+}
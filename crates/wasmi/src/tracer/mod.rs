@@ -1,20 +1,247 @@
+pub use self::{
+    config::TraceConfig,
+    sink::{CallbackSink, TraceSink, VecSink},
+};
 use self::{
-    etable::ETable,
-    imtable::{IMTable, ValueType},
-    mtable::{memory_event_of_step, MTable},
+    etable::{CustomStepInfo, ETable, ETableEntry, StepInfo},
+    imtable::{IMTable, MemoryGranularity, ValueType},
+    funcs::FuncIndices,
+    itable::{ElemOffsetStep, ITable},
+    mtable::{
+        memory_event_of_step,
+        memory_events_of_steps,
+        AccessType,
+        LocationType,
+        MTable,
+        MemoryTableEntry,
+    },
+};
+use crate::{
+    engine::bytecode::Instruction,
+    errors::MemoryError,
+    module::{DataSegmentKind, ElementSegmentKind, DEFAULT_MEMORY_INDEX},
+    value::WithType,
+    AsContext,
+    Error,
+    Global,
+    Instance,
+    Memory,
+    Module,
+    Table,
+    Val,
+};
+use core::{
+    cell::{RefCell, RefMut},
+    fmt::{self, Debug},
+    ops::{Deref, DerefMut},
 };
-use crate::{AsContext, Global, Memory};
-use std::vec::Vec;
-use wasmi_core::UntypedVal;
+use std::{
+    boxed::Box,
+    collections::{BTreeMap, BTreeSet},
+    rc::Rc,
+    string::String,
+    sync::Arc,
+    vec::Vec,
+};
+use wasmi_core::{UntypedVal, ValType};
 
+pub mod compact;
+mod config;
 pub mod etable;
+pub mod format;
+mod funcs;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod fuzzing;
 pub mod imtable;
+pub mod itable;
 pub mod mtable;
+mod sink;
+
+#[cfg(test)]
+mod tests;
+
+/// A callback invoked with the old and new number of pages of a grown memory.
+type GrowCallback = Box<dyn FnMut(u32, u32) + Send>;
+
+/// A shared [`Tracer`] recording the steps of a traced execution.
+///
+/// Created from an `Rc<RefCell<Tracer>>` for single threaded use or from an
+/// `Arc<Mutex<Tracer>>` for traced executions that run on other threads.
+#[derive(Debug, Clone)]
+pub struct TracerHandle {
+    inner: TracerHandleInner,
+}
+
+#[derive(Debug, Clone)]
+enum TracerHandleInner {
+    Local(Rc<RefCell<Tracer>>),
+    #[cfg(feature = "std")]
+    Shared(Arc<std::sync::Mutex<Tracer>>),
+}
+
+impl From<Rc<RefCell<Tracer>>> for TracerHandle {
+    fn from(tracer: Rc<RefCell<Tracer>>) -> Self {
+        Self {
+            inner: TracerHandleInner::Local(tracer),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Arc<std::sync::Mutex<Tracer>>> for TracerHandle {
+    fn from(tracer: Arc<std::sync::Mutex<Tracer>>) -> Self {
+        Self {
+            inner: TracerHandleInner::Shared(tracer),
+        }
+    }
+}
+
+impl TracerHandle {
+    /// Gives exclusive access to the [`Tracer`] until the returned guard is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If the `Rc<RefCell<Tracer>>` of the [`TracerHandle`] is already borrowed.
+    pub(crate) fn lock(&self) -> TracerGuard<'_> {
+        match &self.inner {
+            TracerHandleInner::Local(tracer) => TracerGuard::Local(tracer.borrow_mut()),
+            #[cfg(feature = "std")]
+            TracerHandleInner::Shared(tracer) => TracerGuard::Shared(
+                tracer
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner),
+            ),
+        }
+    }
+}
+
+/// The exclusive access to the [`Tracer`] of a [`TracerHandle`].
+pub(crate) enum TracerGuard<'a> {
+    Local(RefMut<'a, Tracer>),
+    #[cfg(feature = "std")]
+    Shared(std::sync::MutexGuard<'a, Tracer>),
+}
+
+impl Deref for TracerGuard<'_> {
+    type Target = Tracer;
+
+    fn deref(&self) -> &Tracer {
+        match self {
+            TracerGuard::Local(tracer) => tracer,
+            #[cfg(feature = "std")]
+            TracerGuard::Shared(tracer) => tracer,
+        }
+    }
+}
+
+impl DerefMut for TracerGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Tracer {
+        match self {
+            TracerGuard::Local(tracer) => tracer,
+            #[cfg(feature = "std")]
+            TracerGuard::Shared(tracer) => tracer,
+        }
+    }
+}
+
+/// An error stopping a traced execution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TraceError {
+    /// The traced executions already executed the `max_steps` set by [`Tracer::max_steps`].
+    StepLimitExceeded { max_steps: u64 },
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::StepLimitExceeded { max_steps } => {
+                write!(f, "traced execution exceeded {max_steps} steps")
+            }
+        }
+    }
+}
 
-#[derive(Debug)]
 pub struct Tracer {
     pub imtable: IMTable,
+    pub itable: ITable,
     pub etable: ETable,
+    /// The categories of steps recorded by the [`Tracer`].
+    config: TraceConfig,
+    /// Whether [`Tracer::get_mtable`] skips [`AccessType::Read`] entries.
+    write_only: bool,
+    /// The function names attached to traced call steps.
+    func_names: BTreeMap<u32, String>,
+    /// The [`MTable`] of a trace rebuilt by [`Tracer::from_tables`].
+    ///
+    /// Takes precedence over the [`MTable`] derived from the [`ETable`].
+    mtable: Option<MTable>,
+    /// Whether local sets capture the overwritten value of the local.
+    prev_value: bool,
+    /// Whether only the final memory and globals are traced.
+    output_snapshot: bool,
+    /// The granularity of the memory words recorded by [`Tracer::push_init_memory`].
+    granularity: MemoryGranularity,
+    /// The `Heap` and `Global` locations accessed in [`Tracer::lazy_init_mode`].
+    ///
+    /// This is `None` if the [`Tracer`] is not in lazy init mode.
+    lazy_init: Option<BTreeSet<(LocationType, usize)>>,
+    /// The final memory and globals captured in output snapshot mode.
+    final_state: Option<IMTable>,
+    /// The first `Data` address of each passive data segment by segment index.
+    passive_data: BTreeMap<u32, u32>,
+    /// The first `Element` address of each passive element segment by segment index.
+    passive_elements: BTreeMap<u32, u32>,
+    /// The first `Table` address of each recorded table by table index.
+    tables: BTreeMap<u32, u32>,
+    /// The index of the only function whose steps are traced.
+    only_func: Option<u32>,
+    /// The maximum number of instructions executed under the [`Tracer`].
+    max_steps: Option<u64>,
+    /// The number of instructions executed under the [`Tracer`].
+    executed_steps: u64,
+    /// The callback set by [`Tracer::set_grow_cb`].
+    grow_cb: Option<GrowCallback>,
+    /// The function indices of the functions of the traced instances.
+    func_indices: FuncIndices,
+    /// The parameters passed to the traced root function.
+    root_params: Vec<Val>,
+    /// The result types of the traced root function.
+    root_results: Vec<ValType>,
+    /// The stream set by [`Tracer::stream_mtable_csv`].
+    #[cfg(feature = "std")]
+    mtable_csv: Option<mtable::CsvStream>,
+}
+
+impl Debug for Tracer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Tracer");
+        debug
+            .field("imtable", &self.imtable)
+            .field("itable", &self.itable)
+            .field("etable", &self.etable)
+            .field("config", &self.config)
+            .field("write_only", &self.write_only)
+            .field("func_names", &self.func_names)
+            .field("mtable", &self.mtable)
+            .field("prev_value", &self.prev_value)
+            .field("output_snapshot", &self.output_snapshot)
+            .field("granularity", &self.granularity)
+            .field("lazy_init", &self.lazy_init)
+            .field("final_state", &self.final_state)
+            .field("passive_data", &self.passive_data)
+            .field("passive_elements", &self.passive_elements)
+            .field("tables", &self.tables)
+            .field("only_func", &self.only_func)
+            .field("max_steps", &self.max_steps)
+            .field("executed_steps", &self.executed_steps)
+            .field("grow_cb", &self.grow_cb.is_some())
+            .field("func_indices", &self.func_indices)
+            .field("root_params", &self.root_params)
+            .field("root_results", &self.root_results);
+        #[cfg(feature = "std")]
+        debug.field("mtable_csv", &self.mtable_csv.is_some());
+        debug.finish()
+    }
 }
 
 impl Default for Tracer {
@@ -27,44 +254,752 @@ impl Tracer {
     pub fn new() -> Self {
         Tracer {
             imtable: IMTable::default(),
+            itable: ITable::default(),
             etable: ETable::default(),
+            config: TraceConfig::default(),
+            write_only: false,
+            func_names: BTreeMap::new(),
+            mtable: None,
+            prev_value: false,
+            output_snapshot: false,
+            granularity: MemoryGranularity::default(),
+            lazy_init: None,
+            final_state: None,
+            passive_data: BTreeMap::new(),
+            passive_elements: BTreeMap::new(),
+            tables: BTreeMap::new(),
+            only_func: None,
+            max_steps: None,
+            executed_steps: 0,
+            grow_cb: None,
+            func_indices: FuncIndices::default(),
+            root_params: Vec::new(),
+            root_results: Vec::new(),
+            #[cfg(feature = "std")]
+            mtable_csv: None,
         }
     }
 
-    pub fn push_init_memory(&mut self, mem_ref: Memory, context: impl AsContext) {
-        let pages: u32 = mem_ref.ty(&context).initial_pages().into();
-        for i in 0..(pages * 8192) {
-            let mut buf = [0u8; 8];
-            mem_ref
-                .read(&context, (i * 8).try_into().unwrap(), &mut buf)
-                .unwrap();
-            self.imtable
-                .push(false, true, i, i, ValueType::I64, u64::from_le_bytes(buf));
+    /// Creates a [`Tracer`] that only records the step categories enabled by `config`.
+    pub fn with_config(config: TraceConfig) -> Self {
+        Tracer {
+            config,
+            ..Tracer::new()
         }
+    }
 
-        let max_pages = mem_ref.ty(&context).maximum_pages();
-        self.imtable.push(
-            false,
-            true,
-            pages * 8192,
-            max_pages
-                .map(|limit| u32::from(limit) * 8192 - 1)
-                .unwrap_or(u32::MAX),
-            ValueType::I64,
-            0,
+    /// Returns the function indices of the functions of the traced instances.
+    pub(crate) fn func_indices(&mut self) -> &mut FuncIndices {
+        &mut self.func_indices
+    }
+
+    /// Returns `true` if the step of the executed `instruction` is recorded.
+    pub(crate) fn records(&self, instruction: &Instruction) -> bool {
+        self.config.records(instruction)
+    }
+
+    /// Rebuilds a [`Tracer`] from the tables of a previously recorded trace.
+    ///
+    /// The analysis methods of the [`Tracer`] then operate on the given tables
+    /// and newly traced steps continue after the last eid of the `etable`.
+    pub fn from_tables(etable: ETable, mtable: MTable, imtable: IMTable) -> Self {
+        Tracer {
+            imtable,
+            etable,
+            mtable: Some(mtable),
+            ..Tracer::new()
+        }
+    }
+
+    /// Splits the [`Tracer`] into its execution, memory and init memory tables.
+    ///
+    /// This is the inverse of [`Tracer::from_tables`].
+    pub fn into_tables(self) -> (ETable, MTable, IMTable) {
+        let mtable = self.get_mtable();
+        (self.etable, mtable, self.imtable)
+    }
+
+    /// Discards the recorded trace so that the [`Tracer`] can record another one.
+    ///
+    /// The tables keep their allocated capacity and the steps of the next
+    /// trace start at eid `1` again. The modes, function names and
+    /// breakpoints of the [`Tracer`] are kept.
+    pub fn reset(&mut self) {
+        self.etable.clear();
+        self.imtable.clear();
+        self.itable.clear();
+        self.mtable = None;
+        self.final_state = None;
+        if let Some(accessed) = &mut self.lazy_init {
+            accessed.clear();
+        }
+        self.passive_data.clear();
+        self.passive_elements.clear();
+        self.tables.clear();
+        self.root_params.clear();
+        self.root_results.clear();
+        self.executed_steps = 0;
+    }
+
+    /// Makes the [`Tracer`] emit only `Write` and `Init` entries into its [`MTable`].
+    ///
+    /// This is useful for tools that only need the set of memory mutations
+    /// since the final memory state can be reconstructed from writes alone.
+    pub fn write_only_mode(mut self) -> Self {
+        self.write_only = true;
+        self
+    }
+
+    /// Makes the [`Tracer`] capture the value overwritten by each local set.
+    ///
+    /// The overwritten value is recorded as the `prev_value` of the
+    /// [`StepInfo::LocalOp`](etable::StepInfo::LocalOp) and read before the
+    /// write of the new value, giving the [`MTable`] read-modify-write semantics.
+    pub fn prev_value_mode(mut self) -> Self {
+        self.prev_value = true;
+        self
+    }
+
+    /// Returns `true` if the [`Tracer`] is in [`Tracer::prev_value_mode`].
+    pub(crate) fn is_prev_value_mode(&self) -> bool {
+        self.prev_value
+    }
+
+    /// Makes the [`Tracer`] skip all step tracing and only capture the final state.
+    ///
+    /// After the traced function returned, the values of the default linear memory
+    /// and the globals of its instance are available via [`Tracer::final_state`].
+    /// Together with the [`IMTable`] this forms a commitment to the input and output.
+    pub fn output_snapshot_mode(mut self) -> Self {
+        self.output_snapshot = true;
+        self
+    }
+
+    /// Makes the [`Tracer`] record initial values into its [`IMTable`] on first access.
+    ///
+    /// Instead of snapshotting the memory and globals upfront, e.g. via
+    /// [`Tracer::push_init_memory`], each `Heap` and `Global` location whose
+    /// first traced access is a read gets an [`IMTable`] entry holding the read value.
+    /// Locations that are written first need no initial value. This keeps the
+    /// [`IMTable`] small for programs that touch little of a large memory.
+    pub fn lazy_init_mode(mut self) -> Self {
+        self.lazy_init = Some(BTreeSet::new());
+        self
+    }
+
+    /// Returns `true` if the [`Tracer`] is in [`Tracer::output_snapshot_mode`].
+    pub(crate) fn is_output_snapshot_mode(&self) -> bool {
+        self.output_snapshot
+    }
+
+    /// Makes the [`Tracer`] only trace the steps of the function at `func_index`.
+    ///
+    /// The `func_index` refers to the functions of the executing instance.
+    /// Steps executed by other functions, including the callers and callees
+    /// of the function, are skipped. Calls into the function are thus not
+    /// traced while its returns are.
+    ///
+    /// # Note
+    ///
+    /// Looking up the function of every executed instruction slows down tracing.
+    pub fn only_func(mut self, func_index: u32) -> Self {
+        self.only_func = Some(func_index);
+        self
+    }
+
+    /// Returns the index of the function selected by [`Tracer::only_func`] if any.
+    pub(crate) fn traced_func(&self) -> Option<u32> {
+        self.only_func
+    }
+
+    /// Passes the steps of traced executions to the `sink` instead of buffering them.
+    ///
+    /// The [`ETable`] then only retains the last step, so that the tables derived
+    /// from it, such as [`Tracer::get_mtable`], only cover that step. Use
+    /// [`Tracer::stream_mtable_csv`] to stream the [`MTable`] rows alongside.
+    pub fn with_sink(mut self, sink: impl TraceSink + 'static) -> Self {
+        self.etable.set_sink(sink);
+        self
+    }
+
+    /// Stops traced executions once they executed `max_steps` instructions.
+    ///
+    /// The execution then fails with [`TraceError::StepLimitExceeded`] before
+    /// executing the instruction that would exceed the limit. Instructions count
+    /// whether or not their steps are recorded, e.g. due to the [`TraceConfig`],
+    /// so this also stops non-terminating executions of unrecorded instructions.
+    pub fn max_steps(mut self, max_steps: u64) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Counts the next executed instruction.
+    ///
+    /// # Errors
+    ///
+    /// If executing another instruction exceeds [`Tracer::max_steps`].
+    pub(crate) fn check_step_limit(&mut self) -> Result<(), TraceError> {
+        match self.max_steps {
+            Some(max_steps) if self.executed_steps >= max_steps => {
+                Err(TraceError::StepLimitExceeded { max_steps })
+            }
+            _ => {
+                self.executed_steps += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the final memory and globals captured in [`Tracer::output_snapshot_mode`].
+    ///
+    /// Returns `None` if not in output snapshot mode or if the traced function
+    /// has not returned, e.g. since it trapped.
+    pub fn final_state(&self) -> Option<&IMTable> {
+        self.final_state.as_ref()
+    }
+
+    /// Records the `params` and the types of the `results` of the traced root function.
+    pub(crate) fn set_root_call(&mut self, params: &[Val], results: &[Val]) {
+        self.root_params = params.to_vec();
+        self.root_results = results.iter().map(Val::ty).collect();
+    }
+
+    /// Returns the parameters and results of the traced root function.
+    ///
+    /// If several calls were traced into the [`Tracer`] this refers to the last call.
+    /// The results are the values of the final [`StepInfo::Return`] of the trace.
+    /// They are empty if the trace does not end with a return, e.g. since the
+    /// root function trapped or is a host function.
+    pub fn io_boundary(&self) -> (Vec<Val>, Vec<Val>) {
+        let results = match self.etable.entries().last().map(|entry| &entry.step_info) {
+            Some(StepInfo::Return { values, .. }) => values
+                .iter()
+                .zip(&self.root_results)
+                .map(|(value, ty)| UntypedVal::from(value.val.clone()).with_type(*ty))
+                .collect(),
+            _ => Vec::new(),
+        };
+        (self.root_params.clone(), results)
+    }
+
+    /// Streams the [`MTable`] rows of all further traced steps as CSV into the file at `path`.
+    ///
+    /// The file is created and its [`CSV_HEADER`](mtable::CSV_HEADER) written immediately.
+    /// Each traced step then appends its rows as encoded by [`MTable::to_csv`], so
+    /// the rows of long runs need not be derived from the [`ETable`] at once.
+    /// Call [`Tracer::finish_mtable_csv`] to flush the file once tracing is done.
+    ///
+    /// # Errors
+    ///
+    /// If the file cannot be created or written.
+    #[cfg(feature = "std")]
+    pub fn stream_mtable_csv(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.mtable_csv = Some(mtable::CsvStream::create(path.as_ref())?);
+        Ok(())
+    }
+
+    /// Stops the stream set by [`Tracer::stream_mtable_csv`] and flushes its file.
+    ///
+    /// Does nothing if no stream is set.
+    ///
+    /// # Errors
+    ///
+    /// If writing any of the streamed rows failed.
+    #[cfg(feature = "std")]
+    pub fn finish_mtable_csv(&mut self) -> std::io::Result<()> {
+        match self.mtable_csv.take() {
+            Some(stream) => stream.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Appends the `step` of the instruction at `pc` of the function at `func_index`
+    /// executed with `allocated_memory_pages` to the [`ETable`].
+    pub(crate) fn push_step(
+        &mut self,
+        func_index: u32,
+        pc: u32,
+        allocated_memory_pages: u32,
+        step: StepInfo,
+    ) {
+        self.etable
+            .push_at(func_index, pc, allocated_memory_pages, step);
+        let entry = self
+            .etable
+            .entries()
+            .last()
+            .expect("a step was just pushed");
+        #[cfg(feature = "std")]
+        if let Some(stream) = &mut self.mtable_csv {
+            stream.push(entry, self.write_only);
+        }
+        if let Some(accessed) = &mut self.lazy_init {
+            Self::push_lazy_init(&mut self.imtable, self.granularity, accessed, entry);
+        }
+    }
+
+    /// Records the values read by the first accesses of the `entry` into `imtable`.
+    ///
+    /// `Heap` words are recorded in words of `granularity`.
+    /// See [`Tracer::lazy_init_mode`].
+    fn push_lazy_init(
+        imtable: &mut IMTable,
+        granularity: MemoryGranularity,
+        accessed: &mut BTreeSet<(LocationType, usize)>,
+        entry: &ETableEntry,
+    ) {
+        for event in memory_event_of_step(entry, &mut 0) {
+            if !matches!(event.ltype, LocationType::Heap | LocationType::Global) {
+                continue;
+            }
+            if !accessed.insert((event.ltype, event.addr)) || event.atype != AccessType::Read {
+                continue;
+            }
+            let bits = UntypedVal::from(event.value.clone()).to_bits();
+            if event.ltype == LocationType::Heap {
+                let offsets = granularity.heap_word_offsets(event.addr);
+                for (offset, word) in offsets.zip(granularity.split_heap_word(bits)) {
+                    let offset = u32::try_from(offset).expect("address must fit into u32");
+                    imtable.push_location(
+                        event.ltype,
+                        event.is_mutable,
+                        offset,
+                        offset,
+                        granularity.value_type(),
+                        word,
+                    );
+                }
+                continue;
+            }
+            let offset = u32::try_from(event.addr).expect("address must fit into u32");
+            imtable.push_location(
+                event.ltype,
+                event.is_mutable,
+                offset,
+                offset,
+                event.value.ty().into(),
+                bits,
+            );
+        }
+    }
+
+    /// Records a [`CustomStepInfo`] as the next step of the trace.
+    ///
+    /// This allows code outside of the executor, such as host functions
+    /// emulating custom instructions, to extend the trace vocabulary.
+    /// The `allocated_memory_pages` are the number of pages of the default
+    /// linear memory when the step is executed. The step shares the location
+    /// of the previous step, e.g. the call of the host function pushing it.
+    pub fn push_custom_step(
+        &mut self,
+        allocated_memory_pages: u32,
+        step: impl CustomStepInfo + 'static,
+    ) {
+        let (func_index, pc) = self.etable.last_location();
+        self.push_step(
+            func_index,
+            pc,
+            allocated_memory_pages,
+            StepInfo::Custom(Arc::new(step)),
         );
     }
 
+    /// Calls `breakpoint` with the traced step at `eid` once it is reached.
+    ///
+    /// See [`ETable::set_breakpoint`].
+    pub fn set_breakpoint(
+        &mut self,
+        eid: u64,
+        breakpoint: impl FnMut(&ETableEntry) + Send + 'static,
+    ) {
+        self.etable.set_breakpoint(eid, breakpoint);
+    }
+
+    /// Makes the [`Tracer`] attach the function names of the `module` to traced call steps.
+    ///
+    /// The names are taken from the `name` custom section of the `module`
+    /// and are resolved by the function indices of the `module`.
+    pub fn with_func_names(mut self, module: &Module) -> Self {
+        self.func_names = module
+            .func_names()
+            .map(|(func_idx, name)| (func_idx, String::from(name)))
+            .collect();
+        self
+    }
+
+    /// Returns the name of the function at `func_idx` if known.
+    pub(crate) fn func_name(&self, func_idx: u32) -> Option<&str> {
+        self.func_names.get(&func_idx).map(String::as_str)
+    }
+
+    /// Records the initial words of the linear memory `mem_ref` in words of `granularity`.
+    ///
+    /// Non-zero words of the initial pages are recorded one entry per word.
+    /// Runs of zero words are collapsed into a single entry spanning the run.
+    /// The trailing run of zero words extends to the maximum size of the memory.
+    /// Pages grown by traced steps are recorded in words of the same `granularity`.
+    /// The 8-byte `Heap` words of the [`MTable`] are translated to this `granularity`
+    /// by [`Tracer::get_mtable_with_init`] and [`Tracer::derive_next_imtable`].
+    ///
+    /// # Errors
+    ///
+    /// If the initial pages of `mem_ref` exceed its data. Nothing is recorded then.
+    pub fn push_init_memory(
+        &mut self,
+        mem_ref: Memory,
+        granularity: MemoryGranularity,
+        context: impl AsContext,
+    ) -> Result<(), Error> {
+        let ty = mem_ref.ty(&context);
+        let max_pages = ty.maximum_pages().map(u32::from);
+        self.push_init_words(
+            mem_ref.data(&context),
+            ty.initial_pages().into(),
+            max_pages,
+            granularity,
+        )
+    }
+
+    /// Records the words of the first `pages` pages of the linear memory `data`.
+    ///
+    /// See [`Tracer::push_init_memory`].
+    ///
+    /// # Errors
+    ///
+    /// If `data` holds fewer than `pages` pages. Nothing is recorded then.
+    fn push_init_words(
+        &mut self,
+        data: &[u8],
+        pages: u32,
+        max_pages: Option<u32>,
+        granularity: MemoryGranularity,
+    ) -> Result<(), Error> {
+        let words = data
+            .get(..pages as usize * 65536)
+            .ok_or(MemoryError::OutOfBoundsAccess)?
+            .chunks_exact(granularity.bytes());
+        self.granularity = granularity;
+        let vtype = granularity.value_type();
+        // The first word of the current run of zero words.
+        let mut zeros = None;
+        for (i, word) in words.enumerate() {
+            let i = u32::try_from(i).expect("word index must fit into u32");
+            let word = MemoryGranularity::word_value(word);
+            if word == 0 {
+                zeros.get_or_insert(i);
+                continue;
+            }
+            if let Some(start) = zeros.take() {
+                self.imtable.push(false, true, start, i - 1, vtype, 0);
+            }
+            self.imtable.push(false, true, i, i, vtype, word);
+        }
+
+        let per_page = granularity.words_per_page();
+        let start = zeros.map_or(u64::from(pages) * per_page, u64::from);
+        let end = match max_pages {
+            Some(limit) => (u64::from(limit) * per_page).checked_sub(1),
+            None => Some(u64::from(u32::MAX)),
+        };
+        // A memory of 4 GiB in bytes or with a maximum of zero pages
+        // leaves no trailing run behind its initial pages.
+        if let Some(end) = end.filter(|&end| start <= end) {
+            self.imtable.push(
+                false,
+                true,
+                u32::try_from(start).expect("word index must fit into u32"),
+                u32::try_from(end).expect("word index must fit into u32"),
+                vtype,
+                0,
+            );
+        }
+        Ok(())
+    }
+
+    /// Records the zero-initialized words of the default linear memory grown
+    /// from `old_pages` to `new_pages` pages.
+    ///
+    /// The words have the granularity of the last [`Tracer::push_init_memory`].
+    /// In [`Tracer::lazy_init_mode`] the words are only recorded once read.
+    pub(crate) fn push_grown_memory(&mut self, old_pages: u32, new_pages: u32) {
+        if self.lazy_init.is_none() {
+            let per_page = self.granularity.words_per_page();
+            let word_index = |pages: u32| u64::from(pages) * per_page;
+            self.imtable.push(
+                false,
+                true,
+                u32::try_from(word_index(old_pages)).expect("word index must fit into u32"),
+                u32::try_from(word_index(new_pages) - 1).expect("word index must fit into u32"),
+                self.granularity.value_type(),
+                0,
+            );
+        }
+        if let Some(grow_cb) = &mut self.grow_cb {
+            grow_cb(old_pages, new_pages);
+        }
+    }
+
+    /// Calls `grow_cb` with the old and new number of pages whenever a traced
+    /// step grows the default linear memory.
+    ///
+    /// This allows tools to resize a mirror of the memory as soon as it grows.
+    /// Replaces the previously set callback.
+    pub fn set_grow_cb(&mut self, grow_cb: impl FnMut(u32, u32) + Send + 'static) {
+        self.grow_cb = Some(Box::new(grow_cb));
+    }
+
+    /// Records the current value of the `global` as its initial value.
+    ///
+    /// Globals are addressed by their index within the [`Store`] so that accesses
+    /// through imports of other instances refer to the same location.
+    ///
+    /// [`Store`]: crate::Store
+    pub fn push_init_global(&mut self, global: &Global, context: impl AsContext) {
+        let address = context.as_context().store.inner.global_address(global);
+        let address = u32::try_from(address).expect("global address must fit into u32");
+        self.push_global(address, global, context)
+    }
+
+    /// Records the current values of all globals of the `instance` as their initial values.
+    ///
+    /// This covers the globals defined by the `instance` as well as its imported
+    /// globals, see [`Tracer::push_init_global`]. Globals whose store address
+    /// already has an initial value, e.g. since they are shared with another
+    /// recorded instance, are skipped.
+    pub fn push_init_globals(&mut self, instance: &Instance, context: impl AsContext) {
+        let context = context.as_context();
+        let entity = context.store.inner.resolve_instance(instance);
+        for global in (0..).map_while(|index| entity.get_global(index)) {
+            let address = context.store.inner.global_address(&global);
+            let address = u32::try_from(address).expect("global address must fit into u32");
+            let recorded = self
+                .imtable
+                .entries()
+                .iter()
+                .filter(|entry| entry.ltype == LocationType::Global)
+                .any(|entry| entry.start_offset == address);
+            if !recorded {
+                self.push_global(address, &global, &context);
+            }
+        }
+    }
+
+    /// Records the current values of the default linear memory and the globals
+    /// of `instance` as the final state of the traced execution.
+    pub(crate) fn push_final_state(&mut self, instance: &Instance, context: impl AsContext) {
+        let context = context.as_context();
+        let entity = context.store.inner.resolve_instance(instance);
+        let mut final_state = IMTable::default();
+        if let Some(memory) = entity.get_memory(DEFAULT_MEMORY_INDEX) {
+            for (i, word) in memory.data(&context).chunks_exact(8).enumerate() {
+                let i = u32::try_from(i).expect("word index must fit into u32");
+                let word = u64::from_le_bytes(word.try_into().expect("word must have 8 bytes"));
+                final_state.push(false, true, i, i, ValueType::I64, word);
+            }
+        }
+        for global in (0..).map_while(|index| entity.get_global(index)) {
+            let address = context.store.inner.global_address(&global);
+            let address = u32::try_from(address).expect("global address must fit into u32");
+            Self::push_global_into(&mut final_state, address, &global, &context);
+        }
+        self.final_state = Some(final_state);
+    }
+
+    /// Records the evaluation of the offset expressions of the active element segments
+    /// of the `module`.
+    ///
+    /// The offset expressions of the segments are evaluated against the globals of
+    /// the `instance` of the `module` and recorded as [`ElemOffsetStep`]s.
+    /// The elements the segments seeded at these offsets are recorded by
+    /// [`Tracer::push_init_table`].
+    pub fn push_elem_offsets(
+        &mut self,
+        module: &Module,
+        instance: &Instance,
+        context: impl AsContext,
+    ) {
+        let store = &context.as_context().store.inner;
+        let entity = store.resolve_instance(instance);
+        for (segment_index, segment) in (0..).zip(module.element_segments()) {
+            let ElementSegmentKind::Active(active) = segment.kind() else {
+                continue;
+            };
+            let globals = RefCell::new(Vec::new());
+            let offset = active
+                .offset()
+                .eval_with_context(
+                    |global_index| {
+                        let global = entity
+                            .get_global(global_index)
+                            .expect("offset expression must refer to an existing global");
+                        let value = store.resolve_global(&global).get();
+                        globals
+                            .borrow_mut()
+                            .push((store.global_address(&global), value.clone()));
+                        value
+                    },
+                    |_| unreachable!("offset expressions cannot refer to functions"),
+                )
+                .map(u32::from)
+                .expect("offset expression must evaluate to a value");
+            self.itable.push_step(ElemOffsetStep {
+                segment_index,
+                table_index: active.table_index().into_u32(),
+                globals: globals.into_inner(),
+                offset,
+            });
+        }
+    }
+
+    /// Records the contents of the passive data and element segments of the `module`.
+    ///
+    /// Passive segments are laid out one after another in segment index order.
+    /// Each data segment occupies the `Data` words holding its bytes, padded
+    /// with zeros to a whole word, and each element segment occupies one `Element`
+    /// location per item holding its function index or `u64::MAX` if it is `null`.
+    /// The first address of each segment is returned by [`Tracer::passive_data_address`]
+    /// and [`Tracer::passive_element_address`] respectively.
+    pub fn push_passive_segments(&mut self, module: &Module) {
+        let mut address = 0;
+        for (segment_index, segment) in (0..).zip(module.data_segments()) {
+            let DataSegmentKind::Passive = segment.kind() else {
+                continue;
+            };
+            self.passive_data.insert(segment_index, address);
+            for chunk in segment.bytes().chunks(8) {
+                let mut word = [0x00_u8; 8];
+                word[..chunk.len()].copy_from_slice(chunk);
+                self.imtable.push_location(
+                    LocationType::Data,
+                    false,
+                    address,
+                    address,
+                    ValueType::I64,
+                    u64::from_le_bytes(word),
+                );
+                address += 1;
+            }
+        }
+        let mut address = 0;
+        for (segment_index, segment) in (0..).zip(module.element_segments()) {
+            let ElementSegmentKind::Passive = segment.kind() else {
+                continue;
+            };
+            self.passive_elements.insert(segment_index, address);
+            for item in segment.items_cloned().items() {
+                let value = item
+                    .funcref()
+                    .map_or(u64::MAX, |func_index| u64::from(func_index.into_u32()));
+                self.imtable.push_location(
+                    LocationType::Element,
+                    false,
+                    address,
+                    address,
+                    segment.ty().into(),
+                    value,
+                );
+                address += 1;
+            }
+        }
+    }
+
+    /// Returns the `Data` address of the first word of the passive data segment
+    /// at `segment_index`.
+    ///
+    /// Returns `None` if the segment is not a passive segment recorded by
+    /// [`Tracer::push_passive_segments`].
+    pub fn passive_data_address(&self, segment_index: u32) -> Option<u32> {
+        self.passive_data.get(&segment_index).copied()
+    }
+
+    /// Returns the `Element` address of the first item of the passive element segment
+    /// at `segment_index`.
+    ///
+    /// Returns `None` if the segment is not a passive segment recorded by
+    /// [`Tracer::push_passive_segments`].
+    pub fn passive_element_address(&self, segment_index: u32) -> Option<u32> {
+        self.passive_elements.get(&segment_index).copied()
+    }
+
+    /// Records the current elements of the `table_ref` at `table_idx` into the [`IMTable`].
+    ///
+    /// Recorded tables are laid out one after another in recording order, with one
+    /// `Table` location per element. References are recorded by their canonical
+    /// untyped encoding, like the values of globals. Runs of equal elements are
+    /// collapsed into a single entry spanning the run. The first address of the
+    /// table is returned by [`Tracer::table_address`].
+    ///
+    /// Recording the same `table_idx` again does nothing.
+    pub fn push_init_table(&mut self, table_idx: u32, table_ref: &Table, context: impl AsContext) {
+        if self.tables.contains_key(&table_idx) {
+            return;
+        }
+        let base = self
+            .imtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.ltype == LocationType::Table)
+            .map(|entry| entry.end_offset + 1)
+            .max()
+            .unwrap_or(0);
+        self.tables.insert(table_idx, base);
+        let vtype = table_ref.ty(&context).element();
+        let values = (0..table_ref.size(&context)).map(|index| {
+            let value = table_ref
+                .get(&context, index)
+                .expect("index must be within the table bounds");
+            UntypedVal::from(value).to_bits()
+        });
+        let mut runs = Vec::<(u32, u32, u64)>::new();
+        for (index, value) in (0..).zip(values) {
+            match runs.last_mut() {
+                Some((_, end, run)) if *run == value => *end = index,
+                _ => runs.push((index, index, value)),
+            }
+        }
+        for (start, end, value) in runs {
+            self.imtable.push_location(
+                LocationType::Table,
+                true,
+                base + start,
+                base + end,
+                vtype.into(),
+                value,
+            );
+        }
+    }
+
+    /// Returns the `Table` address of the first element of the table at `table_index`.
+    ///
+    /// Returns `None` if the table was not recorded by [`Tracer::push_init_table`].
+    pub fn table_address(&self, table_index: u32) -> Option<u32> {
+        self.tables.get(&table_index).copied()
+    }
+
     pub(crate) fn push_global(
         &mut self,
         global_idx: u32,
         global_ref: &Global,
         context: impl AsContext,
+    ) {
+        Self::push_global_into(&mut self.imtable, global_idx, global_ref, context)
+    }
+
+    /// Records the current value of the global at `global_idx` into `imtable`.
+    ///
+    /// References are recorded by their canonical untyped encoding which is `0`
+    /// for `null` references. The recorded value type tells them apart from a
+    /// zero integer, see [`IMTableEntry::val`](imtable::IMTableEntry::val).
+    fn push_global_into(
+        imtable: &mut IMTable,
+        global_idx: u32,
+        global_ref: &Global,
+        context: impl AsContext,
     ) {
         let vtype = global_ref.ty(&context);
         let vtype_content = global_ref.ty(&context).content();
         let val = UntypedVal::from(global_ref.get(&context));
-        self.imtable.push(
+        imtable.push(
             true,
             vtype.mutability().is_mut(),
             global_idx,
@@ -75,17 +1010,164 @@ impl Tracer {
     }
 
     pub fn get_mtable(&self) -> MTable {
-        let mentries = self
-            .etable
+        let mentries = match &self.mtable {
+            Some(mtable) => mtable.entries().clone(),
+            None => memory_events_of_steps(self.etable.entries()),
+        };
+        let mentries = match self.write_only {
+            true => mentries
+                .into_iter()
+                .filter(|entry| entry.atype != AccessType::Read)
+                .collect(),
+            false => mentries,
+        };
+
+        MTable::new(mentries)
+    }
+
+    /// Returns the [`MTable`] prefixed with `Init` entries taken from the [`IMTable`].
+    ///
+    /// Every `Heap`, `Global` and passive segment location accessed by the trace
+    /// that is covered by the [`IMTable`] gets an `Init` entry with `eid` `0`,
+    /// so that its first access is preceded by its initial value. The `emid`s
+    /// of the `Init` entries follow the order of their locations.
+    ///
+    /// # Note
+    ///
+    /// The [`IMTable`] collapses runs of words into single entries, so only
+    /// accessed locations get an `Init` entry instead of all covered ones.
+    pub fn get_mtable_with_init(&self) -> MTable {
+        let mtable = self.get_mtable();
+        let locations = mtable
             .entries()
             .iter()
-            .map(|entry| memory_event_of_step(entry, &mut 1))
-            .collect::<Vec<Vec<_>>>()
-            .concat();
-
+            .filter(|entry| entry.ltype != LocationType::Stack)
+            .map(|entry| (entry.ltype, entry.addr))
+            .collect::<BTreeSet<_>>();
+        let mut emid = 0;
+        let mut mentries = Vec::with_capacity(locations.len() + mtable.entries().len());
+        for (ltype, addr) in locations {
+            let init = match ltype {
+                LocationType::Heap => self.init_heap_word(addr),
+                _ => u32::try_from(addr)
+                    .ok()
+                    .and_then(|offset| self.imtable.entry_at(ltype, offset))
+                    .map(|init| (init.is_mutable, init.val())),
+            };
+            if let Some((is_mutable, value)) = init {
+                mentries.push(MemoryTableEntry {
+                    eid: 0,
+                    emid,
+                    addr,
+                    ltype,
+                    atype: AccessType::Init,
+                    is_mutable,
+                    value,
+                    frame: 0,
+                });
+                emid += 1;
+            }
+        }
+        mentries.extend(mtable.entries().iter().cloned());
         MTable::new(mentries)
     }
 
+    /// Returns the mutability and initial value of the 8-byte `Heap` word at `index`.
+    ///
+    /// The value is joined from the words of the granularity of the [`IMTable`].
+    /// Returns `None` unless the [`IMTable`] covers all of these words.
+    fn init_heap_word(&self, index: usize) -> Option<(bool, Val)> {
+        let words = self
+            .granularity
+            .heap_word_offsets(index)
+            .map(|offset| {
+                let offset = u32::try_from(offset).ok()?;
+                self.imtable.entry_at(LocationType::Heap, offset)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let value = self
+            .granularity
+            .join_heap_word(words.iter().map(|init| init.value));
+        Some((words[0].is_mutable, Val::I64(value as i64)))
+    }
+
+    /// Returns the [`IMTable`] holding the memory state after the traced steps.
+    ///
+    /// The last write of the [`MTable`] to each non-`Stack` location is applied
+    /// over the [`IMTable`], splitting its entries around the written locations.
+    /// Written locations not covered by the [`IMTable`] are appended as new entries.
+    /// Written `Heap` words are split into the words of the granularity of the [`IMTable`].
+    /// Writes of [`StepInfo::Unimplemented`] steps are not recorded and thus not applied.
+    /// This allows to chain the initial states of trace segments without reading
+    /// the [`Store`] again.
+    ///
+    /// [`Store`]: crate::Store
+    pub fn derive_next_imtable(&self) -> IMTable {
+        // The mutability, value type and value of the last write to each location.
+        let mut writes = BTreeMap::new();
+        for entry in self.get_mtable().entries() {
+            if entry.atype != AccessType::Write || entry.ltype == LocationType::Stack {
+                continue;
+            }
+            let bits = UntypedVal::from(entry.value.clone()).to_bits();
+            if entry.ltype == LocationType::Heap {
+                let offsets = self.granularity.heap_word_offsets(entry.addr);
+                for (offset, word) in offsets.zip(self.granularity.split_heap_word(bits)) {
+                    if let Ok(offset) = u32::try_from(offset) {
+                        let vtype = self.granularity.value_type();
+                        writes.insert((entry.ltype, offset), (entry.is_mutable, vtype, word));
+                    }
+                }
+            } else if let Ok(offset) = u32::try_from(entry.addr) {
+                let vtype = entry.value.ty().into();
+                writes.insert((entry.ltype, offset), (entry.is_mutable, vtype, bits));
+            }
+        }
+        let mut next = IMTable::default();
+        for init in self.imtable.entries() {
+            let mut start = u64::from(init.start_offset);
+            let written =
+                writes.range((init.ltype, init.start_offset)..=(init.ltype, init.end_offset));
+            for (&(_, offset), &(_, _, value)) in written {
+                if start < u64::from(offset) {
+                    next.push_location(
+                        init.ltype,
+                        init.is_mutable,
+                        start as u32,
+                        offset - 1,
+                        init.vtype,
+                        init.value,
+                    );
+                }
+                next.push_location(
+                    init.ltype,
+                    init.is_mutable,
+                    offset,
+                    offset,
+                    init.vtype,
+                    value,
+                );
+                start = u64::from(offset) + 1;
+            }
+            if start <= u64::from(init.end_offset) {
+                next.push_location(
+                    init.ltype,
+                    init.is_mutable,
+                    start as u32,
+                    init.end_offset,
+                    init.vtype,
+                    init.value,
+                );
+            }
+        }
+        for (&(ltype, offset), &(is_mutable, vtype, value)) in &writes {
+            if self.imtable.entry_at(ltype, offset).is_none() {
+                next.push_location(ltype, is_mutable, offset, offset, vtype, value);
+            }
+        }
+        next
+    }
+
     pub fn show(&self) {
         self.get_mtable().show();
         self.etable.show();
@@ -1,16 +1,43 @@
-use core::fmt::Display;
-use std::{println, vec, vec::Vec};
+use core::{
+    cmp::Ordering,
+    fmt::{self, Display, Write},
+    iter,
+    ops::RangeInclusive,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    println,
+    string::String,
+    vec,
+    vec::Vec,
+};
 
 use crate::{
-    etable::{ETableEntry, IVal, StepInfo},
+    core::UntypedVal,
+    engine::executor::CallKind,
+    etable::{type_name, ETableEntry, IVal, MemoryWord, StepInfo},
+    format::{Decoder, Encoder, FormatError, FormatVersion},
     Val,
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LocationType {
     Stack,
     Heap,
     Global,
+    /// The words of the passive data segments.
+    ///
+    /// Only recorded into the [`IMTable`](super::imtable::IMTable).
+    Data,
+    /// The items of the passive element segments.
+    ///
+    /// Only recorded into the [`IMTable`](super::imtable::IMTable).
+    Element,
+    /// The elements of the tables.
+    ///
+    /// Only recorded into the [`IMTable`](super::imtable::IMTable).
+    Table,
 }
 
 impl Display for LocationType {
@@ -19,11 +46,15 @@ impl Display for LocationType {
             LocationType::Stack => write!(f, "Stack"),
             LocationType::Heap => write!(f, "Heap"),
             LocationType::Global => write!(f, "Global"),
+            LocationType::Data => write!(f, "Data"),
+            LocationType::Element => write!(f, "Element"),
+            LocationType::Table => write!(f, "Table"),
         }
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AccessType {
     Read,
     Write,
@@ -40,28 +71,127 @@ impl Display for AccessType {
     }
 }
 
+/// A memory access of a [`CustomStepInfo`](super::etable::CustomStepInfo).
+///
+/// Becomes a [`MemoryTableEntry`] of the step once the step is traced.
+#[derive(Debug, Clone)]
+pub struct MemoryOp {
+    pub addr: usize,
+    pub ltype: LocationType,
+    pub atype: AccessType,
+    pub is_mutable: bool,
+    pub value: Val,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MemoryTableEntry {
-    pub eid: u32,
+    pub eid: u64,
     pub emid: u32,
+    #[cfg_attr(feature = "serde", serde(with = "super::etable::ser::addr"))]
     pub addr: usize,
     pub ltype: LocationType,
     pub atype: AccessType,
     pub is_mutable: bool,
+    #[cfg_attr(feature = "serde", serde(with = "super::etable::ser::val"))]
     pub value: Val,
+    /// The activation of the function frame that accessed the location.
+    ///
+    /// Activations are numbered in call order starting with `0` for the traced
+    /// function. Results written by a return belong to the caller's activation.
+    pub frame: u32,
 }
 
 impl Display for MemoryTableEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{:7} {:8} {:8} {:6} {:5} {:5} {:?}",
+            "{:20} {:8} {:8} {:6} {:5} {:5} {:?}",
             self.eid, self.emid, self.addr, self.ltype, self.atype, self.is_mutable, self.value
         )
     }
 }
 
-#[derive(Debug, Default)]
+/// The header line of the CSV encoding of an [`MTable`].
+///
+/// Values are encoded by their type and their 64-bit pattern.
+pub const CSV_HEADER: &str = "eid,emid,addr,ltype,atype,is_mutable,vtype,bits,frame";
+
+impl MemoryTableEntry {
+    /// Writes the entry as a line of the CSV encoding of an [`MTable`] into `out`.
+    ///
+    /// # Errors
+    ///
+    /// If writing to `out` fails.
+    pub fn to_csv_row(&self, mut out: impl Write) -> fmt::Result {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{}",
+            self.eid,
+            self.emid,
+            self.addr,
+            self.ltype,
+            self.atype,
+            self.is_mutable,
+            type_name(self.value.ty()),
+            UntypedVal::from(self.value.clone()).to_bits(),
+            self.frame
+        )
+    }
+}
+
+/// A `Read` entry of the [`MTable`] that does not observe the latest value at its location.
+#[derive(Debug, Clone)]
+pub struct InconsistentRead {
+    /// The offending `Read` entry.
+    pub read: MemoryTableEntry,
+    /// The value of the latest `Write` or `Init` entry at the same location.
+    pub expected: Val,
+    /// The activation of another function frame that wrote to the `Stack`
+    /// location of the reading frame in between, if any.
+    pub aliased_by: Option<u32>,
+}
+
+impl Display for InconsistentRead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "read of {} address {} at eid {} (emid {}) observed {:?} but expected {:?}",
+            self.read.ltype,
+            self.read.addr,
+            self.read.eid,
+            self.read.emid,
+            self.read.value,
+            self.expected
+        )?;
+        if let Some(frame) = self.aliased_by {
+            write!(f, " after a write by frame {}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+/// Adjacent entries of a single step accessing consecutive addresses.
+///
+/// Returned by [`MTable::coalesce_spans`].
+#[derive(Debug, Copy, Clone)]
+pub struct MemorySpan<'a> {
+    /// The entries of the span in increasing address order.
+    ///
+    /// All entries share their `eid`, `ltype` and `atype`.
+    pub entries: &'a [MemoryTableEntry],
+}
+
+impl MemorySpan<'_> {
+    /// Returns the addresses covered by the span.
+    pub fn addresses(&self) -> RangeInclusive<usize> {
+        let first = self.entries.first().expect("memory spans are never empty");
+        first.addr..=first.addr + self.entries.len() - 1
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MTable(Vec<MemoryTableEntry>);
 
 impl MTable {
@@ -73,9 +203,318 @@ impl MTable {
         &self.0
     }
 
+    /// Returns the JSON encoding of the entries of the [`MTable`].
+    ///
+    /// Addresses are encoded as `u64` and values as their type and 64-bit pattern.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> std::string::String {
+        serde_json::to_string(self).expect("memory tables always serialize to JSON")
+    }
+
+    /// Returns an iterator over the entries of each step together with the step's eid.
+    ///
+    /// # Note
+    ///
+    /// This relies on the entries being ordered by eid as they are when
+    /// built from the [`ETable`](super::etable::ETable).
+    pub fn group_by_eid(&self) -> impl Iterator<Item = (u64, &[MemoryTableEntry])> {
+        self.0
+            .chunk_by(|a, b| a.eid == b.eid)
+            .map(|entries| (entries[0].eid, entries))
+    }
+
+    /// Returns the entries grouped into spans of adjacent accesses to consecutive addresses.
+    ///
+    /// Adjacent entries of the same step with the same location and access type
+    /// are merged into a single [`MemorySpan`] if each accesses the address following
+    /// the previous one, e.g. the memory words read by an unaligned wide load.
+    /// All other entries form spans of a single entry.
+    ///
+    /// The spans borrow the expanded entries of the [`MTable`].
+    pub fn coalesce_spans(&self) -> Vec<MemorySpan<'_>> {
+        self.0
+            .chunk_by(|a, b| {
+                a.eid == b.eid
+                    && a.ltype == b.ltype
+                    && a.atype == b.atype
+                    && a.addr.checked_add(1) == Some(b.addr)
+            })
+            .map(|entries| MemorySpan { entries })
+            .collect()
+    }
+
+    /// Splits the [`MTable`] into its `Stack`, `Heap`, `Global` and remaining entries.
+    ///
+    /// The last returned [`MTable`] holds the `Data`, `Element` and `Table` entries,
+    /// e.g. the segment words read by a trapping `memory.init` or the accesses of
+    /// custom steps. The entries of each returned [`MTable`] keep their relative order.
+    pub fn partition(self) -> (MTable, MTable, MTable, MTable) {
+        let mut stack = Vec::new();
+        let mut heap = Vec::new();
+        let mut global = Vec::new();
+        let mut segments = Vec::new();
+        for entry in self.0 {
+            match entry.ltype {
+                LocationType::Stack => stack.push(entry),
+                LocationType::Heap => heap.push(entry),
+                LocationType::Global => global.push(entry),
+                LocationType::Data | LocationType::Element | LocationType::Table => {
+                    segments.push(entry)
+                }
+            }
+        }
+        (
+            MTable(stack),
+            MTable(heap),
+            MTable(global),
+            MTable(segments),
+        )
+    }
+
+    /// Returns the entries of the [`MTable`] sorted by [`MTable::canonical_order`].
+    pub fn sorted_by_address(&self) -> MTable {
+        let mut entries = self.0.clone();
+        entries.sort_by(Self::canonical_order);
+        MTable(entries)
+    }
+
+    /// Compares two entries by the canonical order of memory rows used for proving.
+    ///
+    /// Rows are ordered by location type, then by address and then by `eid`.
+    /// Ties on `(addr, eid)` are broken by `emid`, i.e. by the order of the
+    /// accesses within their step. Since steps read all of their operands
+    /// before they write any result, a step that reads and writes the same
+    /// address has its `Read` ordered before its `Write`.
+    ///
+    /// As `(eid, emid)` is unique per entry, this is a total order.
+    pub fn canonical_order(a: &MemoryTableEntry, b: &MemoryTableEntry) -> Ordering {
+        (a.ltype, a.addr, a.eid, a.emid).cmp(&(b.ltype, b.addr, b.eid, b.emid))
+    }
+
+    /// Returns the `n`-th access to the location `(ltype, addr)` in execution order.
+    ///
+    /// `n` is zero-based, i.e. `n == 2` returns the third access to the location.
+    /// Returns `None` if the location is accessed at most `n` times.
+    pub fn nth_access(
+        &self,
+        ltype: LocationType,
+        addr: usize,
+        n: usize,
+    ) -> Option<&MemoryTableEntry> {
+        self.0
+            .iter()
+            .filter(|entry| entry.ltype == ltype && entry.addr == addr)
+            .nth(n)
+    }
+
+    /// Encodes the [`MTable`] in the current [`TRACE_FORMAT_VERSION`].
+    ///
+    /// [`TRACE_FORMAT_VERSION`]: super::format::TRACE_FORMAT_VERSION
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new();
+        encoder.u64(self.0.len() as u64);
+        for entry in &self.0 {
+            encoder.u64(entry.eid);
+            encoder.u32(entry.emid);
+            encoder.u64(entry.addr as u64);
+            encoder.u8(match entry.ltype {
+                LocationType::Stack => 0,
+                LocationType::Heap => 1,
+                LocationType::Global => 2,
+                LocationType::Data => 3,
+                LocationType::Element => 4,
+                LocationType::Table => 5,
+            });
+            encoder.u8(match entry.atype {
+                AccessType::Read => 0,
+                AccessType::Write => 1,
+                AccessType::Init => 2,
+            });
+            encoder.u8(u8::from(entry.is_mutable));
+            encoder.val(&entry.value);
+            encoder.u32(entry.frame);
+        }
+        encoder.finish()
+    }
+
+    /// Decodes an [`MTable`] encoded by [`MTable::to_bytes`].
+    ///
+    /// Encodings of older minor format versions are upgraded to the current one.
+    ///
+    /// # Errors
+    ///
+    /// If the `bytes` are not an encoded [`MTable`] of a supported format version.
+    pub fn from_bytes(bytes: &[u8]) -> Result<MTable, FormatError> {
+        let mut decoder = Decoder::new(bytes)?;
+        let len = decoder.u64()?;
+        let mut entries = Vec::new();
+        for _ in 0..len {
+            let eid = decoder.u64()?;
+            let emid = decoder.u32()?;
+            let addr = decoder.u64()? as usize;
+            let ltype = match decoder.u8()? {
+                0 => LocationType::Stack,
+                1 => LocationType::Heap,
+                2 => LocationType::Global,
+                3 => LocationType::Data,
+                4 => LocationType::Element,
+                5 => LocationType::Table,
+                tag => {
+                    return Err(FormatError::InvalidTag {
+                        kind: "location type",
+                        tag,
+                    })
+                }
+            };
+            let atype = match decoder.u8()? {
+                0 => AccessType::Read,
+                1 => AccessType::Write,
+                2 => AccessType::Init,
+                tag => {
+                    return Err(FormatError::InvalidTag {
+                        kind: "access type",
+                        tag,
+                    })
+                }
+            };
+            let is_mutable = decoder.u8()? != 0;
+            let value = decoder.val()?;
+            let frame = decoder.u32()?;
+            entries.push(MemoryTableEntry {
+                eid,
+                emid,
+                addr,
+                ltype,
+                atype,
+                is_mutable,
+                value,
+                frame,
+            });
+        }
+        let version = decoder.version();
+        decoder.finish()?;
+        Ok(MTable(Self::migrate(version, entries)))
+    }
+
+    /// Upgrades the `entries` decoded from the minor `version` of the format.
+    ///
+    /// Newer minor versions only append fields to the encoded entries. Entries
+    /// of older versions are decoded with defaults for those fields which are
+    /// then derived here. There are no older minor versions of `2.0` yet.
+    fn migrate(version: FormatVersion, entries: Vec<MemoryTableEntry>) -> Vec<MemoryTableEntry> {
+        debug_assert!(version.is_supported());
+        entries
+    }
+
+    /// Returns the last value written to or initialized at each location.
+    ///
+    /// This reconstructs the final memory state of the trace and only
+    /// depends on `Write` and `Init` entries.
+    pub fn final_values(&self) -> BTreeMap<(LocationType, usize), Val> {
+        self.entries()
+            .iter()
+            .filter(|entry| entry.atype != AccessType::Read)
+            .map(|entry| ((entry.ltype, entry.addr), entry.value.clone()))
+            .collect()
+    }
+
+    /// Returns the number of distinct locations accessed per window of `window_eids` steps.
+    ///
+    /// The steps are split into consecutive non-overlapping windows where the
+    /// window starting at eid `start` covers the eids `start..start + window_eids`.
+    /// Each window is returned as its starting eid together with the number of distinct
+    /// `(LocationType, address)` pairs accessed within it. Windows without any accesses
+    /// are omitted.
+    ///
+    /// # Panics
+    ///
+    /// If `window_eids` is zero.
+    pub fn working_set(&self, window_eids: u64) -> Vec<(u64, usize)> {
+        assert!(window_eids > 0, "working set windows must not be empty");
+        let mut windows: BTreeMap<u64, BTreeSet<(LocationType, usize)>> = BTreeMap::new();
+        for entry in self.entries() {
+            // Eids are 1-based so the first window starts at eid 1.
+            let start = (entry.eid - 1) / window_eids * window_eids + 1;
+            windows
+                .entry(start)
+                .or_default()
+                .insert((entry.ltype, entry.addr));
+        }
+        windows
+            .into_iter()
+            .map(|(start, locations)| (start, locations.len()))
+            .collect()
+    }
+
+    /// Checks that every `Read` observes the value of the latest `Write` or `Init`
+    /// to the same location in execution order.
+    ///
+    /// `Stack` locations are qualified by the activation of their function frame:
+    /// a `Read` must observe the latest `Write` of its own frame and no other frame
+    /// may have written to the location in between. This catches registers of
+    /// different frames that are traced at the same stack address.
+    ///
+    /// Reads of locations without a preceding `Write` or `Init` are accepted.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first [`InconsistentRead`] found.
+    pub fn validate(&self) -> Result<(), InconsistentRead> {
+        let mut latest: BTreeMap<_, (u32, &Val)> = BTreeMap::new();
+        let mut frame_latest: BTreeMap<_, &Val> = BTreeMap::new();
+        for entry in self.entries() {
+            let location = (entry.ltype, entry.addr);
+            let is_stack = entry.ltype == LocationType::Stack;
+            match entry.atype {
+                AccessType::Read => {
+                    let (expected, aliased_by) = match is_stack {
+                        true => match frame_latest.get(&(entry.frame, entry.addr)) {
+                            Some(expected) => {
+                                let (writer, _) = latest[&location];
+                                (*expected, (writer != entry.frame).then_some(writer))
+                            }
+                            None => continue,
+                        },
+                        false => match latest.get(&location) {
+                            Some((_, expected)) => (*expected, None),
+                            None => continue,
+                        },
+                    };
+                    if aliased_by.is_some() || !same_bits(expected, &entry.value) {
+                        return Err(InconsistentRead {
+                            read: entry.clone(),
+                            expected: expected.clone(),
+                            aliased_by,
+                        });
+                    }
+                }
+                AccessType::Write | AccessType::Init => {
+                    latest.insert(location, (entry.frame, &entry.value));
+                    if is_stack {
+                        frame_latest.insert((entry.frame, entry.addr), &entry.value);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the [`MTable`] as CSV with a [`CSV_HEADER`] line into `out`.
+    ///
+    /// # Errors
+    ///
+    /// If writing to `out` fails.
+    pub fn to_csv(&self, mut out: impl Write) -> fmt::Result {
+        writeln!(out, "{}", CSV_HEADER)?;
+        for entry in self.entries() {
+            entry.to_csv_row(&mut out)?;
+        }
+        Ok(())
+    }
+
     pub fn show(&self) {
         println!(
-            "{:7} {:8} {:8} {:6} {:5} {:5} value",
+            "{:>20} {:8} {:8} {:6} {:5} {:5} value",
             "eid", "emid", "addr", "ltype", "atype", "is_mutable",
         );
 
@@ -85,6 +524,146 @@ impl MTable {
     }
 }
 
+/// Returns `true` if `a` and `b` have the same bit pattern.
+///
+/// Registers are untyped so the same stack slot may be accessed
+/// with different value types, e.g. by `copy` instructions.
+fn same_bits(a: &Val, b: &Val) -> bool {
+    UntypedVal::from(a.clone()).to_bits() == UntypedVal::from(b.clone()).to_bits()
+}
+
+/// Returns the [`MemoryTableEntry`]s of the traced `steps` in execution order.
+///
+/// Assigns the activation of the accessing function frame to every entry.
+pub(crate) fn memory_events_of_steps(steps: &[ETableEntry]) -> Vec<MemoryTableEntry> {
+    let mut events = MemoryEvents::default();
+    steps.iter().flat_map(|step| events.of_step(step)).collect()
+}
+
+/// Derives the [`MemoryTableEntry`]s of traced steps one step at a time.
+///
+/// Tracks the live activations of the steps seen so far in order to
+/// assign the accessing activation to every entry.
+#[derive(Debug)]
+pub(crate) struct MemoryEvents {
+    /// The live activations with the executing one on top.
+    frames: Vec<u32>,
+    /// The activation of the next called function frame.
+    next_frame: u32,
+}
+
+impl Default for MemoryEvents {
+    fn default() -> Self {
+        Self {
+            frames: vec![0],
+            next_frame: 1,
+        }
+    }
+}
+
+impl MemoryEvents {
+    /// Returns the call depth of the next step, which is `0` for the traced function.
+    #[cfg(feature = "arrow")]
+    pub(crate) fn depth(&self) -> u32 {
+        (self.frames.len() - 1) as u32
+    }
+
+    /// Returns the [`MemoryTableEntry`]s of the `step` following the previously seen steps.
+    pub(crate) fn of_step(&mut self, step: &ETableEntry) -> Vec<MemoryTableEntry> {
+        let frames = &mut self.frames;
+        let mut events = memory_event_of_step(step, &mut 1);
+        let frame = *frames.last().expect("must have a live activation");
+        for event in &mut events {
+            event.frame = frame;
+        }
+        match &step.step_info {
+            StepInfo::Call { host, kind, .. } | StepInfo::CallIndirect { host, kind, .. } => {
+                // Tail calls replace the activation of the caller.
+                if *kind == CallKind::Tail && frames.len() > 1 {
+                    frames.pop();
+                }
+                if !*host {
+                    // The parameters are written into the registers of the callee.
+                    for event in &mut events {
+                        if event.atype == AccessType::Write {
+                            event.frame = self.next_frame;
+                        }
+                    }
+                    frames.push(self.next_frame);
+                    self.next_frame += 1;
+                }
+            }
+            StepInfo::Return { .. } if frames.len() > 1 => {
+                frames.pop();
+                let caller = *frames.last().expect("must have a live activation");
+                for event in &mut events {
+                    if event.atype == AccessType::Write {
+                        event.frame = caller;
+                    }
+                }
+            }
+            _ => {}
+        }
+        events
+    }
+}
+
+/// Streams the CSV encoding of the [`MemoryTableEntry`]s of traced steps into a file.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub(crate) struct CsvStream {
+    out: std::io::BufWriter<std::fs::File>,
+    events: MemoryEvents,
+    /// The first error that occurred while writing to `out`.
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "std")]
+impl CsvStream {
+    /// Creates the file at `path` and writes the [`CSV_HEADER`] line into it.
+    pub(crate) fn create(path: &std::path::Path) -> std::io::Result<Self> {
+        use std::io::Write as _;
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(out, "{}", CSV_HEADER)?;
+        Ok(Self {
+            out,
+            events: MemoryEvents::default(),
+            error: None,
+        })
+    }
+
+    /// Writes the entries of the traced `step`, skipping `Read` entries if `write_only`.
+    ///
+    /// Write errors are deferred to [`CsvStream::finish`].
+    pub(crate) fn push(&mut self, step: &ETableEntry, write_only: bool) {
+        use std::io::Write as _;
+        let mut rows = String::new();
+        for entry in self.events.of_step(step) {
+            if !(write_only && entry.atype == AccessType::Read) {
+                entry
+                    .to_csv_row(&mut rows)
+                    .expect("writing into a string cannot fail");
+            }
+        }
+        if self.error.is_none() {
+            self.error = self.out.write_all(rows.as_bytes()).err();
+        }
+    }
+
+    /// Flushes the written rows and returns the first error that occurred while writing.
+    pub(crate) fn finish(mut self) -> std::io::Result<()> {
+        use std::io::Write as _;
+        match self.error.take() {
+            Some(error) => Err(error),
+            None => self.out.flush(),
+        }
+    }
+}
+
+/// Returns the [`MemoryTableEntry`]s of the traced `event`.
+///
+/// The `frame` of the returned entries is left at `0`.
+/// Use [`memory_events_of_steps`] to assign the accessing activations.
 pub fn memory_event_of_step(event: &ETableEntry, emid: &mut u32) -> Vec<MemoryTableEntry> {
     let eid = event.eid;
 
@@ -94,44 +673,305 @@ pub fn memory_event_of_step(event: &ETableEntry, emid: &mut u32) -> Vec<MemoryTa
             right,
             result,
             ..
+        }
+        | StepInfo::I64BinOp {
+            left,
+            right,
+            result,
+            ..
+        }
+        | StepInfo::F32BinOp {
+            left,
+            right,
+            result,
+            ..
+        }
+        | StepInfo::F64BinOp {
+            left,
+            right,
+            result,
+            ..
         } => mem_op_from_stack_only_step(eid, emid, &[left, right], &[result]),
+        StepInfo::I32UnOp {
+            operand, result, ..
+        }
+        | StepInfo::FloatUnOp {
+            operand, result, ..
+        }
+        | StepInfo::SignExtend {
+            operand, result, ..
+        } => mem_op_from_stack_only_step(eid, emid, &[operand], &[result]),
+        StepInfo::RelOp {
+            left,
+            right,
+            result,
+            ..
+        } => mem_op_from_stack_only_step(eid, emid, &[left, right], &[result]),
+        StepInfo::TestOp {
+            operand, result, ..
+        } => mem_op_from_stack_only_step(eid, emid, &[operand], &[result]),
+        StepInfo::ConvertInt { input, result, .. }
+        | StepInfo::I32WrapI64 { input, result, .. }
+        | StepInfo::I64ExtendI32 { input, result, .. } => {
+            mem_op_from_stack_only_step(eid, emid, &[input], &[result])
+        }
+        StepInfo::Load {
+            address,
+            value,
+            words,
+            ..
+        } => {
+            let mut mentries = mem_op_from_stack_only_step(eid, emid, &[address], &[]);
+            mentries.extend(mem_op_from_heap_words(eid, emid, AccessType::Read, words));
+            mentries.extend(mem_op_from_stack_only_step(eid, emid, &[], &[value]));
+            mentries
+        }
+        StepInfo::Store {
+            address,
+            value,
+            words,
+            ..
+        } => {
+            let mut mentries = mem_op_from_stack_only_step(eid, emid, &[address, value], &[]);
+            mentries.extend(mem_op_from_heap_words(eid, emid, AccessType::Write, words));
+            mentries
+        }
+        StepInfo::MemoryGrow { delta, result, .. } => {
+            mem_op_from_stack_only_step(eid, emid, &[delta], &[result])
+        }
+        StepInfo::MemorySize { result, .. } => {
+            mem_op_from_stack_only_step(eid, emid, &[], &[result])
+        }
+        StepInfo::GlobalGet {
+            address,
+            is_mutable,
+            value,
+            ..
+        } => {
+            let mut mentries = std::vec![MemoryTableEntry {
+                eid,
+                emid: *emid,
+                addr: *address,
+                ltype: LocationType::Global,
+                atype: AccessType::Read,
+                is_mutable: *is_mutable,
+                value: value.val.clone(),
+                frame: 0,
+            }];
+            *emid = (*emid).checked_add(1).unwrap();
+            mentries.extend(mem_op_from_stack_only_step(eid, emid, &[], &[value]));
+            mentries
+        }
+        StepInfo::GlobalSet { address, value, .. } => {
+            let mut mentries = mem_op_from_stack_only_step(eid, emid, &[value], &[]);
+            mentries.push(MemoryTableEntry {
+                eid,
+                emid: *emid,
+                addr: *address,
+                ltype: LocationType::Global,
+                atype: AccessType::Write,
+                // Only mutable globals can be set.
+                is_mutable: true,
+                value: value.val.clone(),
+                frame: 0,
+            });
+            *emid = (*emid).checked_add(1).unwrap();
+            mentries
+        }
+        StepInfo::Br { .. } => vec![],
+        StepInfo::BrIf { lhs, rhs, .. } => mem_op_from_stack_only_step(eid, emid, &[lhs, rhs], &[]),
+        StepInfo::BrTable { index, .. } => mem_op_from_stack_only_step(eid, emid, &[index], &[]),
+        StepInfo::LocalOp {
+            source,
+            dest,
+            prev_value,
+            ..
+        } => {
+            let reads = iter::once(source).chain(prev_value).collect::<Vec<_>>();
+            mem_op_from_stack_only_step(eid, emid, &reads, &[dest])
+        }
+        StepInfo::Const { value } => mem_op_from_stack_only_step(eid, emid, &[], &[value]),
+        StepInfo::Copy { values, results } => {
+            let reads = values.iter().collect::<Vec<_>>();
+            let writes = results.iter().collect::<Vec<_>>();
+            mem_op_from_stack_only_step(eid, emid, &reads, &writes)
+        }
+        StepInfo::Select {
+            cond,
+            lhs,
+            rhs,
+            result,
+            ..
+        } => mem_op_from_stack_only_step(eid, emid, &[cond, lhs, rhs], &[result]),
+        StepInfo::TableGet { index, result, .. } => {
+            mem_op_from_stack_only_step(eid, emid, &[index], &[result])
+        }
+        StepInfo::TableSet { index, value, .. } => {
+            mem_op_from_stack_only_step(eid, emid, &[index, value], &[])
+        }
+        StepInfo::Drop { value } => mem_op_from_stack_only_step(eid, emid, &[value], &[]),
+        StepInfo::Call { args, params, .. } => {
+            let reads = args.iter().collect::<Vec<_>>();
+            let writes = params.iter().collect::<Vec<_>>();
+            mem_op_from_stack_only_step(eid, emid, &reads, &writes)
+        }
+        // The table element holding the called function is not a memory location.
+        StepInfo::CallIndirect {
+            elem_index,
+            args,
+            params,
+            ..
+        } => {
+            let reads = iter::once(elem_index).chain(args).collect::<Vec<_>>();
+            let writes = params.iter().collect::<Vec<_>>();
+            mem_op_from_stack_only_step(eid, emid, &reads, &writes)
+        }
+        StepInfo::HostMutation { words, globals } => {
+            let mut mentries = mem_op_from_heap_words(eid, emid, AccessType::Write, words);
+            for (address, value) in globals {
+                mentries.push(MemoryTableEntry {
+                    eid,
+                    emid: *emid,
+                    addr: *address,
+                    ltype: LocationType::Global,
+                    atype: AccessType::Write,
+                    is_mutable: true,
+                    value: value.clone(),
+                    frame: 0,
+                });
+                *emid = (*emid).checked_add(1).unwrap();
+            }
+            mentries
+        }
+        StepInfo::Return {
+            condition,
+            values,
+            results,
+            dropped,
+        } => {
+            let reads = condition
+                .iter()
+                .chain(values)
+                .chain(dropped)
+                .collect::<Vec<_>>();
+            let writes = results.iter().collect::<Vec<_>>();
+            mem_op_from_stack_only_step(eid, emid, &reads, &writes)
+        }
+        StepInfo::Trap { reads, data, .. } => {
+            let reads = reads.iter().collect::<Vec<_>>();
+            let mut mentries = mem_op_from_stack_only_step(eid, emid, &reads, &[]);
+            mentries.extend(mem_op_from_words(
+                eid,
+                emid,
+                LocationType::Data,
+                AccessType::Read,
+                data,
+            ));
+            mentries
+        }
         StepInfo::Unimplemented(instr) => {
             println!("unimplemented {:?}", instr);
             vec![]
         }
+        StepInfo::Custom(step) => step
+            .memory_ops()
+            .into_iter()
+            .map(|op| {
+                let entry = MemoryTableEntry {
+                    eid,
+                    emid: *emid,
+                    addr: op.addr,
+                    ltype: op.ltype,
+                    atype: op.atype,
+                    is_mutable: op.is_mutable,
+                    value: op.value,
+                    frame: 0,
+                };
+                *emid = (*emid).checked_add(1).unwrap();
+                entry
+            })
+            .collect(),
     }
 }
 
+/// Returns the `Heap` entries of the `words` accessed with `atype`.
+fn mem_op_from_heap_words(
+    eid: u64,
+    emid: &mut u32,
+    atype: AccessType,
+    words: &[MemoryWord],
+) -> Vec<MemoryTableEntry> {
+    mem_op_from_words(eid, emid, LocationType::Heap, atype, words)
+}
+
+/// Returns the `ltype` entries of the `words` accessed with `atype`.
+///
+/// Only `Heap` words are mutable.
+fn mem_op_from_words(
+    eid: u64,
+    emid: &mut u32,
+    ltype: LocationType,
+    atype: AccessType,
+    words: &[MemoryWord],
+) -> Vec<MemoryTableEntry> {
+    words
+        .iter()
+        .map(|word| {
+            let entry = MemoryTableEntry {
+                eid,
+                emid: *emid,
+                addr: word.index,
+                ltype,
+                atype,
+                is_mutable: ltype == LocationType::Heap,
+                value: Val::I64(word.value as i64),
+                frame: 0,
+            };
+            *emid = (*emid).checked_add(1).unwrap();
+            entry
+        })
+        .collect()
+}
+
 fn mem_op_from_stack_only_step(
-    eid: u32,
+    eid: u64,
     emid: &mut u32,
     read_value: &[&IVal],
     write_value: &[&IVal],
 ) -> Vec<MemoryTableEntry> {
     let mut mem_op = Vec::new();
 
-    for ival in read_value {
+    // Immediate operands are part of the instruction and never touch the stack.
+    for (ival, addr) in read_value
+        .iter()
+        .filter_map(|ival| ival.addr.map(|addr| (ival, addr)))
+    {
         mem_op.push(MemoryTableEntry {
             eid,
             emid: *emid,
-            addr: ival.addr,
+            addr,
             ltype: LocationType::Stack,
             atype: AccessType::Read,
             is_mutable: true,
             value: ival.val.clone(),
+            frame: 0,
         });
         *emid = (*emid).checked_add(1).unwrap();
     }
 
-    for ival in write_value {
+    for (ival, addr) in write_value
+        .iter()
+        .filter_map(|ival| ival.addr.map(|addr| (ival, addr)))
+    {
         mem_op.push(MemoryTableEntry {
             eid,
             emid: *emid,
-            addr: ival.addr,
+            addr,
             ltype: LocationType::Stack,
             atype: AccessType::Write,
             is_mutable: true,
             value: ival.val.clone(),
+            frame: 0,
         });
         *emid = (*emid).checked_add(1).unwrap();
     }
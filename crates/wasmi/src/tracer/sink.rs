@@ -0,0 +1,84 @@
+use super::etable::ETableEntry;
+use core::fmt::{self, Debug};
+use std::vec::Vec;
+
+/// A consumer of the steps of a traced execution.
+///
+/// A [`Tracer`](super::Tracer) with a sink set by [`Tracer::with_sink`](super::Tracer::with_sink)
+/// passes each traced step to the sink instead of buffering it in its
+/// [`ETable`](super::etable::ETable). This allows streaming the steps of
+/// long running executions, e.g. to a file or a channel.
+pub trait TraceSink: Send {
+    /// Consumes the `entry` of the next traced step.
+    fn on_step(&mut self, entry: &ETableEntry);
+}
+
+/// A [`TraceSink`] buffering all steps like the [`ETable`](super::etable::ETable)
+/// of a [`Tracer`](super::Tracer) without a sink.
+#[derive(Debug, Default)]
+pub struct VecSink {
+    entries: Vec<ETableEntry>,
+}
+
+impl VecSink {
+    /// Creates an empty [`VecSink`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the buffered steps.
+    pub fn entries(&self) -> &[ETableEntry] {
+        &self.entries
+    }
+
+    /// Returns the buffered steps, consuming the [`VecSink`].
+    pub fn into_entries(self) -> Vec<ETableEntry> {
+        self.entries
+    }
+}
+
+impl TraceSink for VecSink {
+    fn on_step(&mut self, entry: &ETableEntry) {
+        self.entries.push(entry.clone());
+    }
+}
+
+/// A [`TraceSink`] calling a closure with each step without retaining it.
+pub struct CallbackSink<F> {
+    callback: F,
+}
+
+impl<F> CallbackSink<F>
+where
+    F: FnMut(&ETableEntry) + Send,
+{
+    /// Creates a [`CallbackSink`] calling `callback` with each step.
+    pub fn new(callback: F) -> Self {
+        Self { callback }
+    }
+}
+
+impl<F> Debug for CallbackSink<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackSink").finish_non_exhaustive()
+    }
+}
+
+impl<F> TraceSink for CallbackSink<F>
+where
+    F: FnMut(&ETableEntry) + Send,
+{
+    fn on_step(&mut self, entry: &ETableEntry) {
+        (self.callback)(entry)
+    }
+}
+
+/// Shares a [`TraceSink`], e.g. to read the entries of a [`VecSink`] after tracing.
+#[cfg(feature = "std")]
+impl<T: TraceSink> TraceSink for std::sync::Arc<std::sync::Mutex<T>> {
+    fn on_step(&mut self, entry: &ETableEntry) {
+        self.lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .on_step(entry)
+    }
+}
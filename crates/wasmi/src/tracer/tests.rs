@@ -0,0 +1,5509 @@
+use super::*;
+use crate::{
+    compact::CompactStep,
+    core::{TrapCode, UntypedVal, ValType},
+    errors::{ErrorKind, MemoryError},
+    engine::{
+        bytecode::{BranchComparator, Instruction},
+        executor::CallKind,
+    },
+    etable::{
+        BinOp,
+        CustomStepInfo,
+        ETableEntry,
+        EidError,
+        FloatUnOp,
+        IVal,
+        LocalOpKind,
+        MemoryWord,
+        RelOp,
+        StepInfo,
+        TestOp,
+        UnOp,
+    },
+    format::{FormatError, FormatVersion, MAGIC, TRACE_FORMAT_VERSION},
+    mtable::{memory_events_of_steps, AccessType, LocationType, MemoryOp, MemoryTableEntry},
+    AsContext,
+    Caller,
+    CompilationMode,
+    Config,
+    Engine,
+    Error,
+    Extern,
+    ExternRef,
+    FuncEntity,
+    FuncRef,
+    Global,
+    Linker,
+    Module,
+    Mutability,
+    Store,
+    Val,
+};
+use core::{fmt, mem};
+use std::{
+    cell::RefCell,
+    format,
+    rc::Rc,
+    string::String,
+    sync::{Arc, Mutex},
+    thread,
+    vec,
+    vec::Vec,
+};
+
+/// Instantiates the `wat` module and calls its exported `func` with `params` and tracing.
+///
+/// Returns the [`Tracer`] together with the results of the call.
+fn trace_wat(wat: &str, func: &str, params: &[Val]) -> (Tracer, Result<Vec<Val>, Error>) {
+    trace_wat_with(Tracer::new(), wat, func, params)
+}
+
+/// Like [`trace_wat`] but records into the given `tracer`.
+fn trace_wat_with(
+    tracer: Tracer,
+    wat: &str,
+    func: &str,
+    params: &[Val],
+) -> (Tracer, Result<Vec<Val>, Error>) {
+    let wasm = wat::parse_str(wat).unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let linker = <Linker<()>>::new(&engine);
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let func = instance.get_func(&store, func).unwrap();
+    let mut results = func
+        .ty(&store)
+        .results()
+        .iter()
+        .copied()
+        .map(Val::default)
+        .collect::<Vec<_>>();
+    let tracer = Rc::new(RefCell::new(tracer));
+    let result = func
+        .call_with_trace(&mut store, params, &mut results, tracer.clone())
+        .map(|_| results);
+    (tracer.take(), result)
+}
+
+#[test]
+fn table_get_out_of_bounds_traps() {
+    let wat = r#"
+        (module
+            (table 2 funcref)
+            (func (export "get") (param i32) (result funcref)
+                (table.get 0 (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "get", &[Val::I32(5)]);
+    assert_eq!(
+        result.unwrap_err().as_trap_code(),
+        Some(TrapCode::TableOutOfBounds)
+    );
+    let last = tracer.etable.entries().last().unwrap();
+    match &last.step_info {
+        StepInfo::Trap { code, reads, .. } => {
+            assert_eq!(*code, TrapCode::TableOutOfBounds);
+            assert_eq!(reads.len(), 1);
+            assert_eq!(reads[0].val.i32(), Some(5));
+            assert!(reads[0].addr.is_some());
+        }
+        step => panic!("expected a trap step but found: {step:?}"),
+    }
+    let mtable = tracer.get_mtable();
+    let trap_reads = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.eid == last.eid)
+        .collect::<Vec<_>>();
+    assert_eq!(trap_reads.len(), 1);
+    assert_eq!(trap_reads[0].atype, AccessType::Read);
+}
+
+#[test]
+fn table_get_in_bounds_is_traced() {
+    let wat = r#"
+        (module
+            (table 2 funcref)
+            (func (export "get") (param i32) (result funcref)
+                (table.get 0 (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "get", &[Val::I32(1)]);
+    assert!(result.unwrap()[0].funcref().unwrap().is_null());
+    let table_get = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::TableGet { index, result, .. } => Some((index.clone(), result.clone())),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(table_get.0.val.i32(), Some(1));
+    assert!(table_get.1.val.funcref().unwrap().is_null());
+}
+
+#[test]
+fn write_only_mode_skips_reads() {
+    let wat = r#"
+        (module
+            (func (export "f") (param i32 i32) (result i32)
+                (local i32)
+                (local.set 2 (i32.add (local.get 0) (local.get 1)))
+                (i32.add (local.get 2) (local.get 2))
+            )
+        )"#;
+    let params = [Val::I32(3), Val::I32(4)];
+    let (full, _) = trace_wat(wat, "f", &params);
+    let (write_only, result) = trace_wat_with(Tracer::new().write_only_mode(), wat, "f", &params);
+    assert_eq!(result.unwrap()[0].i32(), Some(14));
+    let full = full.get_mtable();
+    let write_only = write_only.get_mtable();
+    assert!(full
+        .entries()
+        .iter()
+        .any(|entry| entry.atype == AccessType::Read));
+    assert!(write_only
+        .entries()
+        .iter()
+        .all(|entry| entry.atype != AccessType::Read));
+    let bits = |mtable: &MTable| {
+        mtable
+            .final_values()
+            .into_iter()
+            .map(|(location, value)| (location, UntypedVal::from(value).to_bits()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(bits(&write_only), bits(&full));
+    assert!(bits(&write_only).iter().any(|(_, value)| *value == 14));
+}
+
+#[test]
+fn disabled_categories_are_not_recorded() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $g (mut i32) (i32.const 0))
+            (func (export "f") (param i32 i32) (result i32)
+                (i32.store (i32.const 8) (i32.add (local.get 0) (local.get 1)))
+                (global.set $g (i32.load (i32.const 8)))
+                (i32.mul (global.get $g) (i32.const 2))
+            )
+        )"#;
+    let params = [Val::I32(3), Val::I32(4)];
+    let is_heap = |entry: &ETableEntry| {
+        matches!(
+            entry.step_info,
+            StepInfo::Load { .. } | StepInfo::Store { .. }
+        )
+    };
+    let (full, _) = trace_wat(wat, "f", &params);
+    let config = TraceConfig {
+        record_stack: false,
+        record_globals: false,
+        record_control_flow: false,
+        ..TraceConfig::default()
+    };
+    let (heap_only, result) = trace_wat_with(Tracer::with_config(config), wat, "f", &params);
+    assert_eq!(result.unwrap()[0].i32(), Some(14));
+    let (full, heap_only) = (full.etable.entries(), heap_only.etable.entries());
+    assert!(full.iter().any(|entry| !is_heap(entry)));
+    assert!(heap_only.iter().all(is_heap));
+    assert_eq!(
+        heap_only.len(),
+        full.iter().filter(|entry| is_heap(entry)).count()
+    );
+}
+
+#[test]
+fn step_limit_stops_an_infinite_loop() {
+    let wat = r#"
+        (module
+            (func (export "spin") (param $n i32)
+                (loop $continue
+                    (local.set $n (i32.add (local.get $n) (i32.const 1)))
+                    (br $continue)
+                )
+            )
+        )"#;
+    let tracer = Tracer::new().max_steps(100);
+    let (tracer, result) = trace_wat_with(tracer, wat, "spin", &[Val::I32(0)]);
+    let error = result.unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::Trace(TraceError::StepLimitExceeded { max_steps: 100 })
+    ));
+    assert_eq!(tracer.etable.entries().len(), 100);
+    tracer.etable.validate_eids().unwrap();
+}
+
+#[test]
+fn step_limit_stops_an_infinite_loop_of_unrecorded_instructions() {
+    let wat = r#"
+        (module
+            (func (export "spin") (param $n i32)
+                (loop $continue
+                    (local.set $n (i32.add (local.get $n) (i32.const 1)))
+                    (br $continue)
+                )
+            )
+        )"#;
+    let config = TraceConfig {
+        record_stack: false,
+        record_control_flow: false,
+        ..TraceConfig::default()
+    };
+    let tracer = Tracer::with_config(config).max_steps(100);
+    let (tracer, result) = trace_wat_with(tracer, wat, "spin", &[Val::I32(0)]);
+    let error = result.unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::Trace(TraceError::StepLimitExceeded { max_steps: 100 })
+    ));
+    assert!(tracer.etable.entries().is_empty());
+}
+
+#[test]
+fn recursive_frames_have_distinct_local_addresses() {
+    let wat = r#"
+        (module
+            (func $sum (export "sum") (param $n i32) (result i32)
+                (local $double i32)
+                (local.set $double (i32.add (local.get $n) (local.get $n)))
+                (if (result i32) (i32.eqz (local.get $n))
+                    (then (i32.const 1))
+                    (else
+                        (i32.add
+                            (local.get $n)
+                            (call $sum (i32.sub (local.get $n) (i32.const 1)))
+                        )
+                    )
+                )
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "sum", &[Val::I32(4)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(11));
+    let local_writes = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::I32BinOp {
+                left,
+                right,
+                result,
+                ..
+            } if left.addr == right.addr => Some(result.addr.unwrap()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // One write of `$double` per frame: `sum(4)` down to `sum(0)`.
+    assert_eq!(local_writes.len(), 5);
+    for (i, addr) in local_writes.iter().enumerate() {
+        assert!(!local_writes[i + 1..].contains(addr));
+    }
+    let mtable = tracer.get_mtable();
+    let deepest = mtable.entries().iter().map(|entry| entry.frame).max();
+    assert_eq!(deepest, Some(4));
+    mtable.validate().unwrap();
+}
+
+#[test]
+fn validate_detects_inconsistent_read() {
+    let entry = |emid, atype, value| MemoryTableEntry {
+        eid: 1,
+        emid,
+        addr: 0,
+        ltype: LocationType::Stack,
+        atype,
+        is_mutable: true,
+        value: Val::I32(value),
+        frame: 0,
+    };
+    let consistent = MTable::new(std::vec![
+        entry(1, AccessType::Write, 1),
+        entry(2, AccessType::Read, 1),
+    ]);
+    assert!(consistent.validate().is_ok());
+    let inconsistent = MTable::new(std::vec![
+        entry(1, AccessType::Write, 1),
+        entry(2, AccessType::Read, 2),
+    ]);
+    let error = inconsistent.validate().unwrap_err();
+    assert_eq!(error.read.emid, 2);
+    assert_eq!(error.expected.i32(), Some(1));
+    assert_eq!(error.aliased_by, None);
+}
+
+#[test]
+fn validate_detects_stack_aliasing_between_frames() {
+    let entry = |emid, atype, value, frame| MemoryTableEntry {
+        eid: u64::from(emid),
+        emid,
+        addr: 5,
+        ltype: LocationType::Stack,
+        atype,
+        is_mutable: true,
+        value: Val::I32(value),
+        frame,
+    };
+    let distinct = MTable::new(std::vec![
+        entry(1, AccessType::Write, 7, 0),
+        entry(2, AccessType::Read, 3, 1),
+        entry(3, AccessType::Write, 3, 1),
+        entry(4, AccessType::Read, 3, 1),
+    ]);
+    assert!(distinct.validate().is_ok());
+    // The callee in frame 1 clobbers a register of its caller in frame 0.
+    let aliased = MTable::new(std::vec![
+        entry(1, AccessType::Write, 7, 0),
+        entry(2, AccessType::Write, 3, 1),
+        entry(3, AccessType::Read, 7, 0),
+    ]);
+    let error = aliased.validate().unwrap_err();
+    assert_eq!(error.read.emid, 3);
+    assert_eq!(error.expected.i32(), Some(7));
+    assert_eq!(error.aliased_by, Some(1));
+}
+
+/// A custom step swapping the heap words at `left` and `right`.
+#[derive(Debug)]
+struct SwapWords {
+    left: usize,
+    right: usize,
+    values: (i64, i64),
+}
+
+impl fmt::Display for SwapWords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "swap heap[{}] heap[{}]", self.left, self.right)
+    }
+}
+
+impl CustomStepInfo for SwapWords {
+    fn opcode(&self) -> String {
+        String::from("custom.swap")
+    }
+
+    fn memory_ops(&self) -> Vec<MemoryOp> {
+        let op = |addr, atype, value| MemoryOp {
+            addr,
+            ltype: LocationType::Heap,
+            atype,
+            is_mutable: true,
+            value: Val::I64(value),
+        };
+        let (left, right) = self.values;
+        vec![
+            op(self.left, AccessType::Read, left),
+            op(self.right, AccessType::Read, right),
+            op(self.left, AccessType::Write, right),
+            op(self.right, AccessType::Write, left),
+        ]
+    }
+}
+
+#[test]
+fn custom_steps_render_and_produce_their_memory_ops() {
+    let mut tracer = Tracer::new();
+    tracer.push_custom_step(
+        1,
+        SwapWords {
+            left: 2,
+            right: 5,
+            values: (7, 9),
+        },
+    );
+    let entry = &tracer.etable.entries()[0];
+    assert_eq!(entry.step_info.opcode(), "custom.swap");
+    assert_eq!(entry.step_info.memory_rows(), 4);
+    assert!(format!("{}", entry).contains("swap heap[2] heap[5]"));
+    let mtable = tracer.get_mtable();
+    assert!(mtable
+        .entries()
+        .iter()
+        .all(|mentry| mentry.eid == entry.eid));
+    let accesses = mtable
+        .entries()
+        .iter()
+        .map(|entry| (entry.emid, entry.addr, entry.atype, entry.value.i64()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        [
+            (1, 2, AccessType::Read, Some(7)),
+            (2, 5, AccessType::Read, Some(9)),
+            (3, 2, AccessType::Write, Some(9)),
+            (4, 5, AccessType::Write, Some(7)),
+        ]
+    );
+}
+
+/// A custom step copying the word at `data` of a passive segment into the table element `elem`.
+#[derive(Debug)]
+struct SegmentToTable {
+    data: usize,
+    elem: usize,
+}
+
+impl fmt::Display for SegmentToTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "copy data[{}] to table[{}]", self.data, self.elem)
+    }
+}
+
+impl CustomStepInfo for SegmentToTable {
+    fn opcode(&self) -> String {
+        String::from("custom.segment_to_table")
+    }
+
+    fn memory_ops(&self) -> Vec<MemoryOp> {
+        vec![
+            MemoryOp {
+                addr: self.data,
+                ltype: LocationType::Data,
+                atype: AccessType::Read,
+                is_mutable: false,
+                value: Val::I64(3),
+            },
+            MemoryOp {
+                addr: self.elem,
+                ltype: LocationType::Table,
+                atype: AccessType::Write,
+                is_mutable: true,
+                value: Val::I64(3),
+            },
+        ]
+    }
+}
+
+#[test]
+fn custom_steps_accessing_segments_and_tables_are_partitioned() {
+    let mut tracer = Tracer::new();
+    tracer.push_custom_step(
+        1,
+        SwapWords {
+            left: 2,
+            right: 5,
+            values: (7, 9),
+        },
+    );
+    tracer.push_custom_step(1, SegmentToTable { data: 4, elem: 1 });
+    let (stack, heap, global, segments) = tracer.get_mtable().partition();
+    assert!(stack.entries().is_empty() && global.entries().is_empty());
+    assert_eq!(heap.entries().len(), 4);
+    let accesses = segments
+        .entries()
+        .iter()
+        .map(|entry| (entry.ltype, entry.addr, entry.atype))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        [
+            (LocationType::Data, 4, AccessType::Read),
+            (LocationType::Table, 1, AccessType::Write),
+        ]
+    );
+}
+
+#[test]
+fn hot_opcodes_ranks_loop_body_highest() {
+    let wat = r#"
+        (module
+            (func (export "sum") (param $n i32) (result i32)
+                (local $i i32)
+                (local $sum i32)
+                (loop $continue
+                    (local.set $sum (i32.add (local.get $sum) (local.get $i)))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $continue (i32.lt_s (local.get $i) (local.get $n)))
+                )
+                (local.get $sum)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "sum", &[Val::I32(100)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(4950));
+    let hot = tracer.etable.hot_opcodes(3);
+    assert_eq!(hot.len(), 3);
+    assert!(hot.iter().all(|(_, count)| *count == 100));
+    assert!(hot.iter().any(|(opcode, _)| opcode == "i32.add"));
+    let histogram = tracer.etable.histogram();
+    assert_eq!(
+        histogram.values().sum::<usize>(),
+        tracer.etable.entries().len()
+    );
+    assert!(histogram.values().all(|count| *count <= 100));
+}
+
+#[test]
+fn steps_are_attributed_to_the_executing_function() {
+    let wat = r#"
+        (module
+            (func $square (param i32) (result i32)
+                (i32.mul (local.get 0) (local.get 0))
+            )
+            (func (export "sum_squares") (param $n i32) (result i32)
+                (local $sum i32)
+                (block $done
+                    (loop $continue
+                        (br_if $done (i32.eqz (local.get $n)))
+                        (local.set $sum
+                            (i32.add (local.get $sum) (call $square (local.get $n)))
+                        )
+                        (local.set $n (i32.sub (local.get $n) (i32.const 1)))
+                        (br $continue)
+                    )
+                )
+                (local.get $sum)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "sum_squares", &[Val::I32(3)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(1 + 4 + 9));
+    // Each call of `$square` executes its multiplication and return while each
+    // iteration of the loop executes a `br_if`, the call, two additions and a `br`.
+    // The last iteration only executes the `br_if` that exits the loop and the return.
+    let steps = tracer.etable.steps_per_function();
+    assert_eq!(steps, BTreeMap::from([(0, 3 * 2), (1, 3 * 5 + 2)]));
+    assert_eq!(steps.values().sum::<usize>(), tracer.etable.entries().len());
+}
+
+#[test]
+fn callback_sink_streams_steps_without_retaining_them() {
+    let wat = r#"
+        (module
+            (func (export "sum") (param $n i32) (result i32)
+                (local $i i32)
+                (local $sum i32)
+                (loop $continue
+                    (local.set $sum (i32.add (local.get $sum) (local.get $i)))
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $continue (i32.lt_s (local.get $i) (local.get $n)))
+                )
+                (local.get $sum)
+            )
+        )"#;
+    let (buffered, _) = trace_wat(wat, "sum", &[Val::I32(100)]);
+    let eids = Arc::new(Mutex::new(Vec::new()));
+    let sink = CallbackSink::new({
+        let eids = eids.clone();
+        move |entry: &ETableEntry| eids.lock().unwrap().push(entry.eid)
+    });
+    let (streamed, result) =
+        trace_wat_with(Tracer::new().with_sink(sink), wat, "sum", &[Val::I32(100)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(4950));
+    let eids = eids.lock().unwrap();
+    let expected = buffered
+        .etable
+        .entries()
+        .iter()
+        .map(|entry| entry.eid)
+        .collect::<Vec<_>>();
+    assert_eq!(*eids, expected);
+    // Only the last step is retained, e.g. to read the results of the trace.
+    assert_eq!(streamed.etable.entries().len(), 1);
+    assert_eq!(streamed.etable.steps(), expected.len() as u64);
+    let (_, results) = streamed.io_boundary();
+    assert_eq!(results[0].i32(), Some(4950));
+    // A shared `VecSink` buffers the steps outside of the tracer.
+    let sink = Arc::new(Mutex::new(VecSink::new()));
+    trace_wat_with(
+        Tracer::new().with_sink(sink.clone()),
+        wat,
+        "sum",
+        &[Val::I32(100)],
+    )
+    .1
+    .unwrap();
+    let opcodes = |entries: &[ETableEntry]| {
+        entries
+            .iter()
+            .map(|entry| entry.step_info.opcode())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        opcodes(sink.lock().unwrap().entries()),
+        opcodes(buffered.etable.entries())
+    );
+}
+
+#[test]
+fn drop_of_a_multi_value_result_reads_nothing() {
+    let wat = r#"
+        (module
+            (func $pair (param i32) (result i32 i32)
+                (i32.mul (local.get 0) (local.get 0))
+                (i32.add (local.get 0) (local.get 0))
+            )
+            (func (export "first") (param $x i32) (result i32)
+                (call $pair (local.get $x))
+                (drop)
+                (i32.add (local.get $x))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "first", &[Val::I32(3)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(12));
+    let entries = tracer.etable.entries();
+    let (pair_return, kept, dropped) = entries
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::Return { results, .. } if results.len() == 2 => {
+                Some((entry.eid, results[0].clone(), results[1].clone()))
+            }
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(kept.val.i64(), Some(9));
+    assert_eq!(dropped.val.i64(), Some(6));
+    // The `drop` executes no instruction and thus records no step.
+    let next = entries
+        .iter()
+        .find(|entry| entry.eid == pair_return + 1)
+        .unwrap();
+    let StepInfo::I32BinOp {
+        class: BinOp::Add,
+        left,
+        right,
+        ..
+    } = &next.step_info
+    else {
+        panic!("expected an i32.add step but found: {}", next.step_info)
+    };
+    assert_eq!((left.addr, left.val.i32()), (kept.addr, Some(9)));
+    assert_eq!(right.val.i32(), Some(3));
+    // The dropped register is only read once the function frame is discarded.
+    let last = entries.last().unwrap();
+    let dropped_reads = tracer
+        .get_mtable()
+        .entries()
+        .iter()
+        .filter(|mentry| mentry.eid > pair_return && mentry.ltype == LocationType::Stack)
+        .filter(|mentry| Some(mentry.addr) == dropped.addr && mentry.atype == AccessType::Read)
+        .map(|mentry| mentry.eid)
+        .collect::<Vec<_>>();
+    assert_eq!(dropped_reads, [last.eid]);
+    let StepInfo::Return {
+        dropped: discarded, ..
+    } = &last.step_info
+    else {
+        panic!("expected a return step but found: {}", last.step_info)
+    };
+    assert!(discarded.iter().any(|ival| ival.addr == dropped.addr));
+}
+
+#[test]
+fn return_reads_dropped_registers() {
+    let wat = r#"
+        (module
+            (func (export "early") (param $x i32) (result i32)
+                (i32.add (local.get $x) (i32.const 1))
+                (i32.mul (local.get $x) (local.get $x))
+                (return (local.get $x))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "early", &[Val::I32(5)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(5));
+    let entry = tracer.etable.entries().last().unwrap();
+    let StepInfo::Return {
+        condition,
+        values,
+        results,
+        dropped,
+    } = &entry.step_info
+    else {
+        panic!("expected a return step but found: {}", entry.step_info)
+    };
+    assert!(condition.is_none());
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0].val.i64(), Some(5));
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].val.i64(), Some(5));
+    let dropped = dropped
+        .iter()
+        .map(|ival| ival.val.i64())
+        .collect::<Vec<_>>();
+    assert_eq!(dropped, [Some(6), Some(25)]);
+    let dropped_reads = tracer
+        .get_mtable()
+        .entries()
+        .iter()
+        .filter(|mentry| mentry.eid == entry.eid && mentry.atype == AccessType::Read)
+        .count();
+    assert_eq!(dropped_reads, 3);
+}
+
+#[test]
+fn reset_tracer_restarts_eids() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "double") (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let func = instance.get_func(&store, "double").unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let mut results = [Val::I32(0)];
+    let mut trace = |param| {
+        let memory = instance.get_memory(&store, "memory").unwrap();
+        tracer.borrow_mut().reset();
+        tracer
+            .borrow_mut()
+            .push_init_memory(memory, MemoryGranularity::Word64, &store)
+            .unwrap();
+        func.call_with_trace(&mut store, &[Val::I32(param)], &mut results, tracer.clone())
+            .unwrap();
+        let tracer = tracer.borrow();
+        let eids = tracer
+            .etable
+            .entries()
+            .iter()
+            .map(|entry| entry.eid)
+            .collect::<Vec<_>>();
+        let capacity = tracer.etable.entries().capacity();
+        let init_entries = tracer.imtable.entries().len();
+        (eids, capacity, init_entries, results[0].i32())
+    };
+    let (first, capacity, init_entries, result) = trace(1);
+    assert_eq!(result, Some(2));
+    let (second, reused_capacity, reused_init_entries, result) = trace(2);
+    assert_eq!(result, Some(4));
+    assert_eq!(second.first(), Some(&1));
+    assert_eq!(first, second);
+    assert_eq!(reused_capacity, capacity);
+    assert_eq!(reused_init_entries, init_entries);
+}
+
+#[test]
+fn traced_executions_run_on_spawned_threads() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func (export "double") (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    let workers = [20, 21]
+        .map(|input| {
+            let wasm = wasm.clone();
+            let tracer = Arc::new(Mutex::new(Tracer::new()));
+            let worker = thread::spawn({
+                let tracer = tracer.clone();
+                move || {
+                    let engine = Engine::default();
+                    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+                    let mut store = Store::new(&engine, ());
+                    let instance = <Linker<()>>::new(&engine)
+                        .instantiate(&mut store, &module)
+                        .unwrap()
+                        .start(&mut store)
+                        .unwrap();
+                    let func = instance.get_func(&store, "double").unwrap();
+                    let mut results = [Val::I32(0)];
+                    func.call_with_trace(&mut store, &[Val::I32(input)], &mut results, tracer)
+                        .unwrap();
+                    results[0].i32()
+                }
+            });
+            (input, tracer, worker)
+        })
+        .map(|(input, tracer, worker)| (input, tracer, worker.join().unwrap()));
+    for (input, tracer, result) in workers {
+        assert_eq!(result, Some(2 * input));
+        let tracer = mem::take(&mut *tracer.lock().unwrap());
+        let sums = tracer
+            .etable
+            .entries()
+            .iter()
+            .filter_map(|entry| match &entry.step_info {
+                StepInfo::I32BinOp { result, .. } => result.val.i32(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sums, [2 * input]);
+        tracer.etable.validate_eids().unwrap();
+    }
+}
+
+#[test]
+fn sequential_calls_accumulate_one_trace() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "store") (param i32 i32)
+                (i32.store (local.get 0) (local.get 1))
+            )
+            (func (export "load") (param i32) (result i32)
+                (i32.load (local.get 0))
+            )
+            (func (export "double") (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    tracer
+        .borrow_mut()
+        .push_init_memory(memory, MemoryGranularity::Word64, &store)
+        .unwrap();
+    let init_entries = tracer.borrow().imtable.entries().len();
+    let mut lens = Vec::new();
+    let mut call = |name: &str, params: &[Val], results: &mut [Val]| {
+        let func = instance.get_func(&store, name).unwrap();
+        func.call_with_trace(&mut store, params, results, tracer.clone())
+            .unwrap();
+        lens.push(tracer.borrow().etable.entries().len());
+    };
+    call("store", &[Val::I32(8), Val::I32(21)], &mut []);
+    let mut loaded = [Val::I32(0)];
+    call("load", &[Val::I32(8)], &mut loaded);
+    assert_eq!(loaded[0].i32(), Some(21));
+    let mut doubled = [Val::I32(0)];
+    call("double", &[loaded[0].clone()], &mut doubled);
+    assert_eq!(doubled[0].i32(), Some(42));
+
+    let tracer = tracer.take();
+    assert!(lens.windows(2).all(|lens| lens[0] < lens[1]));
+    assert_eq!(tracer.etable.entries().len(), lens[2]);
+    tracer.etable.validate_eids().unwrap();
+    assert_eq!(tracer.imtable.entries().len(), init_entries);
+    // The load of the second call observes the store of the first call.
+    let mtable = tracer.get_mtable();
+    mtable.validate().unwrap();
+    let heap = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Heap && entry.addr == 1)
+        .map(|entry| (entry.atype, entry.eid <= lens[0] as u64))
+        .collect::<Vec<_>>();
+    assert!(heap.contains(&(AccessType::Write, true)));
+    assert!(heap.contains(&(AccessType::Read, false)));
+}
+
+#[test]
+fn mtable_with_init_precedes_first_reads() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1)
+            (global $g (export "g") (mut i32) (i32.const 5))
+            (data (i32.const 16) "\07")
+            (func (export "bump") (result i32)
+                (global.set $g (i32.add (global.get $g) (i32.load (i32.const 16))))
+                (global.get $g)
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let global = instance.get_global(&store, "g").unwrap();
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_init_global(&global, &store);
+    tracer
+        .push_init_memory(memory, MemoryGranularity::Word64, &store)
+        .unwrap();
+    let tracer = Rc::new(RefCell::new(tracer));
+    let mut results = [Val::I32(0)];
+    instance
+        .get_func(&store, "bump")
+        .unwrap()
+        .call_with_trace(&mut store, &[], &mut results, tracer.clone())
+        .unwrap();
+    assert_eq!(results[0].i32(), Some(12));
+    let tracer = tracer.take();
+    let plain = tracer.get_mtable();
+    let mtable = tracer.get_mtable_with_init();
+    let inits = mtable
+        .entries()
+        .iter()
+        .take_while(|entry| entry.atype == AccessType::Init)
+        .map(|entry| (entry.eid, entry.emid, entry.ltype, entry.addr))
+        .collect::<Vec<_>>();
+    let global_addr = store.inner.global_address(&global);
+    assert_eq!(
+        inits,
+        [
+            (0, 0, LocationType::Heap, 2),
+            (0, 1, LocationType::Global, global_addr),
+        ]
+    );
+    assert_eq!(mtable.entries().len(), plain.entries().len() + 2);
+    mtable.validate().unwrap();
+    let sorted = mtable.sorted_by_address();
+    let global_accesses = sorted
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Global)
+        .map(|entry| (entry.atype, entry.value.i32()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        global_accesses[..2],
+        [(AccessType::Init, Some(5)), (AccessType::Read, Some(5))]
+    );
+}
+
+#[test]
+fn derived_next_imtable_matches_a_fresh_snapshot() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1 1)
+            (global $g (export "g") (mut i64) (i64.const 5))
+            (data (i32.const 16) "\07")
+            (func (export "segment") (param i32)
+                (i64.store (i32.const 8) (i64.load (i32.const 16)))
+                (i32.store (i32.const 16) (local.get 0))
+                (i64.store offset=64 (i32.const 0) (i64.const 0xABAB))
+                (global.set $g (i64.add (global.get $g) (i64.load (i32.const 8))))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    // Expands the entries of the `imtable` into the value of each location.
+    let locations = |imtable: &IMTable| {
+        let mut locations = BTreeMap::new();
+        for entry in imtable.entries() {
+            for offset in entry.start_offset..=entry.end_offset {
+                let value = (entry.is_mutable, ValType::from(&entry.vtype), entry.value);
+                let previous = locations.insert((entry.ltype, offset), value);
+                assert!(previous.map_or(true, |previous| previous == value));
+            }
+        }
+        locations
+    };
+    // Returns the `Init` rows of the traced locations.
+    let init_rows = |tracer: &Tracer| {
+        tracer
+            .get_mtable_with_init()
+            .entries()
+            .iter()
+            .filter(|entry| entry.atype == AccessType::Init)
+            .map(|entry| (entry.ltype, entry.addr, entry.value.i64()))
+            .collect::<Vec<_>>()
+    };
+    let mut init_rows_of_granularities = Vec::new();
+    for granularity in [
+        MemoryGranularity::Byte,
+        MemoryGranularity::Word32,
+        MemoryGranularity::Word64,
+    ] {
+        let mut store = Store::new(&engine, ());
+        let instance = <Linker<()>>::new(&engine)
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let memory = instance.get_memory(&store, "memory").unwrap();
+        let snapshot = |store: &Store<()>| {
+            let mut tracer = Tracer::new();
+            tracer.push_init_memory(memory, granularity, store).unwrap();
+            tracer.push_init_globals(&instance, store);
+            tracer
+        };
+        let tracer = Rc::new(RefCell::new(snapshot(&store)));
+        let initial = locations(&tracer.borrow().imtable);
+        instance
+            .get_func(&store, "segment")
+            .unwrap()
+            .call_with_trace(&mut store, &[Val::I32(-1)], &mut [], tracer.clone())
+            .unwrap();
+        let next = tracer.borrow().derive_next_imtable();
+        let fresh = snapshot(&store);
+        assert_eq!(locations(&next), locations(&fresh.imtable));
+        assert_ne!(locations(&next), initial);
+        // The initial state of the segment is left untouched.
+        assert_eq!(locations(&tracer.borrow().imtable), initial);
+        init_rows_of_granularities.push(init_rows(&tracer.borrow()));
+    }
+    // The 8-byte heap words are joined from the words of each granularity.
+    let word64 = init_rows_of_granularities.pop().unwrap();
+    assert!(word64.contains(&(LocationType::Heap, 2, Some(7))));
+    for init_rows in init_rows_of_granularities {
+        assert_eq!(init_rows, word64);
+    }
+}
+
+#[test]
+fn streamed_mtable_csv_matches_the_mtable() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $square (param i32) (result i32)
+                (i32.mul (local.get 0) (local.get 0))
+            )
+            (func (export "sum_squares") (param $n i32) (result i32)
+                (local $sum i32)
+                (block $done
+                    (loop $continue
+                        (br_if $done (i32.eqz (local.get $n)))
+                        (local.set $sum
+                            (i32.add (local.get $sum) (call $square (local.get $n)))
+                        )
+                        (i32.store (i32.const 0) (local.get $sum))
+                        (local.set $n (i32.add (local.get $n) (i32.const -1)))
+                        (br $continue)
+                    )
+                )
+                (i32.load (i32.const 0))
+            )
+        )"#;
+    let path = std::env::temp_dir().join(format!(
+        "wasmi-mtable-{}-{:?}.csv",
+        std::process::id(),
+        std::thread::current().id()
+    ));
+    let mut tracer = Tracer::new();
+    tracer.stream_mtable_csv(&path).unwrap();
+    let (mut tracer, result) = trace_wat_with(tracer, wat, "sum_squares", &[Val::I32(10)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(385));
+    tracer.finish_mtable_csv().unwrap();
+    let streamed = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut expected = String::new();
+    tracer.get_mtable().to_csv(&mut expected).unwrap();
+    assert!(streamed.lines().count() > 100);
+    assert_eq!(streamed, expected);
+}
+
+#[test]
+fn eids_continue_past_the_u32_range() {
+    let trap = || StepInfo::Trap {
+        code: TrapCode::UnreachableCodeReached,
+        reads: std::vec![IVal::stack(Val::I32(7), 0)],
+        data: Vec::new(),
+        func_index: 0,
+        pc: 0,
+    };
+    let mut etable = ETable::new(std::vec![ETableEntry {
+        eid: u64::from(u32::MAX),
+        func_index: 0,
+        pc: 0,
+        allocated_memory_pages: 0,
+        step_info: trap(),
+    }]);
+    etable.push(0, trap());
+    etable.push(0, trap());
+    let eids = etable
+        .entries()
+        .iter()
+        .map(|entry| entry.eid)
+        .collect::<Vec<_>>();
+    let boundary = u64::from(u32::MAX);
+    assert_eq!(eids, [boundary, boundary + 1, boundary + 2]);
+    assert_eq!(etable.validate_sparse_eids(), Ok(()));
+    let mtable = MTable::new(memory_events_of_steps(etable.entries()));
+    let decoded = MTable::from_bytes(&mtable.to_bytes()).unwrap();
+    let decoded_eids = decoded
+        .entries()
+        .iter()
+        .map(|entry| entry.eid)
+        .collect::<Vec<_>>();
+    assert_eq!(decoded_eids, eids);
+    // The eid column is wide enough for every eid.
+    let shown = format!("{}", etable.entries()[2]);
+    assert!(shown.starts_with(&format!("{:>20}", boundary + 2)));
+}
+
+#[test]
+fn pc_advances_within_a_straight_line_function() {
+    let wat = r#"
+        (module
+            (func $double (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))
+            )
+            (func (export "f") (param i32 i32) (result i32)
+                (local $x i32)
+                (local.set $x (i32.mul (local.get 0) (local.get 1)))
+                (local.set $x (i32.sub (local.get $x) (local.get 1)))
+                (i32.xor (call $double (local.get $x)) (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(3), Val::I32(5)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(((3 * 5 - 5) * 2) ^ 3));
+    let entries = tracer.etable.entries();
+    let (double, f): (Vec<_>, Vec<_>) = entries.iter().partition(|entry| entry.func_index == 0);
+    assert!(f.iter().all(|entry| entry.func_index == 1));
+    assert!(!double.is_empty() && f.len() > 2);
+    for steps in [&double, &f] {
+        assert!(steps.windows(2).all(|pair| pair[0].pc < pair[1].pc));
+    }
+    // The steps of the callee follow the call of the caller.
+    let call = entries
+        .iter()
+        .position(|entry| entry.func_index == 0)
+        .expect("missing steps of the callee");
+    assert_eq!(entries[call - 1].func_index, 1);
+    assert!(entries[call - 1].step_info.opcode().starts_with("call"));
+    let shown = format!("{}", entries[call]);
+    assert!(shown.starts_with(&format!("{:>20} {:10} {:10}", entries[call].eid, 0, 0)));
+}
+
+#[test]
+fn call_of_an_immediately_trapping_function_precedes_the_trap() {
+    let wat = r#"
+        (module
+            (func $fail (param i32)
+                (unreachable)
+            )
+            (func (export "f") (param i32)
+                (call $fail (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(7)]);
+    assert_eq!(
+        result.unwrap_err().as_trap_code(),
+        Some(TrapCode::UnreachableCodeReached)
+    );
+    let [call, trap] = &tracer.etable.entries()[..] else {
+        panic!(
+            "expected a call and a trap but found: {:?}",
+            tracer.etable.entries()
+        )
+    };
+    // The call enters the frame of the callee before its first instruction traps.
+    let StepInfo::Call {
+        func_index, params, ..
+    } = &call.step_info
+    else {
+        panic!("expected a call but found: {:?}", call.step_info)
+    };
+    assert_eq!(*func_index, 0);
+    assert_eq!(call.func_index, 1);
+    assert_eq!(params.len(), 1);
+    // Call operands are untyped and thus traced by their raw bits.
+    assert_eq!(params[0].val.i64(), Some(7));
+    let StepInfo::Trap {
+        code,
+        func_index,
+        pc,
+        ..
+    } = &trap.step_info
+    else {
+        panic!("expected a trap but found: {:?}", trap.step_info)
+    };
+    assert_eq!(*code, TrapCode::UnreachableCodeReached);
+    assert_eq!((*func_index, *pc), (0, 0));
+    assert_eq!((trap.func_index, trap.pc), (0, 0));
+    assert!(call.eid < trap.eid);
+}
+
+#[test]
+fn validate_eids() {
+    let table = |eids: &[u64]| {
+        ETable::new(
+            eids.iter()
+                .map(|&eid| ETableEntry {
+                    eid,
+                    func_index: 0,
+                    pc: 0,
+                    allocated_memory_pages: 0,
+                    step_info: StepInfo::Trap {
+                        code: TrapCode::UnreachableCodeReached,
+                        reads: Vec::new(),
+                        data: Vec::new(),
+                        func_index: 0,
+                        pc: 0,
+                    },
+                })
+                .collect(),
+        )
+    };
+    let (tracer, _) = trace_wat(
+        r#"(module (func (export "f") (param i32) (result i32) (i32.add (local.get 0) (local.get 0))))"#,
+        "f",
+        &[Val::I32(1)],
+    );
+    assert_eq!(tracer.etable.validate_eids(), Ok(()));
+    assert_eq!(table(&[1, 2, 3]).validate_eids(), Ok(()));
+    assert_eq!(
+        table(&[1, 3, 2]).validate_eids(),
+        Err(EidError::Gap {
+            expected: 2,
+            eid: 3
+        })
+    );
+    assert_eq!(
+        table(&[1, 3, 2]).validate_sparse_eids(),
+        Err(EidError::NotIncreasing { prev: 3, eid: 2 })
+    );
+    assert_eq!(
+        table(&[0]).validate_sparse_eids(),
+        Err(EidError::NotIncreasing { prev: 0, eid: 0 })
+    );
+    let filtered = table(&[1, 4, 7]);
+    assert_eq!(filtered.validate_sparse_eids(), Ok(()));
+    assert_eq!(
+        filtered.validate_eids(),
+        Err(EidError::Gap {
+            expected: 2,
+            eid: 4
+        })
+    );
+}
+
+#[test]
+fn br_table_clamps_out_of_bounds_index() {
+    let wat = r#"
+        (module
+            (func (export "classify") (param $index i32) (result i32)
+                (block $default
+                    (block $one
+                        (block $zero
+                            (br_table $zero $one $default (local.get $index))
+                        )
+                        (return (i32.const 10))
+                    )
+                    (return (i32.const 11))
+                )
+                (i32.const 12)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "classify", &[Val::I32(u32::MAX as i32)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(12));
+    let (index, raw_index, effective_index, len_targets) = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::BrTable {
+                index,
+                raw_index,
+                effective_index,
+                len_targets,
+                ..
+            } => Some((index.clone(), *raw_index, *effective_index, *len_targets)),
+            _ => None,
+        })
+        .expect("missing br_table step");
+    assert_eq!(raw_index, u32::MAX);
+    assert_eq!(effective_index, 2);
+    assert_eq!(len_targets, 3);
+    let mtable = tracer.get_mtable();
+    let index_read = mtable
+        .entries()
+        .iter()
+        .find(|entry| entry.atype == AccessType::Read && Some(entry.addr) == index.addr)
+        .expect("missing br_table index read");
+    assert_eq!(index_read.value.i32(), Some(-1));
+}
+
+#[test]
+fn br_if_records_whether_the_branch_was_taken() {
+    let wat = r#"
+        (module
+            (func (export "pick") (param $condition i32) (result i32)
+                (block $skip
+                    (br_if $skip (local.get $condition))
+                    (return (i32.const 1))
+                )
+                (i32.const 2)
+            )
+        )"#;
+    let mut target_pcs = Vec::new();
+    for (condition, expected, taken) in [(7, 2, true), (0, 1, false)] {
+        let (tracer, result) = trace_wat(wat, "pick", &[Val::I32(condition)]);
+        assert_eq!(result.unwrap()[0].i32(), Some(expected));
+        let (cmp, lhs, rhs, step_taken, target_pc) = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                StepInfo::BrIf {
+                    cmp,
+                    lhs,
+                    rhs,
+                    taken,
+                    target_pc,
+                } => Some((*cmp, lhs.clone(), rhs.clone(), *taken, *target_pc)),
+                _ => None,
+            })
+            .expect("missing br_if step");
+        // A plain `br_if` branches if its condition is not zero.
+        assert_eq!(cmp, BranchComparator::I32Ne);
+        assert_eq!(rhs.addr, None);
+        assert_eq!(rhs.val.i32(), Some(0));
+        assert_eq!(step_taken, taken);
+        let mtable = tracer.get_mtable();
+        let condition_read = mtable
+            .entries()
+            .iter()
+            .find(|entry| entry.atype == AccessType::Read && Some(entry.addr) == lhs.addr)
+            .expect("missing br_if condition read");
+        assert_eq!(condition_read.value.i32(), Some(condition));
+        target_pcs.push(target_pc);
+    }
+    // The branch target does not depend on whether the branch was taken.
+    assert_eq!(target_pcs[0], target_pcs[1]);
+}
+
+#[test]
+fn br_table_continues_at_its_selected_target() {
+    let wat = r#"
+        (module
+            (func (export "count") (param $n i32) (result i32)
+                (local $sum i32)
+                (block $done
+                    (loop $continue
+                        (local.set $sum (i32.add (local.get $sum) (local.get $n)))
+                        (local.set $n (i32.add (local.get $n) (i32.const -1)))
+                        (br_table $continue $continue $done (local.get $n))
+                    )
+                )
+                (local.get $sum)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "count", &[Val::I32(1)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(1));
+    let steps = tracer
+        .etable
+        .entries()
+        .iter()
+        .map(|entry| &entry.step_info)
+        .collect::<Vec<_>>();
+    let (position, target_pc) = steps
+        .iter()
+        .enumerate()
+        .find_map(|(position, step)| match step {
+            StepInfo::BrTable { target_pc, .. } => Some((position, *target_pc)),
+            _ => None,
+        })
+        .expect("missing br_table step");
+    // The selected target is a `br` back to the loop header.
+    let StepInfo::Br { target_pc: loop_pc } = steps[position + 1] else {
+        panic!("expected br after br_table: {:?}", steps[position + 1])
+    };
+    assert!(*loop_pc < target_pc);
+}
+
+#[test]
+fn only_func_skips_the_steps_of_other_functions() {
+    let wat = r#"
+        (module
+            (func $a (export "a") (param $x i32) (result i32)
+                (i32.add (call $b (local.get $x)) (local.get $x))
+            )
+            (func $b (param $x i32) (result i32)
+                (i32.add (local.get $x) (local.get $x))
+            )
+        )"#;
+    let left_operands = |tracer: &Tracer| {
+        tracer
+            .etable
+            .entries()
+            .iter()
+            .filter_map(|entry| match &entry.step_info {
+                StepInfo::I32BinOp { left, .. } => left.val.i32(),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    let (tracer, result) = trace_wat(wat, "a", &[Val::I32(5)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(15));
+    assert_eq!(left_operands(&tracer), [5, 10]);
+    let (tracer, result) = trace_wat_with(Tracer::new().only_func(1), wat, "a", &[Val::I32(5)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(15));
+    assert_eq!(left_operands(&tracer), [5]);
+    // The call of `a` into `b` is skipped but the return of `b` is traced.
+    let steps = tracer
+        .etable
+        .entries()
+        .iter()
+        .map(|entry| &entry.step_info)
+        .collect::<Vec<_>>();
+    assert!(!steps
+        .iter()
+        .any(|step| matches!(step, StepInfo::Call { .. })));
+    assert!(matches!(steps.last(), Some(StepInfo::Return { .. })));
+    assert_eq!(tracer.etable.validate_eids(), Ok(()));
+}
+
+#[test]
+fn call_steps_carry_func_names() {
+    let wat = r#"
+        (module
+            (import "env" "unused" (func $imported))
+            (func $double (param $x i32) (result i32)
+                (i32.add (local.get $x) (local.get $x))
+            )
+            (func (export "quadruple") (param $x i32) (result i32)
+                (call $double (call $double (local.get $x)))
+            )
+        )"#;
+    let wasm = wat::parse_str(wat).unwrap();
+    let module = Module::new(&Engine::default(), &mut &wasm[..]).unwrap();
+    assert_eq!(module.get_func_name(0), Some("imported"));
+    assert_eq!(module.get_func_name(1), Some("double"));
+    assert_eq!(module.get_func_name(2), None);
+    let wat = wat.replace(r#"(import "env" "unused" (func $imported))"#, "");
+    let wasm = wat::parse_str(&wat).unwrap();
+    let module = Module::new(&Engine::default(), &mut &wasm[..]).unwrap();
+    let tracer = Tracer::new().with_func_names(&module);
+    let (tracer, result) = trace_wat_with(tracer, &wat, "quadruple", &[Val::I32(3)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(12));
+    let calls = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::Call {
+                func_index, name, ..
+            } => Some((*func_index, name.clone())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        calls,
+        [
+            (0, Some(String::from("double"))),
+            (0, Some(String::from("double")))
+        ]
+    );
+    let (unnamed, _) = trace_wat(&wat, "quadruple", &[Val::I32(3)]);
+    assert!(unnamed.etable.entries().iter().any(|entry| matches!(
+        entry.step_info,
+        StepInfo::Call {
+            func_index: 0,
+            name: None,
+            ..
+        }
+    )));
+}
+
+#[test]
+fn grown_memory_is_zero_initialized() {
+    let wat = r#"
+        (module
+            (memory 1 4)
+            (func (export "grow_and_load") (param $delta i32) (result i32)
+                (drop (memory.grow (local.get $delta)))
+                (i32.load offset=65544 (i32.const 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "grow_and_load", &[Val::I32(1)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(0));
+    // The loaded cell at byte 65544 is the word 8193 of the newly grown second page.
+    let word = 65544 / 8;
+    let init = tracer
+        .imtable
+        .entries()
+        .iter()
+        .find(|entry| {
+            entry.ltype == LocationType::Heap
+                && entry.start_offset <= word
+                && word <= entry.end_offset
+        })
+        .expect("missing init entry for the grown memory");
+    assert_eq!((init.start_offset, init.end_offset), (8192, 2 * 8192 - 1));
+    assert_eq!(init.value, 0);
+    let mtable = tracer.get_mtable();
+    let read = mtable
+        .entries()
+        .iter()
+        .find(|entry| entry.ltype == LocationType::Heap && entry.addr == word as usize)
+        .expect("missing read of the grown memory");
+    assert_eq!(read.atype, AccessType::Read);
+    assert_eq!(read.value.i64(), Some(init.value as i64));
+    mtable.validate().unwrap();
+}
+
+#[test]
+fn init_memory_collapses_runs_of_zero_words() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 4)
+            (data (i32.const 1000) "\01\02\03\04\05\06\07\08")
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let mut tracer = Tracer::new();
+    tracer
+        .push_init_memory(memory, MemoryGranularity::Word64, &store)
+        .unwrap();
+    let entries = tracer
+        .imtable
+        .entries()
+        .iter()
+        .map(|entry| (entry.start_offset, entry.end_offset, entry.value))
+        .collect::<Vec<_>>();
+    // The byte offset 1000 is the start of the word 125.
+    let word = u64::from_le_bytes([1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(entries, [(0, 124, 0), (125, 125, word), (126, u32::MAX, 0)]);
+}
+
+#[test]
+fn lazy_init_mode_records_only_read_locations() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1024)
+            (global $g (mut i64) (i64.const 5))
+            (data (i32.const 800000) "\07")
+            (func (export "sum") (result i64)
+                (i64.store (i32.const 16) (i64.const 3))
+                (i64.add
+                    (i64.add
+                        (i64.load (i32.const 16))
+                        (i64.load (i32.const 800000))
+                    )
+                    (i64.add
+                        (i64.load (i32.const 60000000))
+                        (global.get $g)
+                    )
+                )
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new().lazy_init_mode()));
+    let mut results = [Val::I64(0)];
+    instance
+        .get_func(&store, "sum")
+        .unwrap()
+        .call_with_trace(&mut store, &[], &mut results, tracer.clone())
+        .unwrap();
+    assert_eq!(results[0].i64(), Some(3 + 7 + 5));
+    let tracer = tracer.take();
+    // The word written before it is read needs no initial value.
+    let inits = tracer
+        .imtable
+        .entries()
+        .iter()
+        .map(|entry| {
+            assert_eq!(entry.start_offset, entry.end_offset);
+            (entry.ltype, entry.start_offset, entry.value)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        inits,
+        [
+            (LocationType::Heap, 100_000, 7),
+            (LocationType::Heap, 7_500_000, 0),
+            (LocationType::Global, 0, 5),
+        ]
+    );
+    let mtable = tracer.get_mtable_with_init();
+    mtable.validate().unwrap();
+    let init_rows = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.atype == AccessType::Init)
+        .count();
+    assert_eq!(init_rows, 3);
+}
+
+#[test]
+fn init_memory_reports_reads_beyond_the_memory() {
+    let wasm = wat::parse_str(r#"(module (memory (export "memory") 1 2))"#).unwrap();
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let mut tracer = Tracer::new();
+    tracer
+        .push_init_memory(memory, MemoryGranularity::Word64, &store)
+        .unwrap();
+    let recorded = tracer.imtable.entries().len();
+    assert_eq!(recorded, 1);
+    // The data of a single page misses the last word of the page.
+    let data = &memory.data(&store)[..65536 - 8];
+    let error = tracer
+        .push_init_words(data, 1, Some(2), MemoryGranularity::Word64)
+        .unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        ErrorKind::Memory(MemoryError::OutOfBoundsAccess)
+    ));
+    assert_eq!(tracer.imtable.entries().len(), recorded);
+}
+
+#[test]
+fn init_memory_of_a_memory_without_pages_records_nothing() {
+    let wasm = wat::parse_str(r#"(module (memory (export "memory") 0 0))"#).unwrap();
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    for granularity in [
+        MemoryGranularity::Byte,
+        MemoryGranularity::Word32,
+        MemoryGranularity::Word64,
+    ] {
+        let mut tracer = Tracer::new();
+        tracer
+            .push_init_memory(memory, granularity, &store)
+            .unwrap();
+        assert!(tracer.imtable.entries().is_empty());
+    }
+}
+
+/// Returns the `(start, end, vtype, value)` of the entries recorded for the memory
+/// of a module with 8 non-zero bytes at offset 1000 in words of `granularity`.
+fn init_memory_entries(granularity: MemoryGranularity) -> Vec<(u32, u32, ValueType, u64)> {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1 2)
+            (data (i32.const 1000) "\01\02\03\04\05\06\07\08"))
+        "#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let mut tracer = Tracer::new();
+    tracer
+        .push_init_memory(memory, granularity, &store)
+        .unwrap();
+    tracer
+        .imtable
+        .entries()
+        .iter()
+        .map(|entry| {
+            (
+                entry.start_offset,
+                entry.end_offset,
+                entry.vtype,
+                entry.value,
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn init_memory_in_bytes_records_each_non_zero_byte() {
+    let entries = init_memory_entries(MemoryGranularity::Byte);
+    assert_eq!(entries.len(), 10);
+    assert!(entries.iter().all(|entry| entry.2 == ValueType::I32));
+    assert_eq!(entries[0], (0, 999, ValueType::I32, 0));
+    let bytes: Vec<_> = entries[1..9]
+        .iter()
+        .map(|entry| (entry.0, entry.3))
+        .collect();
+    assert_eq!(bytes, (1000..1008).zip(1..=8).collect::<Vec<_>>());
+    // The trailing run extends to the maximum of 2 pages.
+    assert_eq!(entries[9], (1008, 2 * 65536 - 1, ValueType::I32, 0));
+}
+
+#[test]
+fn init_memory_in_32_bit_words_records_i32_words() {
+    let entries = init_memory_entries(MemoryGranularity::Word32);
+    assert_eq!(
+        entries,
+        [
+            (0, 249, ValueType::I32, 0),
+            (250, 250, ValueType::I32, 0x0403_0201),
+            (251, 251, ValueType::I32, 0x0807_0605),
+            (252, 2 * 16384 - 1, ValueType::I32, 0),
+        ]
+    );
+}
+
+#[test]
+fn init_memory_in_64_bit_words_records_i64_words() {
+    let entries = init_memory_entries(MemoryGranularity::Word64);
+    assert_eq!(
+        entries,
+        [
+            (0, 124, ValueType::I64, 0),
+            (125, 125, ValueType::I64, 0x0807_0605_0403_0201),
+            (126, 2 * 8192 - 1, ValueType::I64, 0),
+        ]
+    );
+}
+
+#[test]
+fn grow_callback_fires_on_successful_grows() {
+    let wat = r#"
+        (module
+            (memory 1 4)
+            (func (export "grow_twice") (param $delta i32) (result i32)
+                (drop (memory.grow (local.get $delta)))
+                (memory.grow (local.get $delta))
+            )
+        )"#;
+    let grows = Arc::new(Mutex::new(Vec::new()));
+    let mut tracer = Tracer::new();
+    tracer.set_grow_cb({
+        let grows = grows.clone();
+        move |old_pages, new_pages| grows.lock().unwrap().push((old_pages, new_pages))
+    });
+    // The second grow fails since it exceeds the maximum of 4 pages.
+    let (_, result) = trace_wat_with(tracer, wat, "grow_twice", &[Val::I32(2)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(-1));
+    assert_eq!(*grows.lock().unwrap(), [(1, 3)]);
+}
+
+#[test]
+fn memory_grow_updates_the_allocated_pages_of_later_steps() {
+    let wat = r#"
+        (module
+            (memory 1 3)
+            (func (export "grow") (param $delta i32) (result i32)
+                (drop (memory.grow (local.get $delta)))
+                (memory.size)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "grow", &[Val::I32(2)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(3));
+    let entries = tracer.etable.entries();
+    let grow = entries
+        .iter()
+        .position(|entry| matches!(entry.step_info, StepInfo::MemoryGrow { .. }))
+        .expect("missing memory.grow step");
+    let StepInfo::MemoryGrow {
+        delta,
+        previous_pages,
+        result,
+    } = &entries[grow].step_info
+    else {
+        unreachable!()
+    };
+    assert_eq!(delta.val.i32(), Some(2));
+    assert_eq!((*previous_pages, result.val.i32()), (1, Some(1)));
+    assert_eq!(entries[grow].allocated_memory_pages, 1);
+    assert_eq!(entries[grow + 1].allocated_memory_pages, 3);
+    assert!(entries.iter().any(|entry| matches!(
+        &entry.step_info,
+        StepInfo::MemorySize { pages: 3, result } if result.val.i32() == Some(3)
+    )));
+    tracer.get_mtable().validate().unwrap();
+    // A failed grow keeps the number of pages from before the step.
+    let (tracer, result) = trace_wat(wat, "grow", &[Val::I32(3)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(1));
+    let entries = tracer.etable.entries();
+    let grow = entries
+        .iter()
+        .position(|entry| matches!(entry.step_info, StepInfo::MemoryGrow { .. }))
+        .expect("missing memory.grow step");
+    assert!(matches!(
+        &entries[grow].step_info,
+        StepInfo::MemoryGrow { previous_pages: 1, result, .. } if result.val.i32() == Some(-1)
+    ));
+    assert_eq!(entries[grow + 1].allocated_memory_pages, 1);
+}
+
+#[test]
+fn allocated_pages_are_committed_pages() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory (export "memory") 1 8)
+            (func (export "grow") (param $delta i32) (result i32)
+                (drop (memory.grow (local.get $delta)))
+                (memory.size)
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let memory = instance.get_memory(&store, "memory").unwrap();
+    let func = instance.get_func(&store, "grow").unwrap();
+    for delta in [2, 3] {
+        let tracer = Rc::new(RefCell::new(Tracer::new()));
+        let mut pages = [Val::I32(0)];
+        func.call_with_trace(&mut store, &[Val::I32(delta)], &mut pages, tracer.clone())
+            .unwrap();
+        let tracer = tracer.take();
+        let last = tracer.etable.entries().last().unwrap();
+        // Every allocated page is backed by bytes of the linear memory.
+        assert_eq!(last.allocated_memory_pages as i32, pages[0].i32().unwrap());
+        assert_eq!(
+            memory.data(&store).len(),
+            last.allocated_memory_pages as usize * 65536
+        );
+    }
+}
+
+#[test]
+fn loads_of_every_width_read_the_overlapping_words() {
+    // Byte `8 + i` holds `0x80 + i` so that every loaded byte has its sign bit set.
+    let pattern = (0x80..0x90_u8).collect::<Vec<_>>();
+    let address = 15;
+    for (op, ty, width, signed) in [
+        ("i32.load", "i32", 4, false),
+        ("i64.load", "i64", 8, false),
+        ("f32.load", "f32", 4, false),
+        ("f64.load", "f64", 8, false),
+        ("i32.load8_s", "i32", 1, true),
+        ("i32.load8_u", "i32", 1, false),
+        ("i32.load16_s", "i32", 2, true),
+        ("i32.load16_u", "i32", 2, false),
+        ("i64.load8_s", "i64", 1, true),
+        ("i64.load8_u", "i64", 1, false),
+        ("i64.load16_s", "i64", 2, true),
+        ("i64.load16_u", "i64", 2, false),
+        ("i64.load32_s", "i64", 4, true),
+        ("i64.load32_u", "i64", 4, false),
+    ] {
+        let wat = format!(
+            r#"
+            (module
+                (memory 1)
+                (data (i32.const 8) "\80\81\82\83\84\85\86\87\88\89\8a\8b\8c\8d\8e\8f")
+                (func (export "load") (param i32) (result {ty})
+                    ({op} (local.get 0))
+                )
+            )"#
+        );
+        let (tracer, result) = trace_wat(&wat, "load", &[Val::I32(address)]);
+        result.unwrap();
+        let step = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                step @ StepInfo::Load { .. } => Some(step),
+                _ => None,
+            })
+            .expect("missing load step");
+        let StepInfo::Load {
+            width: traced_width,
+            signed: traced_signed,
+            effective_address,
+            value,
+            words,
+            ..
+        } = step
+        else {
+            unreachable!()
+        };
+        assert_eq!(step.opcode(), op);
+        assert_eq!((*traced_width, *traced_signed), (width, signed));
+        assert_eq!(*effective_address, address as u64);
+        // Extend the loaded little-endian bytes to the bits of the loaded value.
+        let start = address as usize - 8;
+        let bytes = &pattern[start..start + width as usize];
+        let mut bits = bytes
+            .iter()
+            .rev()
+            .fold(0_u64, |bits, byte| bits << 8 | u64::from(*byte));
+        if signed {
+            let unused = 64 - 8 * width;
+            bits = ((bits << unused) as i64 >> unused) as u64;
+        }
+        let value_bits = match value.val {
+            Val::I32(value) => u64::from(value as u32),
+            Val::I64(value) => value as u64,
+            Val::F32(value) => u64::from(value.to_bits()),
+            Val::F64(value) => value.to_bits(),
+            _ => unreachable!(),
+        };
+        let type_bits = if ty.ends_with("32") { 32 } else { 64 };
+        assert_eq!(value_bits, bits & (u64::MAX >> (64 - type_bits)), "{op}");
+        let first = address as usize / 8;
+        let last = (address as usize + width as usize - 1) / 8;
+        let indices = words.iter().map(|word| word.index).collect::<Vec<_>>();
+        assert_eq!(indices, (first..=last).collect::<Vec<_>>(), "{op}");
+        let mtable = tracer.get_mtable();
+        let heap_reads = mtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.ltype == LocationType::Heap)
+            .map(|entry| (entry.atype, entry.addr))
+            .collect::<Vec<_>>();
+        let expected = (first..=last)
+            .map(|index| (AccessType::Read, index))
+            .collect::<Vec<_>>();
+        assert_eq!(heap_reads, expected, "{op}");
+    }
+}
+
+#[test]
+fn stores_of_every_width_write_the_overlapping_words() {
+    let address = 13;
+    for (op, ty, width, value, bits) in [
+        ("i32.store", "i32", 4, Val::I32(-2), 0xffff_fffe),
+        ("i64.store", "i64", 8, Val::I64(-2), 0xffff_ffff_ffff_fffe),
+        ("f32.store", "f32", 4, Val::F32(1.5.into()), 0x3fc0_0000),
+        ("f64.store", "f64", 8, Val::F64(1.5.into()), 0x3ff8 << 48),
+        ("i32.store8", "i32", 1, Val::I32(-2), 0xfe),
+        ("i32.store16", "i32", 2, Val::I32(-2), 0xfffe),
+        ("i64.store8", "i64", 1, Val::I64(-2), 0xfe),
+        ("i64.store16", "i64", 2, Val::I64(-2), 0xfffe),
+        ("i64.store32", "i64", 4, Val::I64(-2), 0xffff_fffe),
+    ] {
+        let wat = format!(
+            r#"
+            (module
+                (memory 1)
+                (func (export "store") (param i32 {ty})
+                    ({op} (local.get 0) (local.get 1))
+                )
+            )"#
+        );
+        let (tracer, result) = trace_wat(&wat, "store", &[Val::I32(address), value]);
+        result.unwrap();
+        let step = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                step @ StepInfo::Store { .. } => Some(step),
+                _ => None,
+            })
+            .expect("missing store step");
+        let StepInfo::Store {
+            width: traced_width,
+            effective_address,
+            ..
+        } = step
+        else {
+            unreachable!()
+        };
+        assert_eq!(step.opcode(), op);
+        assert_eq!(*traced_width, width);
+        assert_eq!(*effective_address, address as u64);
+        // Only the low `width` bytes of the value are written into the zeroed memory.
+        let mut memory = [0x00_u8; 24];
+        let start = address as usize;
+        memory[start..start + width as usize]
+            .copy_from_slice(&u64::to_le_bytes(bits)[..width as usize]);
+        let first = start / 8;
+        let last = (start + width as usize - 1) / 8;
+        let expected = (first..=last)
+            .map(|index| {
+                let mut word = [0x00_u8; 8];
+                word.copy_from_slice(&memory[index * 8..index * 8 + 8]);
+                let word = u64::from_le_bytes(word) as i64;
+                (AccessType::Write, index, Some(word))
+            })
+            .collect::<Vec<_>>();
+        let mtable = tracer.get_mtable();
+        let heap_writes = mtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.ltype == LocationType::Heap)
+            .map(|entry| (entry.atype, entry.addr, entry.value.i64()))
+            .collect::<Vec<_>>();
+        assert_eq!(heap_writes, expected, "{op}");
+    }
+}
+
+#[test]
+fn coalesced_spans_cover_the_words_of_a_wide_load() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "load") (param i32) (result i64)
+                (i64.load (local.get 0))
+            )
+        )"#;
+    // The unaligned load reads the words 1 and 2.
+    let (tracer, result) = trace_wat(wat, "load", &[Val::I32(13)]);
+    result.unwrap();
+    let mtable = tracer.get_mtable();
+    let spans = mtable.coalesce_spans();
+    let heap = spans
+        .iter()
+        .filter(|span| span.entries[0].ltype == LocationType::Heap)
+        .collect::<Vec<_>>();
+    assert_eq!(heap.len(), 1);
+    assert_eq!(heap[0].addresses(), 1..=2);
+    assert_eq!(heap[0].entries[0].atype, AccessType::Read);
+    // The spans keep every expanded entry in order.
+    let expanded = spans
+        .iter()
+        .flat_map(|span| span.entries)
+        .map(|entry| (entry.eid, entry.emid))
+        .collect::<Vec<_>>();
+    let entries = mtable
+        .entries()
+        .iter()
+        .map(|entry| (entry.eid, entry.emid))
+        .collect::<Vec<_>>();
+    assert_eq!(expanded, entries);
+}
+
+#[test]
+fn memory_init_traps_record_the_read_segment_words() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory 1)
+            (data $passive "0123456789")
+            (func (export "init") (param $dst i32) (param $src i32) (param $len i32)
+                (memory.init $passive (local.get $dst) (local.get $src) (local.get $len))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let trap = |params: [i32; 3]| {
+        let mut store = Store::new(&engine, ());
+        let instance = <Linker<()>>::new(&engine)
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let mut tracer = Tracer::new();
+        tracer.push_passive_segments(&module);
+        let tracer = Rc::new(RefCell::new(tracer));
+        let error = instance
+            .get_func(&store, "init")
+            .unwrap()
+            .call_with_trace(&mut store, &params.map(Val::I32), &mut [], tracer.clone())
+            .unwrap_err();
+        assert_eq!(error.as_trap_code(), Some(TrapCode::MemoryOutOfBounds));
+        let tracer = tracer.take();
+        let base = tracer.passive_data_address(0).unwrap();
+        let Some(StepInfo::Trap {
+            code, reads, data, ..
+        }) = tracer.etable.entries().last().map(|entry| &entry.step_info)
+        else {
+            panic!("expected a trap step");
+        };
+        assert_eq!(*code, TrapCode::MemoryOutOfBounds);
+        let reads = reads.iter().map(|read| read.val.i32()).collect::<Vec<_>>();
+        assert_eq!(reads, params.map(Some));
+        let data = data
+            .iter()
+            .map(|word| (word.index - base as usize, word.value.to_le_bytes()))
+            .collect::<Vec<_>>();
+        let mtable = tracer.get_mtable();
+        let data_reads = mtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.ltype == LocationType::Data)
+            .inspect(|entry| assert_eq!(entry.atype, AccessType::Read))
+            .count();
+        assert_eq!(data_reads, data.len());
+        data
+    };
+    // The source range `6..16` overruns the segment after the bytes `6..10`
+    // which lie within the words `0` and `1` of the segment.
+    assert_eq!(
+        trap([0, 6, 10]),
+        [(0, *b"01234567"), (1, *b"89\0\0\0\0\0\0")]
+    );
+    // The destination overruns the memory so the segment is never read.
+    assert_eq!(trap([65530, 0, 8]), []);
+}
+
+#[test]
+fn partition_keeps_the_segment_reads_of_a_trapping_memory_init() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory 1)
+            (data $passive "0123456789")
+            (func (export "init")
+                (memory.init $passive (i32.const 0) (i32.const 5) (i32.const 10))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_passive_segments(&module);
+    let tracer = Rc::new(RefCell::new(tracer));
+    let error = instance
+        .get_func(&store, "init")
+        .unwrap()
+        .call_with_trace(&mut store, &[], &mut [], tracer.clone())
+        .unwrap_err();
+    assert_eq!(error.as_trap_code(), Some(TrapCode::MemoryOutOfBounds));
+    let mtable = tracer.take().get_mtable();
+    let rows = mtable.entries().len();
+    let (stack, heap, global, segments) = mtable.partition();
+    assert!(!segments.entries().is_empty());
+    assert!(segments
+        .entries()
+        .iter()
+        .all(|entry| entry.ltype == LocationType::Data && entry.atype == AccessType::Read));
+    let partitioned = [&stack, &heap, &global, &segments]
+        .iter()
+        .map(|mtable| mtable.entries().len())
+        .sum::<usize>();
+    assert_eq!(partitioned, rows);
+}
+
+#[test]
+fn passive_segments_are_recorded_as_init_rows() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (memory 1)
+            (table 2 funcref)
+            (data (i32.const 0) "active")
+            (data $passive "the bytes of a passive segment")
+            (elem $elems func $f $g)
+            (func $f)
+            (func $g)
+            (func (export "init") (result i64)
+                (memory.init $passive (i32.const 64) (i32.const 4) (i32.const 8))
+                (i64.load (i32.const 64))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_passive_segments(&module);
+    let tracer = Rc::new(RefCell::new(tracer));
+    let mut results = [Val::I64(0)];
+    instance
+        .get_func(&store, "init")
+        .unwrap()
+        .call_with_trace(&mut store, &[], &mut results, tracer.clone())
+        .unwrap();
+    let tracer = tracer.take();
+    // The passive data segment is the second data segment.
+    assert_eq!(tracer.passive_data_address(0), None);
+    let base = tracer.passive_data_address(1).unwrap();
+    let data = tracer
+        .imtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Data)
+        .collect::<Vec<_>>();
+    assert!(data.iter().all(|entry| !entry.is_mutable));
+    let addresses = data
+        .iter()
+        .map(|entry| entry.start_offset)
+        .collect::<Vec<_>>();
+    assert_eq!(addresses, (base..base + 4).collect::<Vec<_>>());
+    let bytes = data
+        .iter()
+        .flat_map(|entry| entry.value.to_le_bytes())
+        .collect::<Vec<_>>();
+    assert_eq!(&bytes[..30], b"the bytes of a passive segment");
+    assert_eq!(&bytes[30..], [0x00; 2]);
+    // The `memory.init` copied the bytes `4..12` of the recorded segment.
+    let loaded = results[0].i64().unwrap();
+    assert_eq!(loaded.to_le_bytes(), bytes[4..12]);
+    let base = tracer.passive_element_address(0).unwrap();
+    let elements = tracer
+        .imtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Element)
+        .map(|entry| (entry.start_offset, entry.value))
+        .collect::<Vec<_>>();
+    assert_eq!(elements, [(base, 0), (base + 1, 1)]);
+}
+
+#[test]
+fn working_set_of_strided_accesses() {
+    let entry = |eid, ltype, addr| MemoryTableEntry {
+        eid,
+        emid: 1,
+        addr,
+        ltype,
+        atype: AccessType::Read,
+        is_mutable: true,
+        value: Val::I32(0),
+        frame: 0,
+    };
+    // Every step reads the loop counter on the stack and the next heap word.
+    let strided = MTable::new(
+        (1..=8)
+            .flat_map(|eid| {
+                [
+                    entry(eid, LocationType::Stack, 0),
+                    entry(eid, LocationType::Heap, eid as usize),
+                ]
+            })
+            .collect(),
+    );
+    assert_eq!(strided.working_set(4), [(1, 5), (5, 5)]);
+    assert_eq!(strided.working_set(8), [(1, 9)]);
+    assert_eq!(strided.working_set(3), [(1, 4), (4, 4), (7, 3)]);
+    // Every step reads one of two alternating heap words.
+    let alternating = MTable::new(
+        (1..=8)
+            .flat_map(|eid| {
+                [
+                    entry(eid, LocationType::Stack, 0),
+                    entry(eid, LocationType::Heap, eid as usize % 2),
+                ]
+            })
+            .collect(),
+    );
+    assert_eq!(alternating.working_set(4), [(1, 3), (5, 3)]);
+}
+
+#[test]
+fn imported_global_set_targets_defining_instance() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = <Linker<()>>::new(&engine);
+    let module = |wat: &str| Module::new(&engine, &mut &wat::parse_str(wat).unwrap()[..]).unwrap();
+    let defining = module(
+        r#"
+        (module
+            (global $unused (export "unused") i32 (i32.const 0))
+            (global $counter (export "counter") (mut i32) (i32.const 5))
+        )"#,
+    );
+    let importing = module(
+        r#"
+        (module
+            (import "defining" "counter" (global $counter (mut i32)))
+            (func (export "set") (param $value i32)
+                (global.set $counter (local.get $value))
+            )
+        )"#,
+    );
+    let defining = linker
+        .instantiate(&mut store, &defining)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let counter = defining.get_global(&store, "counter").unwrap();
+    linker.define("defining", "counter", counter).unwrap();
+    let importing = linker
+        .instantiate(&mut store, &importing)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_init_global(&counter, &store);
+    let tracer = Rc::new(RefCell::new(tracer));
+    importing
+        .get_func(&store, "set")
+        .unwrap()
+        .call_with_trace(&mut store, &[Val::I32(42)], &mut [], tracer.clone())
+        .unwrap();
+    assert_eq!(counter.get(&store).i32(), Some(42));
+    let tracer = tracer.take();
+    let init = tracer
+        .imtable
+        .entries()
+        .iter()
+        .find(|entry| entry.ltype == LocationType::Global)
+        .expect("missing init entry for the imported global");
+    assert!(init.is_mutable);
+    assert_eq!(init.value, 5);
+    let mtable = tracer.get_mtable();
+    let write = mtable
+        .entries()
+        .iter()
+        .find(|entry| entry.ltype == LocationType::Global)
+        .expect("missing write of the imported global");
+    assert_eq!(write.atype, AccessType::Write);
+    assert_eq!(write.addr, init.start_offset as usize);
+    assert_eq!(write.value.i32(), Some(42));
+    let set = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::GlobalSet {
+                global_index,
+                address,
+                ..
+            } => Some((*global_index, *address)),
+            _ => None,
+        })
+        .expect("missing global.set step");
+    // The imported global is the first global of the importing module
+    // but the second global of the defining module.
+    assert_eq!(set, (0, write.addr));
+    assert_ne!(set.0 as usize, set.1);
+}
+
+#[test]
+fn null_funcref_global_is_traced_as_null() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (global $callback (export "callback") (mut funcref) (ref.null func))
+            (func (export "callback_is_null") (result i32)
+                (ref.is_null (global.get $callback))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let callback = instance.get_global(&store, "callback").unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_init_global(&callback, &store);
+    let tracer = Rc::new(RefCell::new(tracer));
+    let mut results = [Val::I32(0)];
+    instance
+        .get_func(&store, "callback_is_null")
+        .unwrap()
+        .call_with_trace(&mut store, &[], &mut results, tracer.clone())
+        .unwrap();
+    assert_eq!(results[0].i32(), Some(1));
+    let tracer = tracer.take();
+    let is_null = |val: &Val| matches!(val, Val::FuncRef(funcref) if funcref.is_null());
+    let init = tracer
+        .imtable
+        .entries()
+        .iter()
+        .find(|entry| entry.ltype == LocationType::Global)
+        .expect("missing init entry for the funcref global");
+    assert!(is_null(&init.val()));
+    let mtable = tracer.get_mtable();
+    let read = mtable
+        .entries()
+        .iter()
+        .find(|entry| entry.ltype == LocationType::Global)
+        .expect("missing read of the funcref global");
+    assert_eq!(read.atype, AccessType::Read);
+    assert_eq!(read.addr, init.start_offset as usize);
+    assert!(is_null(&read.value));
+    let value = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::GlobalGet { value, .. } => Some(value.clone()),
+            _ => None,
+        })
+        .expect("missing global.get step");
+    assert!(is_null(&value.val));
+    assert!(format!("{}", value).starts_with("funcref#null"));
+    mtable.validate().unwrap();
+}
+
+#[test]
+fn global_get_reads_global_with_its_mutability() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = <Linker<()>>::new(&engine);
+    let module = |wat: &str| Module::new(&engine, &mut &wat::parse_str(wat).unwrap()[..]).unwrap();
+    let defining = module(
+        r#"
+        (module
+            (global (export "step") i32 (i32.const 3))
+        )"#,
+    );
+    // The translator replaces reads of immutable internal globals with their
+    // initial value so the immutable global has to be imported.
+    let importing = module(
+        r#"
+        (module
+            (import "defining" "step" (global $step i32))
+            (global $counter (mut i32) (i32.const 5))
+            (func (export "bump") (result i32)
+                (global.set $counter (i32.add (global.get $counter) (global.get $step)))
+                (global.get $counter)
+            )
+        )"#,
+    );
+    let defining = linker
+        .instantiate(&mut store, &defining)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let step = defining.get_global(&store, "step").unwrap();
+    linker.define("defining", "step", step).unwrap();
+    let importing = linker
+        .instantiate(&mut store, &importing)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let mut results = [Val::I32(0)];
+    importing
+        .get_func(&store, "bump")
+        .unwrap()
+        .call_with_trace(&mut store, &[], &mut results, tracer.clone())
+        .unwrap();
+    assert_eq!(results[0].i32(), Some(8));
+    let tracer = tracer.take();
+    let gets = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::GlobalGet {
+                global_index,
+                is_mutable,
+                value,
+                ..
+            } => Some((*global_index, *is_mutable, value.val.i32())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        gets,
+        [(1, true, Some(5)), (0, false, Some(3)), (1, true, Some(8))]
+    );
+    let mtable = tracer.get_mtable();
+    let accesses = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Global)
+        .map(|entry| (entry.atype, entry.is_mutable, entry.value.i32()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        [
+            (AccessType::Read, true, Some(5)),
+            (AccessType::Read, false, Some(3)),
+            (AccessType::Write, true, Some(8)),
+            (AccessType::Read, true, Some(8)),
+        ]
+    );
+    let read_addrs = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Global && entry.atype == AccessType::Read)
+        .map(|entry| entry.addr)
+        .collect::<Vec<_>>();
+    let get_addrs = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::GlobalGet { address, .. } => Some(*address),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(read_addrs, get_addrs);
+}
+
+#[test]
+fn canonical_order_places_reads_before_writes_of_the_same_step() {
+    let wat = r#"
+        (module
+            (func (export "inc") (param i32 i32) (result i32)
+                (local.set 0 (i32.add (local.get 0) (local.get 1)))
+                (local.get 0)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "inc", &[Val::I32(41), Val::I32(1)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(42));
+    let mtable = tracer.get_mtable();
+    // The `i32.add` reads and writes the register of the local in place.
+    let (eid, addr) = mtable
+        .group_by_eid()
+        .find_map(|(eid, entries)| {
+            entries
+                .iter()
+                .find(|read| {
+                    read.atype == AccessType::Read
+                        && entries.iter().any(|write| {
+                            write.atype == AccessType::Write && write.addr == read.addr
+                        })
+                })
+                .map(|read| (eid, read.addr))
+        })
+        .expect("missing step reading and writing the same address");
+    let mut reversed = mtable.entries().clone();
+    reversed.reverse();
+    let sorted = MTable::new(reversed).sorted_by_address();
+    for pair in sorted.entries().windows(2) {
+        assert!(MTable::canonical_order(&pair[0], &pair[1]).is_lt());
+    }
+    let accesses = sorted
+        .entries()
+        .iter()
+        .filter(|entry| entry.eid == eid && entry.addr == addr)
+        .map(|entry| (entry.atype, entry.value.i32()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        [(AccessType::Read, Some(41)), (AccessType::Write, Some(42))]
+    );
+    assert_eq!(sorted.entries().len(), mtable.entries().len());
+}
+
+#[test]
+fn sorted_by_address_groups_interleaved_locations() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "swap") (param i32 i32) (result i32)
+                (i32.store (i32.const 8) (local.get 0))
+                (i32.store (i32.const 16) (local.get 1))
+                (local.set 0 (i32.load (i32.const 16)))
+                (i32.store (i32.const 8) (i32.add (local.get 0) (local.get 1)))
+                (i32.load (i32.const 8))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "swap", &[Val::I32(3), Val::I32(4)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(8));
+    let mtable = tracer.get_mtable();
+    let sorted = mtable.sorted_by_address();
+    assert_eq!(sorted.entries().len(), mtable.entries().len());
+    // The original execution order stays available.
+    assert!(mtable
+        .entries()
+        .windows(2)
+        .all(|pair| (pair[0].eid, pair[0].emid) < (pair[1].eid, pair[1].emid)));
+    // Every location forms a single run of entries in execution order.
+    let mut seen = Vec::new();
+    for run in sorted
+        .entries()
+        .chunk_by(|a, b| (a.ltype, a.addr) == (b.ltype, b.addr))
+    {
+        let location = (run[0].ltype, run[0].addr);
+        assert!(!seen.contains(&location), "{location:?} is split");
+        seen.push(location);
+        assert!(run
+            .windows(2)
+            .all(|pair| (pair[0].eid, pair[0].emid) < (pair[1].eid, pair[1].emid)));
+        // Each read observes the latest write to its location.
+        let mut latest = None;
+        for entry in run {
+            match entry.atype {
+                AccessType::Read => {
+                    if let Some(latest) = latest {
+                        assert_eq!(UntypedVal::from(entry.value.clone()).to_bits(), latest);
+                    }
+                }
+                AccessType::Write | AccessType::Init => {
+                    latest = Some(UntypedVal::from(entry.value.clone()).to_bits());
+                }
+            }
+        }
+    }
+    let heap = seen
+        .iter()
+        .filter(|(ltype, _)| *ltype == LocationType::Heap)
+        .map(|(_, addr)| *addr)
+        .collect::<Vec<_>>();
+    assert_eq!(heap, [1, 2]);
+    assert!(seen.iter().any(|(ltype, _)| *ltype == LocationType::Stack));
+}
+#[test]
+fn mtable_bytes_round_trip_and_reject_other_versions() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $g (mut f64) (f64.const 0.5))
+            (func (export "f") (param $x i32) (result i32)
+                (i32.store (i32.const 8) (local.get $x))
+                (global.set $g (f64.const -1.25))
+                (i32.add (i32.load (i32.const 8)) (local.get $x))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(21)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(42));
+    let mtable = tracer.get_mtable();
+    let bytes = mtable.to_bytes();
+    assert_eq!(bytes[..4], MAGIC);
+    let decoded = MTable::from_bytes(&bytes).unwrap();
+    assert_eq!(
+        format!("{:?}", decoded.entries()),
+        format!("{:?}", mtable.entries())
+    );
+    // Encodings of another major or a newer minor version are rejected.
+    for found in [
+        FormatVersion {
+            major: TRACE_FORMAT_VERSION.major + 1,
+            minor: 0,
+        },
+        FormatVersion {
+            minor: TRACE_FORMAT_VERSION.minor + 1,
+            ..TRACE_FORMAT_VERSION
+        },
+    ] {
+        let mut bytes = bytes.clone();
+        bytes[4..6].copy_from_slice(&found.major.to_le_bytes());
+        bytes[6..8].copy_from_slice(&found.minor.to_le_bytes());
+        let error = MTable::from_bytes(&bytes).unwrap_err();
+        assert_eq!(error, FormatError::UnsupportedVersion { found });
+        assert_eq!(
+            format!("{error}"),
+            format!(
+                "unsupported trace format version {found} (supported: {}.0 to {})",
+                TRACE_FORMAT_VERSION.major, TRACE_FORMAT_VERSION
+            )
+        );
+    }
+    assert_eq!(
+        MTable::from_bytes(b"not a trace").unwrap_err(),
+        FormatError::BadMagic
+    );
+    assert_eq!(
+        MTable::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err(),
+        FormatError::UnexpectedEnd
+    );
+}
+
+#[test]
+fn partition_by_location_type() {
+    let entry = |eid, emid, ltype| MemoryTableEntry {
+        eid,
+        emid,
+        addr: 0,
+        ltype,
+        atype: AccessType::Read,
+        is_mutable: true,
+        value: Val::I32(0),
+        frame: 0,
+    };
+    let mtable = MTable::new(std::vec![
+        entry(1, 1, LocationType::Stack),
+        entry(1, 2, LocationType::Heap),
+        entry(1, 3, LocationType::Stack),
+        entry(2, 1, LocationType::Global),
+        entry(2, 2, LocationType::Heap),
+        entry(3, 1, LocationType::Stack),
+        entry(3, 2, LocationType::Data),
+        entry(4, 1, LocationType::Table),
+        entry(4, 2, LocationType::Element),
+    ]);
+    let ids = |mtable: &MTable| {
+        mtable
+            .entries()
+            .iter()
+            .map(|entry| (entry.eid, entry.emid))
+            .collect::<Vec<_>>()
+    };
+    let mut all = ids(&mtable);
+    let (stack, heap, global, segments) = mtable.partition();
+    for (partition, ltype) in [
+        (&stack, LocationType::Stack),
+        (&heap, LocationType::Heap),
+        (&global, LocationType::Global),
+    ] {
+        assert!(partition.entries().iter().all(|entry| entry.ltype == ltype));
+    }
+    assert_eq!(ids(&stack), [(1, 1), (1, 3), (3, 1)]);
+    assert_eq!(ids(&heap), [(1, 2), (2, 2)]);
+    assert_eq!(ids(&global), [(2, 1)]);
+    assert_eq!(ids(&segments), [(3, 2), (4, 1), (4, 2)]);
+    let mut union = [ids(&stack), ids(&heap), ids(&global), ids(&segments)].concat();
+    union.sort();
+    all.sort();
+    assert_eq!(union, all);
+}
+
+#[test]
+fn nth_access_counts_the_accesses_to_one_location() {
+    let entry = |eid, ltype, addr, atype, value| MemoryTableEntry {
+        eid,
+        emid: 1,
+        addr,
+        ltype,
+        atype,
+        is_mutable: true,
+        value: Val::I32(value),
+        frame: 0,
+    };
+    let mtable = MTable::new(std::vec![
+        entry(1, LocationType::Heap, 4, AccessType::Write, 1),
+        entry(2, LocationType::Heap, 4, AccessType::Read, 1),
+        entry(3, LocationType::Stack, 4, AccessType::Write, 7),
+        entry(4, LocationType::Heap, 5, AccessType::Write, 8),
+        entry(5, LocationType::Heap, 4, AccessType::Write, 2),
+        entry(6, LocationType::Heap, 4, AccessType::Read, 2),
+        entry(7, LocationType::Heap, 4, AccessType::Write, 3),
+    ]);
+    let third = mtable.nth_access(LocationType::Heap, 4, 2).unwrap();
+    assert_eq!((third.eid, third.atype), (5, AccessType::Write));
+    assert_eq!(third.value.i32(), Some(2));
+    assert_eq!(mtable.nth_access(LocationType::Heap, 4, 4).unwrap().eid, 7);
+    assert!(mtable.nth_access(LocationType::Heap, 4, 5).is_none());
+    assert_eq!(mtable.nth_access(LocationType::Stack, 4, 0).unwrap().eid, 3);
+    assert!(mtable.nth_access(LocationType::Global, 4, 0).is_none());
+}
+
+/// Returns the `(left, right, result)` bits of the only float binary step of `tracer`.
+fn traced_float_binop_bits(tracer: &Tracer) -> (u64, u64, u64) {
+    let bits = |ival: &IVal| UntypedVal::from(ival.val.clone()).to_bits();
+    let mut steps = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::F32BinOp {
+                left,
+                right,
+                result,
+                ..
+            }
+            | StepInfo::F64BinOp {
+                left,
+                right,
+                result,
+                ..
+            } => Some((bits(left), bits(right), bits(result))),
+            _ => None,
+        });
+    let step = steps.next().expect("missing float binary step");
+    assert!(steps.next().is_none());
+    step
+}
+
+#[test]
+fn float_min_max_follow_wasm_semantics() {
+    let wat = r#"
+        (module
+            (func (export "f32.min") (param f32 f32) (result f32)
+                (f32.min (local.get 0) (local.get 1))
+            )
+            (func (export "f32.max") (param f32 f32) (result f32)
+                (f32.max (local.get 0) (local.get 1))
+            )
+            (func (export "f64.min") (param f64 f64) (result f64)
+                (f64.min (local.get 0) (local.get 1))
+            )
+            (func (export "f64.max") (param f64 f64) (result f64)
+                (f64.max (local.get 0) (local.get 1))
+            )
+        )"#;
+    let f32s = |lhs: f32, rhs: f32| [Val::F32(lhs.into()), Val::F32(rhs.into())];
+    let f64s = |lhs: f64, rhs: f64| [Val::F64(lhs.into()), Val::F64(rhs.into())];
+    let cases: [(&str, [Val; 2], u64); 6] = [
+        ("f32.min", f32s(-0.0, 0.0), u64::from((-0.0_f32).to_bits())),
+        ("f32.max", f32s(-0.0, 0.0), u64::from(0.0_f32.to_bits())),
+        (
+            "f32.min",
+            f32s(f32::NAN, 1.0),
+            u64::from(f32::NAN.to_bits()),
+        ),
+        ("f64.min", f64s(-0.0, 0.0), (-0.0_f64).to_bits()),
+        ("f64.max", f64s(-0.0, 0.0), 0.0_f64.to_bits()),
+        ("f64.min", f64s(f64::NAN, 1.0), f64::NAN.to_bits()),
+    ];
+    for (func, params, expected) in cases {
+        let (tracer, result) = trace_wat(wat, func, &params);
+        let result = UntypedVal::from(result.unwrap()[0].clone()).to_bits();
+        let (left, right, traced) = traced_float_binop_bits(&tracer);
+        assert_eq!(left, UntypedVal::from(params[0].clone()).to_bits());
+        assert_eq!(right, UntypedVal::from(params[1].clone()).to_bits());
+        assert_eq!(traced, result, "{func}{params:?}");
+        assert_eq!(traced, expected, "{func}{params:?}");
+    }
+}
+
+#[test]
+fn float_division_by_zero_does_not_trap() {
+    let wat = r#"
+        (module
+            (func (export "div") (param f64 f64) (result f64)
+                (f64.div (local.get 0) (local.get 1))
+            )
+        )"#;
+    for lhs in [1.0, -1.0, 0.0] {
+        let params = [Val::F64(lhs.into()), Val::F64(0.0.into())];
+        let (tracer, result) = trace_wat(wat, "div", &params);
+        let result = result.unwrap()[0].f64().unwrap().to_float();
+        let (_, _, traced) = traced_float_binop_bits(&tracer);
+        assert_eq!(traced, result.to_bits());
+        let traced = f64::from_bits(traced);
+        match lhs {
+            0.0 => assert!(traced.is_nan()),
+            _ => assert_eq!(traced, lhs * f64::INFINITY),
+        }
+        assert!(!tracer
+            .etable
+            .entries()
+            .iter()
+            .any(|entry| matches!(entry.step_info, StepInfo::Trap { .. })));
+    }
+}
+
+#[test]
+fn float_nan_results_keep_the_executor_bits() {
+    let wat = r#"
+        (module
+            (func (export "add") (param f64) (result f64)
+                (f64.add (local.get 0) (f64.const 1.0))
+            )
+        )"#;
+    let nan = f64::from_bits(0x7ff8_0000_0000_1234);
+    let (tracer, result) = trace_wat(wat, "add", &[Val::F64(nan.into())]);
+    let result = result.unwrap()[0].f64().unwrap().to_float();
+    assert!(result.is_nan());
+    let (left, right, traced) = traced_float_binop_bits(&tracer);
+    assert_eq!(left, nan.to_bits());
+    assert_eq!(right, 1.0_f64.to_bits());
+    assert_eq!(traced, result.to_bits());
+    let mtable = tracer.get_mtable();
+    mtable.validate().unwrap();
+    assert!(mtable.entries().iter().any(|entry| {
+        entry.atype == AccessType::Write
+            && UntypedVal::from(entry.value.clone()).to_bits() == traced
+    }));
+}
+
+#[test]
+fn float_binops_record_typed_operands_and_results() {
+    let nan = f32::from_bits(0x7fc0_1234);
+    let f32_cases: [(&str, f32, f32, f32); 5] = [
+        ("add", 1.5, 2.25, 3.75),
+        ("sub", 1.5, 2.25, -0.75),
+        ("mul", 1.5, -2.0, -3.0),
+        ("copysign", 1.5, -0.0, -1.5),
+        // The sign is copied onto the NaN without touching its payload.
+        ("copysign", nan, -1.0, f32::from_bits(0xffc0_1234)),
+    ];
+    for (op, left, right, expected) in f32_cases {
+        let wat = format!(
+            r#"(module
+                (func (export "f") (param f32 f32) (result f32)
+                    (f32.{op} (local.get 0) (local.get 1))
+                )
+            )"#
+        );
+        let params = [Val::F32(left.into()), Val::F32(right.into())];
+        let (tracer, result) = trace_wat(&wat, "f", &params);
+        let result = result.unwrap()[0].f32().unwrap().to_float();
+        assert_eq!(result.to_bits(), expected.to_bits(), "f32.{op}");
+        let traced = traced_float_binop_bits(&tracer);
+        let bits = |value: f32| u64::from(value.to_bits());
+        assert_eq!(
+            traced,
+            (bits(left), bits(right), bits(expected)),
+            "f32.{op}"
+        );
+        let step = &tracer.etable.entries()[0];
+        assert_eq!(step.step_info.opcode(), format!("f32.{op}"));
+        let accesses = memory_events_of_steps(core::slice::from_ref(step))
+            .iter()
+            .map(|entry| entry.atype)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            accesses,
+            [AccessType::Read, AccessType::Read, AccessType::Write]
+        );
+    }
+    let f64_cases: [(&str, f64, f64, f64); 3] = [
+        ("sub", 0.5, 2.0, -1.5),
+        ("mul", 0.5, 3.0, 1.5),
+        ("copysign", 2.0, -3.0, -2.0),
+    ];
+    for (op, left, right, expected) in f64_cases {
+        let wat = format!(
+            r#"(module
+                (func (export "f") (param f64 f64) (result f64)
+                    (f64.{op} (local.get 0) (local.get 1))
+                )
+            )"#
+        );
+        let params = [Val::F64(left.into()), Val::F64(right.into())];
+        let (tracer, result) = trace_wat(&wat, "f", &params);
+        assert_eq!(result.unwrap()[0].f64().unwrap().to_float(), expected);
+        let traced = traced_float_binop_bits(&tracer);
+        let bits = f64::to_bits;
+        assert_eq!(
+            traced,
+            (bits(left), bits(right), bits(expected)),
+            "f64.{op}"
+        );
+    }
+    // A constant sign operand is encoded as an immediate `1.0` or `-1.0`.
+    let wat = r#"
+        (module
+            (func (export "f") (param f32) (result f32)
+                (f32.copysign (local.get 0) (f32.const -8.0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::F32(2.5_f32.into())]);
+    assert_eq!(result.unwrap()[0].f32().unwrap().to_float(), -2.5);
+    let StepInfo::F32BinOp {
+        class: BinOp::CopySign,
+        right,
+        ..
+    } = &tracer.etable.entries()[0].step_info
+    else {
+        panic!("expected an f32.copysign step")
+    };
+    assert_eq!(right.addr, None);
+    assert_eq!(right.val.f32().unwrap().to_float(), -1.0);
+}
+
+#[test]
+fn io_boundary_holds_root_params_and_results() {
+    let wat = r#"
+        (module
+            (func $double (param i64) (result i64)
+                (i64.add (local.get 0) (local.get 0))
+            )
+            (func (export "swap_double") (param i32 i64) (result i64 i32)
+                (call $double (local.get 1))
+                (local.get 0)
+            )
+        )"#;
+    let params = [Val::I32(7), Val::I64(21)];
+    let (tracer, result) = trace_wat(wat, "swap_double", &params);
+    let result = result.unwrap();
+    let (inputs, outputs) = tracer.io_boundary();
+    let bits = |vals: &[Val]| {
+        vals.iter()
+            .map(|val| UntypedVal::from(val.clone()).to_bits())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(bits(&inputs), bits(&params));
+    assert_eq!(bits(&outputs), bits(&result));
+    assert_eq!(outputs[0].i64(), Some(42));
+    assert_eq!(outputs[1].i32(), Some(7));
+
+    let wat = r#"
+        (module
+            (func (export "trap") (param i32) (result i32)
+                (unreachable)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "trap", &[Val::I32(1)]);
+    assert!(result.is_err());
+    let (inputs, outputs) = tracer.io_boundary();
+    assert_eq!(inputs[0].i32(), Some(1));
+    assert!(outputs.is_empty());
+}
+
+#[test]
+#[cfg(feature = "arrow")]
+fn etable_exports_arrow_columns() {
+    use arrow_array::{cast::AsArray, types::UInt64Type, Array, ListArray, UInt32Array};
+
+    let wat = r#"
+        (module
+            (func $double (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))
+            )
+            (func (export "f") (param i32) (result i32)
+                (call $double (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(21)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(42));
+    let batch = tracer.etable.to_arrow();
+    let entries = tracer.etable.entries();
+    assert_eq!(batch.num_rows(), entries.len());
+    let eids = batch["eid"].as_primitive::<UInt64Type>().values();
+    assert!(eids.iter().eq(entries.iter().map(|entry| &entry.eid)));
+    let opcodes = batch["opcode"].as_string::<i32>();
+    assert_eq!(
+        opcodes.iter().flatten().collect::<Vec<_>>(),
+        ["call", "i32.add", "return", "return"]
+    );
+    let depths = batch["depth"]
+        .as_any()
+        .downcast_ref::<UInt32Array>()
+        .unwrap();
+    assert_eq!(depths.values(), &[0, 1, 1, 0]);
+    // Every row nests the accesses of its step in emid order.
+    let mtable = tracer.get_mtable();
+    let values = batch["values"].as_list::<i32>();
+    let addresses = batch["addresses"].as_list::<i32>();
+    let atypes = batch["access_types"].as_list::<i32>();
+    let words = |list: &ListArray, row| {
+        list.value(row)
+            .as_primitive::<UInt64Type>()
+            .values()
+            .to_vec()
+    };
+    for (row, entry) in entries.iter().enumerate() {
+        let accesses = mtable
+            .entries()
+            .iter()
+            .filter(|access| access.eid == entry.eid)
+            .collect::<Vec<_>>();
+        let bits = accesses
+            .iter()
+            .map(|access| UntypedVal::from(access.value.clone()).to_bits())
+            .collect::<Vec<_>>();
+        let addrs = accesses
+            .iter()
+            .map(|access| access.addr as u64)
+            .collect::<Vec<_>>();
+        assert_eq!(words(values, row), bits);
+        assert_eq!(words(addresses, row), addrs);
+        assert_eq!(atypes.value(row).len(), accesses.len());
+    }
+    // The addition reads 21 twice and writes 42.
+    assert_eq!(words(values, 1), [21, 21, 42]);
+    let add_atypes = atypes.value(1);
+    let add_atypes = add_atypes.as_string::<i32>();
+    assert_eq!(
+        add_atypes.iter().flatten().collect::<Vec<_>>(),
+        ["Read", "Read", "Write"]
+    );
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn etable_serializes_to_json() {
+    let wat = r#"
+        (module
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "add", &[Val::I32(3), Val::I32(-4)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(-1));
+    let json = tracer.etable.to_json();
+    let json = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+    let entries = json["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), tracer.etable.entries().len());
+    assert_eq!(entries[0]["eid"], 1);
+    let add = &entries[0]["step_info"]["I32BinOp"];
+    assert_eq!(add["class"], "Add");
+    assert_eq!(add["left"]["val"]["ty"], "i32");
+    assert_eq!(add["left"]["val"]["bits"], 3);
+    assert_eq!(add["right"]["val"]["bits"], u64::from(-4_i32 as u32));
+    assert_eq!(add["result"]["val"]["bits"], u64::from(u32::MAX));
+    assert!(add["result"]["addr"].is_u64());
+    assert_eq!(entries[1]["eid"], 2);
+    assert!(entries[1]["step_info"]["Return"].is_object());
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn memory_tables_round_trip_through_json() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $g (mut i64) (i64.const -2))
+            (data (i32.const 8) "\01\02")
+            (func (export "run") (param i32) (result f32)
+                (i32.store (local.get 0) (i32.const 7))
+                (global.set $g (i64.load (local.get 0)))
+                (f32.const nan:0x12345)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "run", &[Val::I32(16)]);
+    result.unwrap();
+    let key = |entry: &MemoryTableEntry| {
+        (
+            entry.eid,
+            entry.emid,
+            entry.addr,
+            entry.ltype,
+            entry.atype,
+            entry.is_mutable,
+            entry.value.ty(),
+            UntypedVal::from(entry.value.clone()).to_bits(),
+            entry.frame,
+        )
+    };
+    let mtable = tracer.get_mtable();
+    assert!(mtable
+        .entries()
+        .iter()
+        .any(|entry| entry.ltype == LocationType::Heap));
+    let decoded = serde_json::from_str::<MTable>(&mtable.to_json()).unwrap();
+    assert_eq!(
+        decoded.entries().iter().map(key).collect::<Vec<_>>(),
+        mtable.entries().iter().map(key).collect::<Vec<_>>(),
+    );
+
+    let key = |entry: &imtable::IMTableEntry| {
+        (
+            entry.ltype,
+            entry.is_mutable,
+            entry.start_offset,
+            entry.end_offset,
+            ValType::from(&entry.vtype),
+            entry.value,
+        )
+    };
+    let mut imtable = IMTable::default();
+    imtable.push(true, true, 0, 0, ValueType::I64, -2_i64 as u64);
+    imtable.push(false, true, 1, 1, ValueType::I64, 0x0201);
+    imtable.push_location(LocationType::Data, false, 0, 0, ValueType::I64, 0x0201);
+    let decoded = serde_json::from_str::<IMTable>(&imtable.to_json()).unwrap();
+    assert_eq!(
+        decoded.entries().iter().map(key).collect::<Vec<_>>(),
+        imtable.entries().iter().map(key).collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn group_by_eid_covers_all_entries() {
+    let wat = r#"
+        (module
+            (func $double (param $x i32) (result i32)
+                (i32.add (local.get $x) (local.get $x))
+            )
+            (func (export "quadruple") (param $x i32) (result i32)
+                (call $double (call $double (local.get $x)))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "quadruple", &[Val::I32(3)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(12));
+    let mtable = tracer.get_mtable();
+    let groups = mtable.group_by_eid().collect::<Vec<_>>();
+    assert!(groups.len() > 1);
+    assert!(groups.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    for (eid, entries) in &groups {
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|entry| entry.eid == *eid));
+    }
+    let covered = groups
+        .iter()
+        .map(|(_, entries)| entries.len())
+        .sum::<usize>();
+    assert_eq!(covered, mtable.entries().len());
+}
+
+#[test]
+fn host_memory_writes_are_traced() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "poke" (func $poke (param i32 i32)))
+            (memory (export "mem") 1)
+            (func (export "run")
+                (call $poke (i32.const 16) (i32.const 0x11223344))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let mut linker = <Linker<()>>::new(&engine);
+    linker
+        .func_wrap(
+            "env",
+            "poke",
+            |mut caller: Caller<'_, ()>, address: u32, value: u32| {
+                let memory = caller
+                    .get_export("mem")
+                    .and_then(Extern::into_memory)
+                    .unwrap();
+                memory
+                    .write(&mut caller, address as usize, &value.to_le_bytes())
+                    .unwrap();
+            },
+        )
+        .unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    instance
+        .get_func(&store, "run")
+        .unwrap()
+        .call_with_trace(&mut store, &[], &mut [], tracer.clone())
+        .unwrap();
+    let tracer = tracer.take();
+    let mutations = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::HostMutation { words, globals } => Some((entry.eid, words, globals)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(mutations.len(), 1);
+    let (eid, words, globals) = mutations[0];
+    assert_eq!(
+        words,
+        &[MemoryWord {
+            index: 2,
+            value: 0x11223344
+        }]
+    );
+    assert!(globals.is_empty());
+    let mtable = tracer.get_mtable();
+    let writes = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.eid == eid)
+        .collect::<Vec<_>>();
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].ltype, LocationType::Heap);
+    assert_eq!(writes[0].atype, AccessType::Write);
+    assert_eq!(writes[0].addr, 2);
+    assert_eq!(writes[0].value.i64(), Some(0x11223344));
+}
+
+#[test]
+fn breakpoints_fire_at_their_eid() {
+    let wat = r#"
+        (module
+            (func (export "add3") (param i32 i32) (result i32)
+                (i32.add
+                    (i32.add (local.get 0) (local.get 1))
+                    (local.get 1)
+                )
+            )
+        )"#;
+    let hits = Arc::new(Mutex::new(Vec::new()));
+    let mut tracer = Tracer::new();
+    for tag in ["first", "second"] {
+        let hits = hits.clone();
+        tracer.set_breakpoint(2, move |entry| {
+            hits.lock()
+                .unwrap()
+                .push((tag, entry.eid, entry.step_info.clone()));
+        });
+    }
+    let (tracer, result) = trace_wat_with(tracer, wat, "add3", &[Val::I32(1), Val::I32(2)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(5));
+    assert!(tracer.etable.entries().len() > 2);
+    let hits = mem::take(&mut *hits.lock().unwrap());
+    assert_eq!(hits.len(), 2);
+    assert_eq!(hits[0].0, "first");
+    assert_eq!(hits[1].0, "second");
+    for (_, eid, step) in hits {
+        assert_eq!(eid, 2);
+        match step {
+            StepInfo::I32BinOp {
+                left,
+                right,
+                result,
+                ..
+            } => {
+                assert_eq!(left.val.i32(), Some(3));
+                assert_eq!(right.val.i32(), Some(2));
+                assert_eq!(result.val.i32(), Some(5));
+            }
+            step => panic!("expected an i32 binop step but found: {step:?}"),
+        }
+    }
+}
+
+#[test]
+fn shift_amounts_wrap_at_the_operand_bit_width() {
+    let wat = r#"
+        (module
+            (func (export "shl64") (param i64 i64) (result i64)
+                (i64.shl (local.get 0) (local.get 1))
+            )
+            (func (export "shl32") (param i32 i32) (result i32)
+                (i32.shl (local.get 0) (local.get 1))
+            )
+        )"#;
+    let traced_shift = |func: &str, params: &[Val]| {
+        let (tracer, result) = trace_wat(wat, func, params);
+        result.unwrap();
+        tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                StepInfo::I32BinOp {
+                    class: BinOp::Shl,
+                    right,
+                    result,
+                    ..
+                }
+                | StepInfo::I64BinOp {
+                    class: BinOp::Shl,
+                    right,
+                    result,
+                    ..
+                } => Some((
+                    entry.step_info.opcode(),
+                    right.val.clone(),
+                    result.val.clone(),
+                )),
+                _ => None,
+            })
+            .expect("missing shift step")
+    };
+    let (opcode, amount, result) = traced_shift("shl64", &[Val::I64(1), Val::I64(64)]);
+    assert_eq!(opcode, "i64.shl");
+    assert_eq!(amount.i64(), Some(64));
+    assert_eq!(result.i64(), Some(1));
+    let (_, amount, result) = traced_shift("shl64", &[Val::I64(1), Val::I64(65)]);
+    assert_eq!(amount.i64(), Some(65));
+    assert_eq!(result.i64(), Some(2));
+    let (_, _, result) = traced_shift("shl64", &[Val::I64(1), Val::I64(32)]);
+    assert_eq!(result.i64(), Some(1 << 32));
+    let (opcode, _, result) = traced_shift("shl32", &[Val::I32(1), Val::I32(32)]);
+    assert_eq!(opcode, "i32.shl");
+    assert_eq!(result.i32(), Some(1));
+    let (_, _, result) = traced_shift("shl32", &[Val::I32(1), Val::I32(33)]);
+    assert_eq!(result.i32(), Some(2));
+}
+
+#[test]
+fn rotations_by_zero_and_width_are_identities() {
+    let wat = r#"
+        (module
+            (func (export "i32.rotl") (param i32 i32) (result i32)
+                (i32.rotl (local.get 0) (local.get 1))
+            )
+            (func (export "i32.rotr") (param i32 i32) (result i32)
+                (i32.rotr (local.get 0) (local.get 1))
+            )
+            (func (export "i64.rotl") (param i64 i64) (result i64)
+                (i64.rotl (local.get 0) (local.get 1))
+            )
+            (func (export "i64.rotr") (param i64 i64) (result i64)
+                (i64.rotr (local.get 0) (local.get 1))
+            )
+        )"#;
+    let i32s = |amount| [Val::I32(0x1234_5678), Val::I32(amount)];
+    let i64s = |amount| [Val::I64(0x1234_5678_9abc_def0), Val::I64(amount)];
+    let cases = [
+        ("i32.rotl", i32s(0)),
+        ("i32.rotl", i32s(32)),
+        ("i32.rotr", i32s(0)),
+        ("i32.rotr", i32s(32)),
+        ("i64.rotl", i64s(0)),
+        ("i64.rotl", i64s(64)),
+        ("i64.rotr", i64s(0)),
+        ("i64.rotr", i64s(64)),
+    ];
+    for (func, params) in cases {
+        let (tracer, result) = trace_wat(wat, func, &params);
+        result.unwrap();
+        let (opcode, left, result) = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                StepInfo::I32BinOp {
+                    class: BinOp::Rotl | BinOp::Rotr,
+                    left,
+                    result,
+                    ..
+                }
+                | StepInfo::I64BinOp {
+                    class: BinOp::Rotl | BinOp::Rotr,
+                    left,
+                    result,
+                    ..
+                } => Some((
+                    entry.step_info.opcode(),
+                    left.val.clone(),
+                    result.val.clone(),
+                )),
+                _ => None,
+            })
+            .expect("missing rotate step");
+        assert_eq!(opcode, func);
+        let bits = |val: Val| UntypedVal::from(val).to_bits();
+        assert_eq!(bits(left), bits(params[0].clone()), "{func}{params:?}");
+        assert_eq!(bits(result), bits(params[0].clone()), "{func}{params:?}");
+    }
+}
+
+#[test]
+fn folded_stacks_of_nested_calls() {
+    let wat = r#"
+        (module
+            (func $leaf (param $x i32) (result i32)
+                (i32.add (local.get $x) (local.get $x))
+            )
+            (func $middle (param $x i32) (result i32)
+                (i32.add (call $leaf (local.get $x)) (local.get $x))
+            )
+            (func (export "run") (param $x i32) (result i32)
+                (call $middle (local.get $x))
+            )
+        )"#;
+    let wasm = wat::parse_str(wat).unwrap();
+    let module = Module::new(&Engine::default(), &mut &wasm[..]).unwrap();
+    let tracer = Tracer::new().with_func_names(&module);
+    let (tracer, result) = trace_wat_with(tracer, wat, "run", &[Val::I32(2)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(6));
+    let mut folded = String::new();
+    tracer.etable.to_folded_stacks(&mut folded).unwrap();
+    // Call steps count towards the caller and return steps towards the callee.
+    assert_eq!(folded, "root 2\nroot;middle 3\nroot;middle;leaf 2\n");
+}
+
+#[test]
+fn table_elements_are_recorded_into_the_imtable() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (table $funcs (export "funcs") 5 funcref)
+            (table $externs (export "externs") 2 externref)
+            (func $f)
+            (func $g)
+            (elem (table $funcs) (i32.const 1) func $f $g)
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let funcs = instance.get_table(&store, "funcs").unwrap();
+    let externs = instance.get_table(&store, "externs").unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_init_table(0, &funcs, &store);
+    tracer.push_init_table(1, &externs, &store);
+    tracer.push_init_table(0, &funcs, &store);
+    assert_eq!(tracer.table_address(0), Some(0));
+    assert_eq!(tracer.table_address(1), Some(5));
+    assert_eq!(tracer.table_address(2), None);
+    let bits = |index| UntypedVal::from(funcs.get(&store, index).unwrap()).to_bits();
+    assert_ne!(bits(1), bits(2));
+    let entries = tracer
+        .imtable
+        .entries()
+        .iter()
+        .map(|entry| {
+            assert_eq!(entry.ltype, LocationType::Table);
+            assert!(entry.is_mutable);
+            (
+                entry.start_offset,
+                entry.end_offset,
+                ValType::from(&entry.vtype),
+                entry.value,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [
+            (0, 0, ValType::FuncRef, 0),
+            (1, 1, ValType::FuncRef, bits(1)),
+            (2, 2, ValType::FuncRef, bits(2)),
+            (3, 4, ValType::FuncRef, 0),
+            (5, 6, ValType::ExternRef, 0),
+        ]
+    );
+    let null = tracer.imtable.entries()[0].val();
+    assert!(null.funcref().unwrap().is_null());
+}
+
+#[test]
+fn init_globals_cover_defined_and_imported_globals() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "base" (global $base (mut i32)))
+            (global $counter (mut i64) (i64.const 7))
+            (global $limit f32 (f32.const 1.5))
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let base = Global::new(&mut store, Val::I32(3), Mutability::Var);
+    let mut linker = <Linker<()>>::new(&engine);
+    linker.define("env", "base", base).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_init_globals(&instance, &store);
+    tracer.push_init_globals(&instance, &store);
+    let globals = tracer
+        .imtable
+        .entries()
+        .iter()
+        .map(|entry| {
+            assert_eq!(entry.ltype, LocationType::Global);
+            assert_eq!(entry.start_offset, entry.end_offset);
+            let bits = UntypedVal::from(entry.val()).to_bits();
+            (entry.is_mutable, ValType::from(&entry.vtype), bits)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        globals,
+        [
+            (true, ValType::I32, 3),
+            (true, ValType::I64, 7),
+            (false, ValType::F32, u64::from(1.5_f32.to_bits())),
+        ]
+    );
+    let address = store.as_context().store.inner.global_address(&base);
+    assert_eq!(tracer.imtable.entries()[0].start_offset as usize, address);
+}
+
+#[test]
+fn element_segment_offset_from_global() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "base" (global $base i32))
+            (table (export "table") 8 funcref)
+            (func $f (export "f"))
+            (func $g (export "g"))
+            (elem (global.get $base) $g $f)
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let base = Global::new(&mut store, Val::I32(3), Mutability::Const);
+    let mut linker = <Linker<()>>::new(&engine);
+    linker.define("env", "base", base).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let mut tracer = Tracer::new();
+    tracer.push_elem_offsets(&module, &instance, &store);
+    let table = instance.get_table(&store, "table").unwrap();
+    tracer.push_init_table(0, &table, &store);
+    let steps = tracer.itable.steps();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].table_index, 0);
+    assert_eq!(steps[0].offset, 3);
+    assert_eq!(steps[0].globals.len(), 1);
+    assert_eq!(
+        steps[0].globals[0].0,
+        store.as_context().store.inner.global_address(&base)
+    );
+    assert_eq!(steps[0].globals[0].1.i32(), Some(3));
+    let f = instance.get_func(&store, "f").unwrap();
+    let g = instance.get_func(&store, "g").unwrap();
+    let bits = |func| UntypedVal::from(FuncRef::new(func)).to_bits();
+    let elements = tracer
+        .imtable
+        .entries()
+        .iter()
+        .map(|entry| (entry.start_offset, entry.end_offset, entry.value))
+        .collect::<Vec<_>>();
+    // The segment seeds `$g` and `$f` at the offset read from the global.
+    assert_eq!(
+        elements,
+        [(0, 2, 0), (3, 3, bits(g)), (4, 4, bits(f)), (5, 7, 0)]
+    );
+}
+
+#[test]
+fn trap_records_its_location() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func $unused)
+            (func (export "f") (param $x i32) (param $y i32) (result i32)
+                (i32.add
+                    (i32.add (local.get $x) (local.get $y))
+                    (i32.div_s (local.get $x) (local.get $y))
+                )
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let func = instance.get_func(&store, "f").unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let error = func
+        .call_with_trace(
+            &mut store,
+            &[Val::I32(1), Val::I32(0)],
+            &mut [Val::I32(0)],
+            tracer.clone(),
+        )
+        .unwrap_err();
+    assert_eq!(error.as_trap_code(), Some(TrapCode::IntegerDivisionByZero));
+    let FuncEntity::Wasm(wasm_func) = store.inner.resolve_func(&func) else {
+        panic!("expected a Wasm function");
+    };
+    let div_pc = (0..)
+        .map_while(|index| engine.resolve_instr(wasm_func.func_body(), index).unwrap())
+        .position(|instr| matches!(instr, Instruction::I32DivS(_)))
+        .expect("missing i32.div_s instruction");
+    assert!(div_pc > 0);
+    let tracer = tracer.take();
+    match &tracer.etable.entries().last().unwrap().step_info {
+        StepInfo::Trap {
+            code,
+            func_index,
+            pc,
+            ..
+        } => {
+            assert_eq!(*code, TrapCode::IntegerDivisionByZero);
+            assert_eq!(*func_index, 1);
+            assert_eq!(*pc as usize, div_pc);
+        }
+        step => panic!("expected a trap step but found: {step:?}"),
+    }
+}
+
+#[test]
+fn signed_division_overflow_traps_after_reading_operands() {
+    for (wat, params) in [
+        (
+            r#"
+            (module
+                (func (export "div") (param i32 i32) (result i32)
+                    (i32.div_s (local.get 0) (local.get 1))
+                )
+            )"#,
+            [Val::I32(i32::MIN), Val::I32(-1)],
+        ),
+        (
+            r#"
+            (module
+                (func (export "div") (param i64 i64) (result i64)
+                    (i64.div_s (local.get 0) (local.get 1))
+                )
+            )"#,
+            [Val::I64(i64::MIN), Val::I64(-1)],
+        ),
+    ] {
+        let (tracer, result) = trace_wat(wat, "div", &params);
+        let error = result.unwrap_err();
+        assert_eq!(error.as_trap_code(), Some(TrapCode::IntegerOverflow));
+        match &tracer.etable.entries().last().unwrap().step_info {
+            StepInfo::Trap { code, reads, .. } => {
+                assert_eq!(*code, TrapCode::IntegerOverflow);
+                let reads = reads
+                    .iter()
+                    .map(|read| UntypedVal::from(read.val.clone()))
+                    .collect::<Vec<_>>();
+                assert_eq!(reads, params.map(UntypedVal::from));
+            }
+            step => panic!("expected a trap step but found: {step:?}"),
+        }
+    }
+}
+
+#[test]
+fn select_normalizes_its_condition() {
+    let wat = r#"
+        (module
+            (func (export "select") (param i32 i32 i32) (result i32)
+                (select (local.get 0) (local.get 1) (local.get 2))
+            )
+        )"#;
+    for (cond, expected_is_true, expected) in [(42, true, 10), (0, false, 20)] {
+        let (tracer, result) =
+            trace_wat(wat, "select", &[Val::I32(10), Val::I32(20), Val::I32(cond)]);
+        assert_eq!(result.unwrap()[0].i32(), Some(expected));
+        let step = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                StepInfo::Select {
+                    cond,
+                    cond_is_true,
+                    result,
+                    ..
+                } => Some((cond.clone(), *cond_is_true, result.clone())),
+                _ => None,
+            })
+            .expect("missing select step");
+        assert_eq!(step.0.val.i32(), Some(cond));
+        assert!(step.0.addr.is_some());
+        assert_eq!(step.1, expected_is_true);
+        assert_eq!(step.2.val.i64(), Some(i64::from(expected)));
+    }
+}
+
+#[test]
+fn selects_of_immediates_record_their_register_condition() {
+    for (ty, lhs, rhs) in [
+        ("i32", "(i32.const 10)", "(i32.const 20)"),
+        ("i64", "(i64.const 10)", "(i64.const 20)"),
+        ("f64", "(f64.const 10)", "(f64.const 20)"),
+    ] {
+        let wat = format!(
+            r#"
+            (module
+                (func (export "select") (param i32) (result {ty})
+                    (select {lhs} {rhs} (local.get 0))
+                )
+            )"#
+        );
+        for (cond, expected_is_true) in [(7, true), (0, false)] {
+            let (tracer, result) = trace_wat(&wat, "select", &[Val::I32(cond)]);
+            let expected = if expected_is_true { 10.0 } else { 20.0 };
+            let result = match result.unwrap()[0] {
+                Val::I32(value) => f64::from(value),
+                Val::I64(value) => value as f64,
+                Val::F64(value) => f64::from(value),
+                ref value => panic!("unexpected select result: {value:?}"),
+            };
+            assert_eq!(result, expected);
+            let (cond_read, cond_is_true) = tracer
+                .etable
+                .entries()
+                .iter()
+                .find_map(|entry| match &entry.step_info {
+                    StepInfo::Select {
+                        cond, cond_is_true, ..
+                    } => Some((cond.clone(), *cond_is_true)),
+                    _ => None,
+                })
+                .expect("missing select step");
+            assert_eq!(cond_read.val.i32(), Some(cond));
+            assert!(cond_read.addr.is_some());
+            assert_eq!(cond_is_true, expected_is_true);
+        }
+    }
+}
+
+#[test]
+fn select_of_externrefs_records_the_discarded_reference() {
+    let wat = r#"
+        (module
+            (func (export "select") (param externref externref i32) (result externref)
+                (select (result externref) (local.get 0) (local.get 1) (local.get 2))
+            )
+        )"#;
+    let wasm = wat::parse_str(wat).unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let func = instance.get_func(&store, "select").unwrap();
+    let first = ExternRef::new::<&str>(&mut store, "first");
+    let second = ExternRef::new::<&str>(&mut store, "second");
+    let id = |object| IVal::imm(Val::ExternRef(object)).externref_id();
+    for (cond, chosen, discarded) in [(1, first, second), (0, second, first)] {
+        let mut results = [Val::ExternRef(ExternRef::null())];
+        let tracer = Rc::new(RefCell::new(Tracer::new()));
+        let params = [
+            Val::ExternRef(first),
+            Val::ExternRef(second),
+            Val::I32(cond),
+        ];
+        func.call_with_trace(&mut store, &params, &mut results, tracer.clone())
+            .unwrap();
+        let tracer = tracer.take();
+        let step = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                StepInfo::Select {
+                    lhs,
+                    rhs,
+                    result,
+                    discarded,
+                    ..
+                } => Some((lhs.clone(), rhs.clone(), result.clone(), discarded.clone())),
+                _ => None,
+            })
+            .expect("missing select step");
+        let (lhs, rhs, result, recorded) = step;
+        assert_eq!(result.externref_id(), id(chosen));
+        assert_eq!(recorded.externref_id(), id(discarded));
+        assert_ne!(recorded.externref_id(), result.externref_id());
+        // The discarded reference is the unselected operand read by the step.
+        let operand = if cond != 0 { rhs } else { lhs };
+        assert_eq!(recorded.addr, operand.addr);
+    }
+}
+
+#[test]
+fn typed_select_reads_three_values_and_writes_one() {
+    let wat = r#"
+        (module
+            (func (export "select") (param i64 i64 i32) (result i64)
+                (select (result i64) (local.get 0) (local.get 1) (local.get 2))
+            )
+        )"#;
+    for (cond, expected) in [(1, -10), (0, 20)] {
+        let params = [Val::I64(-10), Val::I64(20), Val::I32(cond)];
+        let (tracer, result) = trace_wat(wat, "select", &params);
+        assert_eq!(result.unwrap()[0].i64(), Some(expected));
+        let (eid, selected) = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                StepInfo::Select { result, .. } => Some((entry.eid, result.val.i64())),
+                _ => None,
+            })
+            .expect("missing select step");
+        assert_eq!(selected, Some(expected));
+        let mtable = tracer.get_mtable();
+        let accesses = mtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.eid == eid)
+            .map(|entry| (entry.ltype, entry.atype))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            accesses,
+            [
+                (LocationType::Stack, AccessType::Read),
+                (LocationType::Stack, AccessType::Read),
+                (LocationType::Stack, AccessType::Read),
+                (LocationType::Stack, AccessType::Write),
+            ]
+        );
+    }
+}
+
+#[test]
+fn tracer_rebuilt_from_tables() {
+    let wat = r#"
+        (module
+            (func (export "f") (param i32) (result i32)
+                (i32.add (i32.add (local.get 0) (local.get 0)) (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(2)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(6));
+    let len_mentries = tracer.get_mtable().entries().len();
+    let (etable, mtable, imtable) = tracer.into_tables();
+    let len_eids = etable.entries().len();
+    let mut loaded = Tracer::from_tables(etable, mtable, imtable);
+    loaded.etable.validate_eids().unwrap();
+    let mtable = loaded.get_mtable();
+    assert_eq!(mtable.entries().len(), len_mentries);
+    mtable.validate().unwrap();
+    loaded.etable.push(
+        0,
+        StepInfo::Trap {
+            code: TrapCode::UnreachableCodeReached,
+            reads: Vec::new(),
+            data: Vec::new(),
+            func_index: 0,
+            pc: 0,
+        },
+    );
+    assert_eq!(
+        loaded.etable.entries().last().unwrap().eid,
+        len_eids as u64 + 1
+    );
+}
+
+#[test]
+fn compact_steps_round_trip() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (func $id (param i32) (result i32) (local.get 0))
+            (func (export "f") (param i32) (result i32)
+                (drop (memory.grow (local.get 0)))
+                (call $id (i32.add (local.get 0) (local.get 0)))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(1)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(2));
+    let compact = tracer.etable.compact_steps().collect::<Vec<_>>();
+    assert_eq!(compact.len(), tracer.etable.entries().len());
+    let find = |matches: fn(&CompactStep) -> bool| {
+        compact
+            .iter()
+            .find(|step| matches(step))
+            .expect("missing compact step")
+    };
+    let unary = find(|step| matches!(step, CompactStep::Unary { .. }));
+    assert_eq!(unary.operands().unwrap().len(), 2);
+    let binary = find(|step| matches!(step, CompactStep::Binary { .. }));
+    assert_eq!(binary.operands().unwrap().len(), 3);
+    let call = find(|step| match step {
+        CompactStep::Variadic(step) => matches!(**step, StepInfo::Call { .. }),
+        _ => false,
+    });
+    assert!(call.operands().is_none());
+    for (entry, step) in tracer.etable.entries().iter().zip(compact) {
+        let decoded = StepInfo::from(step);
+        assert_eq!(format!("{decoded:?}"), format!("{:?}", entry.step_info));
+    }
+    assert!(core::mem::size_of::<CompactStep>() < core::mem::size_of::<StepInfo>());
+}
+
+#[test]
+fn imported_wasm_call_crosses_instance() {
+    let engine = Engine::default();
+    let mut store = Store::new(&engine, ());
+    let mut linker = <Linker<()>>::new(&engine);
+    let module = |wat: &str| Module::new(&engine, &mut &wat::parse_str(wat).unwrap()[..]).unwrap();
+    let defining = module(
+        r#"
+        (module
+            (func (export "double") (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))
+            )
+        )"#,
+    );
+    let importing = module(
+        r#"
+        (module
+            (import "defining" "double" (func $double (param i32) (result i32)))
+            (func $local (param i32) (result i32)
+                (call $double (local.get 0))
+            )
+            (func (export "run") (param i32) (result i32)
+                (call $local (local.get 0))
+            )
+        )"#,
+    );
+    let defining = linker
+        .instantiate(&mut store, &defining)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let double = defining.get_func(&store, "double").unwrap();
+    linker.define("defining", "double", double).unwrap();
+    let importing = linker
+        .instantiate(&mut store, &importing)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let mut results = [Val::I32(0)];
+    importing
+        .get_func(&store, "run")
+        .unwrap()
+        .call_with_trace(&mut store, &[Val::I32(4)], &mut results, tracer.clone())
+        .unwrap();
+    assert_eq!(results[0].i32(), Some(8));
+    let calls = tracer
+        .take()
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match entry.step_info {
+            StepInfo::Call {
+                func_index,
+                host,
+                crosses_instance,
+                ..
+            } => Some((func_index, host, crosses_instance)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(calls, [(1, false, false), (0, false, true)]);
+}
+
+#[test]
+fn estimated_mtable_rows_match_mtable() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $g (mut i64) (i64.const 0))
+            (func $load (param i32) (result i64) (i64.load offset=4 (local.get 0)))
+            (func (export "f") (param i32) (result i64)
+                (global.set $g (call $load (local.get 0)))
+                (block $b
+                    (br_table $b $b (local.get 0))
+                )
+                (select (global.get $g) (i64.const 1) (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(3)]);
+    assert_eq!(result.unwrap()[0].i64(), Some(0));
+    let estimate = tracer.etable.estimate_mtable_rows();
+    assert!(estimate > 0);
+    assert_eq!(estimate, tracer.get_mtable().entries().len());
+}
+
+#[test]
+fn externref_ids_identify_host_objects() {
+    let wat = r#"
+        (module
+            (global $g (mut externref) (ref.null extern))
+            (func (export "roundtrip") (param externref) (result externref)
+                (global.set $g (local.get 0))
+                (global.get $g)
+            )
+        )"#;
+    let wasm = wat::parse_str(wat).unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let func = instance.get_func(&store, "roundtrip").unwrap();
+    let object = ExternRef::new::<&str>(&mut store, "host object");
+    let other = ExternRef::new::<&str>(&mut store, "other host object");
+    let mut results = [Val::ExternRef(ExternRef::null())];
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    func.call_with_trace(
+        &mut store,
+        &[Val::ExternRef(object)],
+        &mut results,
+        tracer.clone(),
+    )
+    .unwrap();
+    let tracer = tracer.take();
+    let id = IVal::imm(Val::ExternRef(object)).externref_id().unwrap();
+    assert_ne!(IVal::imm(Val::ExternRef(other)).externref_id(), Some(id));
+    let null = IVal::imm(Val::ExternRef(ExternRef::null()));
+    assert_eq!(null.externref_id(), None);
+    let set = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::GlobalSet { value, .. } => Some(value.clone()),
+            _ => None,
+        })
+        .expect("missing global.set step");
+    assert_eq!(set.externref_id(), Some(id));
+    assert!(format!("{set}").starts_with(&format!("externref#{id} ")));
+    let returned = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::Return { values, .. } => values.first().cloned(),
+            _ => None,
+        })
+        .expect("missing return step");
+    assert_eq!(returned.externref_id(), Some(id));
+    tracer.get_mtable().validate().unwrap();
+}
+
+#[test]
+fn block_results_are_copied_at_block_end() {
+    let wat = r#"
+        (module
+            (func (export "f") (param i32 i32) (result i32)
+                (i32.add
+                    (block (result i32)
+                        (br_if 0 (i32.add (local.get 0) (local.get 0)) (local.get 1))
+                        (drop)
+                        (i32.const 7)
+                    )
+                    (local.get 0)
+                )
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(3), Val::I32(0)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(10));
+    let steps = tracer
+        .etable
+        .entries()
+        .iter()
+        .map(|entry| &entry.step_info)
+        .collect::<Vec<_>>();
+    // The fallthrough result `i32.const 7` is written into the result register of the block.
+    let position = steps
+        .iter()
+        .position(|step| matches!(step, StepInfo::Const { .. }))
+        .expect("missing const step");
+    let StepInfo::Const { value } = steps[position] else {
+        unreachable!()
+    };
+    assert_eq!(value.val.i32(), Some(7));
+    assert!(value.addr.is_some());
+    let StepInfo::I32BinOp { left, .. } = steps[position + 1] else {
+        panic!("expected the enclosing i32.add after the block");
+    };
+    assert_eq!(left.addr, value.addr);
+    tracer.get_mtable().validate().unwrap();
+}
+
+#[test]
+fn i64_add_is_traced() {
+    let wat = r#"
+        (module
+            (func (export "add") (param i64 i64) (result i64)
+                (i64.add (local.get 0) (local.get 1))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "add", &[Val::I64(1 << 40), Val::I64(-3)]);
+    assert_eq!(result.unwrap()[0].i64(), Some((1 << 40) - 3));
+    let binops = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::I64BinOp {
+                class,
+                left,
+                right,
+                result,
+            } => Some((*class, left, right, result)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let [(class, left, right, result)] = binops[..] else {
+        panic!("expected exactly one i64 binary step: {binops:?}");
+    };
+    assert!(matches!(class, BinOp::Add));
+    // The frame of the traced function starts after the register receiving its result.
+    assert_eq!((left.addr, right.addr), (Some(1), Some(2)));
+    assert!(matches!(left.val, Val::I64(value) if value == 1 << 40));
+    assert!(matches!(right.val, Val::I64(-3)));
+    assert!(matches!(result.val, Val::I64(value) if value == (1 << 40) - 3));
+}
+
+#[test]
+fn i64_arithmetic_is_traced() {
+    let wat = r#"
+        (module
+            (func (export "f") (param $x i64) (param $y i64) (result i64)
+                (i64.sub (local.get $x) (local.get $y))
+                (i64.mul (local.get $y))
+                (i64.div_s (local.get $y))
+                (i64.div_u (i64.const 2))
+                (i64.rem_s (local.get $x))
+                (i64.rem_u (i64.const 7) (local.get $y))
+                (i64.and)
+                (i64.or (local.get $x))
+                (i64.xor (i64.const -1))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I64(20), Val::I64(3)]);
+    let expected = !((((20_i64 - 3) * 3 / 3 / 2) % 20 & (7 % 3)) | 20);
+    assert_eq!(result.unwrap()[0].i64(), Some(expected));
+    let opcodes = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter(|entry| matches!(entry.step_info, StepInfo::I64BinOp { .. }))
+        .map(|entry| entry.step_info.opcode())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        opcodes,
+        [
+            "i64.sub", "i64.mul", "i64.sdiv", "i64.udiv", "i64.srem", "i64.urem", "i64.and",
+            "i64.or", "i64.xor",
+        ]
+    );
+    for entry in tracer.etable.entries() {
+        if let StepInfo::I64BinOp {
+            left,
+            right,
+            result,
+            ..
+        } = &entry.step_info
+        {
+            for ival in [left, right, result] {
+                assert!(matches!(ival.val, Val::I64(_)), "{}", entry.step_info);
+            }
+        }
+    }
+    tracer.get_mtable().validate().unwrap();
+}
+
+#[test]
+fn output_snapshot_captures_final_state() {
+    let wat = r#"
+        (module
+            (memory 1)
+            (global $counter (mut i64) (i64.const 1))
+            (func (export "f") (param i64)
+                (i64.store offset=16 (i32.const 0) (local.get 0))
+                (global.set $counter (i64.add (global.get $counter) (local.get 0)))
+            )
+        )"#;
+    let tracer = Tracer::new().output_snapshot_mode();
+    let (tracer, result) = trace_wat_with(tracer, wat, "f", &[Val::I64(42)]);
+    result.unwrap();
+    assert!(tracer.etable.entries().is_empty());
+    let final_state = tracer.final_state().expect("missing final state");
+    let heap = final_state
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Heap)
+        .collect::<Vec<_>>();
+    // One page of linear memory has 8192 words.
+    assert_eq!(heap.len(), 8192);
+    assert_eq!(heap[2].start_offset, 2);
+    assert_eq!(heap[2].value, 42);
+    assert_eq!(heap.iter().filter(|entry| entry.value != 0).count(), 1);
+    let globals = final_state
+        .entries()
+        .iter()
+        .filter(|entry| entry.ltype == LocationType::Global)
+        .collect::<Vec<_>>();
+    assert_eq!(globals.len(), 1);
+    assert!(globals[0].is_mutable);
+    assert_eq!(globals[0].value, 43);
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I64(42)]);
+    result.unwrap();
+    assert!(tracer.final_state().is_none());
+}
+
+#[test]
+fn i32_popcnt_is_traced() {
+    let wat = r#"
+        (module
+            (func (export "popcnt") (param i32) (result i32)
+                (i32.popcnt (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "popcnt", &[Val::I32(0x00F0_F00F)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(12));
+    let entry = tracer
+        .etable
+        .entries()
+        .iter()
+        .find(|entry| matches!(entry.step_info, StepInfo::I32UnOp { .. }))
+        .expect("missing i32 unary step");
+    let StepInfo::I32UnOp {
+        class,
+        operand,
+        result,
+    } = &entry.step_info
+    else {
+        unreachable!()
+    };
+    assert!(matches!(class, UnOp::Popcnt));
+    assert_eq!(entry.step_info.opcode(), "i32.popcnt");
+    assert_eq!(operand.val.i32(), Some(0x00F0_F00F));
+    assert_eq!(result.val.i32(), Some(12));
+    let mtable = tracer.get_mtable();
+    let mentries = mtable
+        .entries()
+        .iter()
+        .filter(|mentry| mentry.eid == entry.eid)
+        .collect::<Vec<_>>();
+    assert_eq!(mentries.len(), 2);
+    assert_eq!(mentries[0].atype, AccessType::Read);
+    assert_eq!(mentries[0].ltype, LocationType::Stack);
+    assert_eq!(Some(mentries[0].addr), operand.addr);
+    assert_eq!(mentries[0].value.i32(), Some(0x00F0_F00F));
+    assert_eq!(mentries[1].atype, AccessType::Write);
+    assert_eq!(mentries[1].ltype, LocationType::Stack);
+    assert_eq!(Some(mentries[1].addr), result.addr);
+    assert_eq!(mentries[1].value.i32(), Some(12));
+}
+
+#[test]
+fn float_unops_record_one_read_and_one_write() {
+    let wat = r#"
+        (module
+            (func (export "sqrt") (param f64) (result f64)
+                (f64.sqrt (local.get 0))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "sqrt", &[Val::F64(4.0.into())]);
+    assert_eq!(f64::from(result.unwrap()[0].f64().unwrap()), 2.0);
+    let entry = tracer
+        .etable
+        .entries()
+        .iter()
+        .find(|entry| matches!(entry.step_info, StepInfo::FloatUnOp { .. }))
+        .expect("missing float unary step");
+    let StepInfo::FloatUnOp {
+        class,
+        operand,
+        result,
+    } = &entry.step_info
+    else {
+        unreachable!()
+    };
+    assert_eq!(*class, FloatUnOp::Sqrt);
+    assert_eq!(entry.step_info.opcode(), "f64.sqrt");
+    assert_eq!(operand.val.f64().map(f64::from), Some(4.0));
+    assert_eq!(result.val.f64().map(f64::from), Some(2.0));
+    let mtable = tracer.get_mtable();
+    let mentries = mtable
+        .entries()
+        .iter()
+        .filter(|mentry| mentry.eid == entry.eid)
+        .collect::<Vec<_>>();
+    assert_eq!(mentries.len(), 2);
+    assert_eq!(mentries[0].atype, AccessType::Read);
+    assert_eq!(mentries[0].ltype, LocationType::Stack);
+    assert_eq!(Some(mentries[0].addr), operand.addr);
+    assert_eq!(mentries[1].atype, AccessType::Write);
+    assert_eq!(mentries[1].ltype, LocationType::Stack);
+    assert_eq!(Some(mentries[1].addr), result.addr);
+}
+
+#[test]
+fn float_neg_and_abs_preserve_sign_and_nan_bits() {
+    let wat = r#"
+        (module
+            (func (export "neg") (param f32) (result f32)
+                (f32.neg (local.get 0))
+            )
+            (func (export "abs") (param f32) (result f32)
+                (f32.abs (local.get 0))
+            )
+        )"#;
+    let nan = f32::from_bits(0xFFC0_0001);
+    for (func, input, expected) in [
+        ("neg", 0.0_f32, (-0.0_f32).to_bits()),
+        ("neg", -0.0, 0.0_f32.to_bits()),
+        ("neg", nan, 0x7FC0_0001),
+        ("abs", -0.0, 0.0_f32.to_bits()),
+        ("abs", nan, 0x7FC0_0001),
+    ] {
+        let (tracer, result) = trace_wat(wat, func, &[Val::F32(input.into())]);
+        assert_eq!(result.unwrap()[0].f32().unwrap().to_bits(), expected);
+        let entry = tracer
+            .etable
+            .entries()
+            .iter()
+            .find(|entry| matches!(entry.step_info, StepInfo::FloatUnOp { .. }))
+            .expect("missing float unary step");
+        let StepInfo::FloatUnOp {
+            operand, result, ..
+        } = &entry.step_info
+        else {
+            unreachable!()
+        };
+        assert_eq!(entry.step_info.opcode(), format!("f32.{func}"));
+        assert_eq!(operand.val.f32().unwrap().to_bits(), input.to_bits());
+        assert_eq!(
+            result.val.f32().unwrap().to_bits(),
+            expected,
+            "{func}({input})"
+        );
+    }
+}
+
+#[test]
+fn int_to_float_conversion_records_rounded_result() {
+    let wat = r#"
+        (module
+            (func (export "convert") (param i64) (result f64)
+                (f64.convert_i64_u (local.get 0))
+            )
+        )"#;
+    let input = u64::MAX as i64;
+    let (tracer, result) = trace_wat(wat, "convert", &[Val::I64(input)]);
+    let executed = result.unwrap()[0].f64().unwrap().to_float();
+    assert_eq!(executed, u64::MAX as f64);
+    let step = tracer
+        .etable
+        .entries()
+        .iter()
+        .map(|entry| &entry.step_info)
+        .find(|step| matches!(step, StepInfo::ConvertInt { .. }))
+        .expect("missing conversion step");
+    assert_eq!(step.opcode(), "f64.convert_i64_u");
+    let StepInfo::ConvertInt {
+        signed,
+        input: traced_input,
+        result,
+    } = step
+    else {
+        unreachable!()
+    };
+    assert!(!signed);
+    assert_eq!(traced_input.val.i64(), Some(input));
+    let traced = result.val.f64().expect("result must be an f64").to_float();
+    assert_eq!(traced.to_bits(), executed.to_bits());
+}
+
+#[test]
+fn wrap_records_the_discarded_high_bits() {
+    let wat = r#"
+        (module
+            (func (export "wrap") (param i64) (result i32)
+                (i32.wrap_i64 (local.get 0))
+            )
+        )"#;
+    let low = 0x9abc_def0_u32 as i32;
+    for (input, high) in [
+        (0x1234_5678_9abc_def0, Some(0x1234_5678)),
+        (0x9abc_def0, None),
+    ] {
+        let (tracer, result) = trace_wat(wat, "wrap", &[Val::I64(input)]);
+        assert_eq!(result.unwrap()[0].i32(), Some(low));
+        let step = tracer
+            .etable
+            .entries()
+            .iter()
+            .map(|entry| &entry.step_info)
+            .find(|step| matches!(step, StepInfo::I32WrapI64 { .. }))
+            .expect("missing wrap step");
+        assert_eq!(step.opcode(), "i32.wrap_i64");
+        let StepInfo::I32WrapI64 {
+            input: traced_input,
+            result,
+            discarded_high,
+        } = step
+        else {
+            unreachable!()
+        };
+        assert_eq!(traced_input.val.i64(), Some(input));
+        assert_eq!(result.val.i32(), Some(low));
+        assert_eq!(*discarded_high, high);
+    }
+}
+
+#[test]
+fn chained_comparisons_read_operands_in_evaluation_order() {
+    // Computes `(a < b) == c`.
+    let wat = r#"
+        (module
+            (func (export "chain") (param $a i32) (param $b i32) (param $c i32) (result i32)
+                (i32.eq
+                    (i32.lt_s (local.get $a) (local.get $b))
+                    (local.get $c)
+                )
+            )
+        )"#;
+    let params = [Val::I32(1), Val::I32(2), Val::I32(1)];
+    let (tracer, result) = trace_wat(wat, "chain", &params);
+    assert_eq!(result.unwrap()[0].i32(), Some(1));
+    let relops = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::RelOp {
+                left,
+                right,
+                result,
+                ..
+            } => Some((entry.eid, left.addr, right.addr, result.addr)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let [(lt_eid, a, b, lt_result), (eq_eid, lhs, c, _)] = relops[..] else {
+        panic!("expected two comparisons but found {relops:?}")
+    };
+    // The outer comparison reads the result of the inner one as its left operand.
+    assert!(lt_eid < eq_eid);
+    assert_eq!(lhs, lt_result);
+    // The parameters occupy registers in declaration order.
+    assert!(a < b && b < c);
+    let mtable = tracer.get_mtable();
+    let reads = |eid: u64| {
+        let mut reads = mtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.eid == eid && entry.atype == AccessType::Read)
+            .map(|entry| (entry.emid, Some(entry.addr)))
+            .collect::<Vec<_>>();
+        reads.sort();
+        reads.into_iter().map(|(_, addr)| addr).collect::<Vec<_>>()
+    };
+    assert_eq!(reads(lt_eid), [a, b]);
+    assert_eq!(reads(eq_eid), [lhs, c]);
+}
+
+#[test]
+fn signed_and_unsigned_comparisons_of_the_same_bits_record_opposite_results() {
+    let wat = r#"
+        (module
+            (func (export "lt") (param i32 i32) (result i32 i32)
+                (i32.lt_s (local.get 0) (local.get 1))
+                (i32.lt_u (local.get 0) (local.get 1))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "lt", &[Val::I32(-1), Val::I32(1)]);
+    let result = result.unwrap();
+    assert_eq!((result[0].i32(), result[1].i32()), (Some(1), Some(0)));
+    let relops = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::RelOp {
+                class,
+                left,
+                right,
+                result,
+            } => Some((
+                *class,
+                entry.step_info.opcode(),
+                left.val.i32(),
+                right.val.i32(),
+                result.val.i32(),
+            )),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        relops,
+        [
+            (
+                RelOp::SignedLt,
+                String::from("i32.lt_s"),
+                Some(-1),
+                Some(1),
+                Some(1)
+            ),
+            (
+                RelOp::UnsignedLt,
+                String::from("i32.lt_u"),
+                Some(-1),
+                Some(1),
+                Some(0)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn extension_of_a_reinterpreted_float_keeps_its_raw_bits() {
+    let wat = r#"
+        (module
+            (func (export "extend_s") (param f32) (result i64)
+                (i64.extend_i32_s (i32.reinterpret_f32 (local.get 0)))
+            )
+            (func (export "extend_u") (param f32) (result i64)
+                (i64.extend_i32_u (i32.reinterpret_f32 (local.get 0)))
+            )
+        )"#;
+    let float = -1.5_f32;
+    let bits = float.to_bits();
+    assert_eq!(bits, 0xBFC0_0000);
+    for (func, expected) in [
+        ("extend_s", 0xFFFF_FFFF_BFC0_0000_u64),
+        ("extend_u", 0x0000_0000_BFC0_0000_u64),
+    ] {
+        let (tracer, result) = trace_wat(wat, func, &[Val::F32(float.into())]);
+        assert_eq!(result.unwrap()[0].i64(), Some(expected as i64));
+        let entries = tracer.etable.entries();
+        // A reinterpretation only changes the type of the register and is not a step.
+        let [extend, ret] = &entries[..] else {
+            panic!("expected an extension and a return but found {entries:?}")
+        };
+        let StepInfo::I64ExtendI32 { input, result, .. } = &extend.step_info else {
+            panic!("expected an extension but found {}", extend.step_info)
+        };
+        assert_eq!(input.val.i32(), Some(bits as i32));
+        assert_eq!(result.val.i64(), Some(expected as i64));
+        let StepInfo::Return { values, .. } = &ret.step_info else {
+            panic!("expected a return but found {}", ret.step_info)
+        };
+        assert_eq!(values[0].addr, result.addr);
+        // The extension reads the raw bits of the float parameter.
+        let param = tracer
+            .get_mtable()
+            .entries()
+            .iter()
+            .find(|entry| entry.eid == extend.eid && entry.atype == AccessType::Read)
+            .map(|entry| (entry.addr, UntypedVal::from(entry.value.clone()).to_bits()))
+            .unwrap();
+        assert_eq!(param, (input.addr.unwrap(), u64::from(bits)));
+    }
+}
+
+#[test]
+fn extended_comparison_feeds_an_i64_shift_amount() {
+    let wat = r#"
+        (module
+            (func (export "shift") (param i64 i32 i32) (result i64)
+                (i64.shl
+                    (local.get 0)
+                    (i64.extend_i32_u (i32.lt_s (local.get 1) (local.get 2)))
+                )
+            )
+        )"#;
+    let params = [Val::I64(5), Val::I32(-1), Val::I32(2)];
+    let (tracer, result) = trace_wat(wat, "shift", &params);
+    assert_eq!(result.unwrap()[0].i64(), Some(10));
+    let step = |opcode: &str| {
+        tracer
+            .etable
+            .entries()
+            .iter()
+            .find(|entry| entry.step_info.opcode() == opcode)
+            .unwrap_or_else(|| panic!("missing {} step", opcode))
+    };
+    let StepInfo::RelOp { result: cmp, .. } = &step("i32.lt_s").step_info else {
+        unreachable!()
+    };
+    let extend = step("i64.extend_i32_u");
+    let StepInfo::I64ExtendI32 {
+        signed: false,
+        input,
+        result: extended,
+    } = &extend.step_info
+    else {
+        unreachable!()
+    };
+    let shift = step("i64.shl");
+    let StepInfo::I64BinOp {
+        left,
+        right: amount,
+        result,
+        ..
+    } = &shift.step_info
+    else {
+        unreachable!()
+    };
+    assert_eq!(cmp.val.ty(), ValType::I32);
+    assert_eq!((input.addr, input.val.i32()), (cmp.addr, Some(1)));
+    assert_eq!(extended.val.i64(), Some(1));
+    assert_eq!(left.val.ty(), ValType::I64);
+    assert_eq!((amount.addr, amount.val.i64()), (extended.addr, Some(1)));
+    assert_eq!(result.val.i64(), Some(10));
+    // The amount register is read as an `i32` before and as an `i64` after the extension.
+    let mtable = tracer.get_mtable();
+    let types = |eid: u64| {
+        mtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.eid == eid && entry.ltype == LocationType::Stack)
+            .map(|entry| (entry.atype, entry.value.ty()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        types(extend.eid),
+        [
+            (AccessType::Read, ValType::I32),
+            (AccessType::Write, ValType::I64)
+        ]
+    );
+    assert_eq!(
+        types(shift.eid),
+        [
+            (AccessType::Read, ValType::I64),
+            (AccessType::Read, ValType::I64),
+            (AccessType::Write, ValType::I64)
+        ]
+    );
+}
+
+#[test]
+fn sign_extensions_record_the_extended_result() {
+    let wat = r#"
+        (module
+            (func (export "i32.extend8_s") (param i32) (result i32)
+                (i32.extend8_s (local.get 0))
+            )
+            (func (export "i32.extend16_s") (param i32) (result i32)
+                (i32.extend16_s (local.get 0))
+            )
+            (func (export "i64.extend8_s") (param i64) (result i64)
+                (i64.extend8_s (local.get 0))
+            )
+            (func (export "i64.extend16_s") (param i64) (result i64)
+                (i64.extend16_s (local.get 0))
+            )
+            (func (export "i64.extend32_s") (param i64) (result i64)
+                (i64.extend32_s (local.get 0))
+            )
+        )"#;
+    for (func, bits, input, expected) in [
+        ("i32.extend8_s", (8, 32), Val::I32(0xFF), Val::I32(-1)),
+        (
+            "i32.extend16_s",
+            (16, 32),
+            Val::I32(0x1_7FFF),
+            Val::I32(0x7FFF),
+        ),
+        ("i64.extend8_s", (8, 64), Val::I64(0x80), Val::I64(-0x80)),
+        ("i64.extend16_s", (16, 64), Val::I64(0xFFFF), Val::I64(-1)),
+        (
+            "i64.extend32_s",
+            (32, 64),
+            Val::I64(0x8000_0000),
+            Val::I64(-0x8000_0000),
+        ),
+    ] {
+        let (tracer, result) = trace_wat(wat, func, &[input.clone()]);
+        let bits_of = |val: &Val| UntypedVal::from(val.clone()).to_bits();
+        assert_eq!(bits_of(&result.unwrap()[0]), bits_of(&expected));
+        let entry = tracer
+            .etable
+            .entries()
+            .iter()
+            .find(|entry| matches!(entry.step_info, StepInfo::SignExtend { .. }))
+            .expect("missing sign extension step");
+        let StepInfo::SignExtend {
+            from_bits,
+            to_bits,
+            operand,
+            result,
+        } = &entry.step_info
+        else {
+            unreachable!()
+        };
+        assert_eq!((*from_bits, *to_bits), bits);
+        assert_eq!(entry.step_info.opcode(), func);
+        assert_eq!(bits_of(&operand.val), bits_of(&input));
+        assert_eq!(bits_of(&result.val), bits_of(&expected));
+        let mtable = tracer.get_mtable();
+        let accesses = mtable
+            .entries()
+            .iter()
+            .filter(|mentry| mentry.eid == entry.eid)
+            .map(|mentry| (mentry.ltype, mentry.atype, Some(mentry.addr)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            accesses,
+            [
+                (LocationType::Stack, AccessType::Read, operand.addr),
+                (LocationType::Stack, AccessType::Write, result.addr),
+            ]
+        );
+    }
+}
+
+#[test]
+fn i64_comparisons_write_i32_booleans() {
+    let wat = r#"
+        (module
+            (func (export "lt") (param i64 i64) (result i32)
+                (i64.lt_u (local.get 0) (local.get 1))
+            )
+            (func (export "eqz") (param i64) (result i32)
+                (i64.eqz (local.get 0))
+            )
+        )"#;
+    for (left, right, expected) in [(1, -1, 1), (-1, 1, 0)] {
+        let (tracer, result) = trace_wat(wat, "lt", &[Val::I64(left), Val::I64(right)]);
+        assert_eq!(result.unwrap()[0].i32(), Some(expected));
+        let (eid, step) = tracer
+            .etable
+            .entries()
+            .iter()
+            .find_map(|entry| match &entry.step_info {
+                step @ StepInfo::RelOp { .. } => Some((entry.eid, step)),
+                _ => None,
+            })
+            .expect("missing comparison step");
+        assert_eq!(step.opcode(), "i64.lt_u");
+        let StepInfo::RelOp { class, result, .. } = step else {
+            unreachable!()
+        };
+        assert_eq!(*class, RelOp::UnsignedLt);
+        assert_eq!(result.val.i32(), Some(expected));
+        let mtable = tracer.get_mtable();
+        let accesses = mtable
+            .entries()
+            .iter()
+            .filter(|entry| entry.eid == eid)
+            .map(|entry| (entry.ltype, entry.atype, entry.value.ty()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            accesses,
+            [
+                (LocationType::Stack, AccessType::Read, ValType::I64),
+                (LocationType::Stack, AccessType::Read, ValType::I64),
+                (LocationType::Stack, AccessType::Write, ValType::I32),
+            ]
+        );
+    }
+    let (tracer, result) = trace_wat(wat, "eqz", &[Val::I64(0)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(1));
+    let step = tracer
+        .etable
+        .entries()
+        .iter()
+        .map(|entry| &entry.step_info)
+        .find(|step| matches!(step, StepInfo::TestOp { .. }))
+        .expect("missing test step");
+    assert_eq!(step.opcode(), "i64.eqz");
+    let StepInfo::TestOp { class, result, .. } = step else {
+        unreachable!()
+    };
+    assert_eq!(*class, TestOp::Eqz);
+    assert_eq!(result.val.i32(), Some(1));
+}
+
+#[test]
+fn out_of_fuel_traps_at_the_exhausting_step() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func (export "spin") (param $n i32)
+                (loop $continue
+                    (br_if $continue
+                        (local.tee $n (i32.sub (local.get $n) (i32.const 1)))
+                    )
+                )
+            )
+        )"#,
+    )
+    .unwrap();
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let func = instance.get_func(&store, "spin").unwrap();
+    store.set_fuel(10).unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let error = func
+        .call_with_trace(&mut store, &[Val::I32(1000)], &mut [], tracer.clone())
+        .unwrap_err();
+    assert_eq!(error.as_trap_code(), Some(TrapCode::OutOfFuel));
+    let tracer = tracer.take();
+    let last = tracer.etable.entries().last().unwrap();
+    // The fuel suffices for the function entry and four loop iterations of three
+    // steps each, so the fuel consumption of the fifth iteration traps.
+    assert_eq!(last.eid, 1 + 4 * 3 + 1);
+    assert_eq!(last.eid as usize, tracer.etable.entries().len());
+    match &last.step_info {
+        StepInfo::Trap {
+            code, func_index, ..
+        } => {
+            assert_eq!(*code, TrapCode::OutOfFuel);
+            assert_eq!(*func_index, 0);
+        }
+        step => panic!("expected a trap step but found: {step:?}"),
+    }
+}
+
+#[test]
+fn tracing_does_not_compile_functions_that_are_not_executed() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func (export "unused") (param i32) (result i32)
+                (i32.mul (local.get 0) (local.get 0))
+            )
+            (func (export "run") (param i32) (result i32)
+                (call $callee (local.get 0))
+            )
+            (func $callee (param i32) (result i32)
+                (i32.add (local.get 0) (local.get 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    // Lazily compiled functions consume fuel when they are compiled on their first call,
+    // so `unused` consumes less fuel if it was compiled while tracing `run`.
+    let unused_fuel = |trace_run: bool| {
+        let mut config = Config::default();
+        config
+            .consume_fuel(true)
+            .compilation_mode(CompilationMode::Lazy);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+        let mut store = Store::new(&engine, ());
+        store.set_fuel(1_000_000).unwrap();
+        let instance = <Linker<()>>::new(&engine)
+            .instantiate(&mut store, &module)
+            .unwrap()
+            .start(&mut store)
+            .unwrap();
+        let mut results = [Val::I32(0)];
+        if trace_run {
+            let run = instance.get_func(&store, "run").unwrap();
+            let tracer = Rc::new(RefCell::new(Tracer::new()));
+            run.call_with_trace(&mut store, &[Val::I32(3)], &mut results, tracer.clone())
+                .unwrap();
+            assert_eq!(results[0].i32(), Some(6));
+            assert!(!tracer.borrow().etable.entries().is_empty());
+        }
+        let unused = instance.get_func(&store, "unused").unwrap();
+        let before = store.get_fuel().unwrap();
+        unused
+            .call(&mut store, &[Val::I32(3)], &mut results)
+            .unwrap();
+        assert_eq!(results[0].i32(), Some(9));
+        before - store.get_fuel().unwrap()
+    };
+    assert_eq!(unused_fuel(true), unused_fuel(false));
+}
+
+#[test]
+fn fuzzing_seeds_are_consistent() {
+    let memory = wat::parse_str(
+        r#"
+        (module
+            (memory 1)
+            (func (export "f") (param i32) (result i64)
+                (i64.store offset=3 (local.get 0) (i64.const -2))
+                (i64.load offset=3 (local.get 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    fuzzing::assert_trace_consistent(&memory, "f", &[Val::I32(5)]);
+    let globals = wat::parse_str(
+        r#"
+        (module
+            (global $g (mut i32) (i32.const 7))
+            (func (export "f") (param i32) (result i32)
+                (global.set $g (i32.add (global.get $g) (local.get 0)))
+                (global.get $g)
+            )
+        )"#,
+    )
+    .unwrap();
+    fuzzing::assert_trace_consistent(&globals, "f", &[Val::I32(3)]);
+    let calls = wat::parse_str(
+        r#"
+        (module
+            (func $fib (export "fib") (param $n i32) (result i32)
+                (if (result i32) (i32.lt_u (local.get $n) (i32.const 2))
+                    (then (local.get $n))
+                    (else
+                        (i32.add
+                            (call $fib (i32.sub (local.get $n) (i32.const 1)))
+                            (call $fib (i32.sub (local.get $n) (i32.const 2)))
+                        )
+                    )
+                )
+            )
+            (func (export "trap") (param i32) (result i32)
+                (i32.div_u (call $fib (local.get 0)) (i32.const 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    fuzzing::assert_trace_consistent(&calls, "fib", &[Val::I32(6)]);
+    fuzzing::assert_trace_consistent(&calls, "trap", &[Val::I32(3)]);
+}
+
+#[test]
+fn local_set_captures_the_overwritten_value() {
+    let wat = r#"
+        (module
+            (func (export "f") (param $a i32) (param $b i32) (result i32)
+                (local $x i32)
+                (local.set $x (local.get $a))
+                (local.set $x (local.get $b))
+                (local.get $x)
+            )
+        )"#;
+    let params = [Val::I32(10), Val::I32(20)];
+    let (tracer, result) = trace_wat_with(Tracer::new().prev_value_mode(), wat, "f", &params);
+    assert_eq!(result.unwrap()[0].i32(), Some(20));
+    let sets = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::LocalOp {
+                kind: LocalOpKind::Set,
+                dest,
+                prev_value,
+                ..
+            } => Some((entry.eid, dest, prev_value.as_ref())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let [(_, first, _), (eid, second, Some(prev))] = &sets[..] else {
+        panic!("expected two local sets with a captured value: {sets:?}");
+    };
+    assert_eq!(prev.addr, first.addr);
+    assert_eq!(prev.val.i64(), first.val.i64());
+    assert_eq!(prev.val.i64(), Some(10));
+    assert_eq!(second.val.i64(), Some(20));
+    // The overwritten value is read before the new value is written.
+    let mtable = tracer.get_mtable();
+    let accesses = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.eid == *eid && entry.addr == second.addr.unwrap())
+        .map(|entry| (entry.atype, entry.value.i64()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        [(AccessType::Read, Some(10)), (AccessType::Write, Some(20))]
+    );
+    mtable.validate().unwrap();
+    // Without the mode the overwritten value is not captured.
+    let (tracer, _) = trace_wat(wat, "f", &params);
+    assert!(tracer.etable.entries().iter().all(|entry| !matches!(
+        entry.step_info,
+        StepInfo::LocalOp {
+            prev_value: Some(_),
+            ..
+        }
+    )));
+}
+
+#[test]
+fn constants_are_written_into_their_registers() {
+    let wat = r#"
+        (module
+            (func (export "f") (param $c i32) (result i32)
+                (if (result i32) (local.get $c)
+                    (then (i32.const 42))
+                    (else (i32.const 7))
+                )
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(1)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(42));
+    let (eid, value) = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::Const { value } => Some((entry.eid, value)),
+            _ => None,
+        })
+        .expect("missing const step");
+    assert_eq!(value.val.i32(), Some(42));
+    let addr = value.addr.expect("constants are written into registers");
+    let mtable = tracer.get_mtable();
+    let accesses = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.eid == eid)
+        .map(|entry| (entry.ltype, entry.addr, entry.atype, entry.value.i32()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        [(LocationType::Stack, addr, AccessType::Write, Some(42))]
+    );
+    // The returned value is read from the register of the constant.
+    assert!(mtable
+        .entries()
+        .iter()
+        .any(|entry| entry.eid > eid && entry.addr == addr && entry.atype == AccessType::Read));
+    mtable.validate().unwrap();
+}
+
+#[test]
+fn calls_read_their_arguments_into_the_callee_frame() {
+    let wat = r#"
+        (module
+            (func $add (param $a i32) (param $b i32) (result i32)
+                (i32.add (local.get $a) (local.get $b))
+            )
+            (func $countdown (param $n i32) (result i32)
+                (if (result i32) (local.get $n)
+                    (then (call $countdown (call $add (local.get $n) (i32.const -1))))
+                    (else (i32.const 0))
+                )
+            )
+            (func (export "f") (param $x i32) (param $y i32) (result i32)
+                (call $countdown (call $add (local.get $x) (local.get $y)))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(2), Val::I32(0)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(0));
+    let calls = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::Call {
+                func_index,
+                args,
+                params,
+                ..
+            } => Some((entry.eid, *func_index, args, params)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // The exported function calls `$add` and then `$countdown` which recurses twice.
+    let func_indices = calls.iter().map(|call| call.1).collect::<Vec<_>>();
+    assert_eq!(func_indices, [0, 1, 0, 1, 0, 1]);
+    let (eid, _, args, params) = calls[0];
+    // The arguments are the parameter registers of the exported function.
+    let args = args
+        .iter()
+        .map(|arg| (arg.addr.unwrap(), arg.val.i64()))
+        .collect::<Vec<_>>();
+    assert_eq!(args.len(), 2);
+    assert_eq!((args[0].1, args[1].1), (Some(2), Some(0)));
+    assert_eq!(args[1].0, args[0].0 + 1);
+    let params = params
+        .iter()
+        .map(|param| (param.addr.unwrap(), param.val.i64()))
+        .collect::<Vec<_>>();
+    assert_eq!(params.len(), 2);
+    assert_eq!((params[0].1, params[1].1), (Some(2), Some(0)));
+    assert!(params[0].0 > args[1].0);
+    // The callee reads its parameters from the registers written by the call.
+    let mtable = tracer.get_mtable();
+    let written = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.eid == eid && entry.atype == AccessType::Write)
+        .map(|entry| entry.addr)
+        .collect::<Vec<_>>();
+    assert_eq!(written, [params[0].0, params[1].0]);
+    let sub = tracer
+        .etable
+        .entries()
+        .iter()
+        .find(|entry| entry.eid > eid && matches!(entry.step_info, StepInfo::I32BinOp { .. }))
+        .expect("missing i32.add of the callee");
+    let StepInfo::I32BinOp { left, right, .. } = &sub.step_info else {
+        unreachable!()
+    };
+    assert_eq!(
+        (left.addr, right.addr),
+        (Some(params[0].0), Some(params[1].0))
+    );
+    // Nested recursive calls pass their arguments at ever deeper stack addresses.
+    let countdowns = calls
+        .iter()
+        .filter(|call| call.1 == 1)
+        .map(|call| call.3[0].addr.unwrap())
+        .collect::<Vec<_>>();
+    assert!(countdowns.windows(2).all(|pair| pair[0] < pair[1]));
+    mtable.validate().unwrap();
+}
+
+#[test]
+fn tail_calls_are_traced_with_their_call_kind() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func $add (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1))
+            )
+            (func (export "nested") (param i32) (result i32)
+                (call $add (local.get 0) (local.get 0))
+            )
+            (func (export "tail") (param i32) (result i32)
+                (return_call $add (local.get 0) (local.get 0))
+            )
+        )"#,
+    )
+    .unwrap();
+    let mut config = Config::default();
+    config.wasm_tail_call(true);
+    let engine = Engine::new(&config);
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let instance = <Linker<()>>::new(&engine)
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    for (name, expected) in [("nested", CallKind::Nested), ("tail", CallKind::Tail)] {
+        let func = instance.get_func(&store, name).unwrap();
+        let tracer = Rc::new(RefCell::new(Tracer::new()));
+        let mut results = [Val::I32(0)];
+        func.call_with_trace(&mut store, &[Val::I32(21)], &mut results, tracer.clone())
+            .unwrap();
+        assert_eq!(results[0].i32(), Some(42));
+        let tracer = tracer.borrow();
+        let calls = tracer
+            .etable
+            .entries()
+            .iter()
+            .filter_map(|entry| match &entry.step_info {
+                StepInfo::Call { kind, params, .. } => Some((*kind, params.len())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(calls, [(expected, 2)]);
+        let returns = tracer
+            .etable
+            .entries()
+            .iter()
+            .filter(|entry| matches!(entry.step_info, StepInfo::Return { .. }))
+            .count();
+        // The callee of a tail call returns in place of its caller.
+        assert_eq!(returns, if expected == CallKind::Tail { 1 } else { 2 });
+        tracer.get_mtable().validate().unwrap();
+    }
+}
+
+#[test]
+fn call_indirect_reads_the_element_index_and_resolves_the_callee() {
+    let wat = r#"
+        (module
+            (type $binary (func (param i32 i32) (result i32)))
+            (table 3 funcref)
+            (elem (i32.const 0) $add $mul $neg)
+            (func $add (type $binary) (i32.add (local.get 0) (local.get 1)))
+            (func $mul (type $binary) (i32.mul (local.get 0) (local.get 1)))
+            (func $neg (param i32) (result i32) (i32.sub (i32.const 0) (local.get 0)))
+            (func (export "apply") (param $elem i32) (param $x i32) (result i32)
+                (call_indirect (type $binary) (local.get $x) (local.get $x) (local.get $elem))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "apply", &[Val::I32(1), Val::I32(6)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(36));
+    let (eid, elem_index, args, params) = tracer
+        .etable
+        .entries()
+        .iter()
+        .find_map(|entry| match &entry.step_info {
+            StepInfo::CallIndirect {
+                table_index: 0,
+                elem_index,
+                resolved_func_index: Some(1),
+                host: false,
+                args,
+                params,
+                kind: CallKind::Nested,
+                ..
+            } => Some((entry.eid, elem_index, args, params)),
+            _ => None,
+        })
+        .expect("missing call_indirect of $mul");
+    assert_eq!(elem_index.val.i32(), Some(1));
+    assert_eq!((args.len(), params.len()), (2, 2));
+    let mtable = tracer.get_mtable();
+    let reads = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.eid == eid && entry.atype == AccessType::Read)
+        .map(|entry| (entry.addr, entry.value.i32()))
+        .collect::<Vec<_>>();
+    assert_eq!(reads[0], (elem_index.addr.unwrap(), Some(1)));
+    assert_eq!(reads.len(), 3);
+    mtable.validate().unwrap();
+    // Calls failing the bounds or signature check trap after reading the element index.
+    for (elem, code) in [(3, TrapCode::TableOutOfBounds), (2, TrapCode::BadSignature)] {
+        let (tracer, result) = trace_wat(wat, "apply", &[Val::I32(elem), Val::I32(6)]);
+        assert_eq!(result.unwrap_err().as_trap_code(), Some(code));
+        let steps = tracer
+            .etable
+            .entries()
+            .iter()
+            .map(|entry| &entry.step_info)
+            .collect::<Vec<_>>();
+        assert!(!steps
+            .iter()
+            .any(|step| matches!(step, StepInfo::CallIndirect { .. })));
+        let Some(StepInfo::Trap {
+            code: traced,
+            reads,
+            ..
+        }) = steps.last()
+        else {
+            panic!("expected a trap step: {steps:?}");
+        };
+        assert_eq!(*traced, code);
+        assert_eq!(reads.len(), 1);
+        assert_eq!(reads[0].val.i32(), Some(elem));
+    }
+}
+
+#[test]
+fn call_indirect_resolves_callees_to_their_index_in_the_calling_instance() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (import "env" "double" (func $double (param i32) (result i32)))
+            (import "env" "double" (func $double_again (param i32) (result i32)))
+            (type $unary (func (param i32) (result i32)))
+            (table 3 funcref)
+            (elem (i32.const 0) $double_again $negate $double)
+            (func $negate (type $unary) (i32.sub (i32.const 0) (local.get 0)))
+            (func (export "apply") (param $elem i32) (param $x i32) (result i32)
+                (call_indirect (type $unary) (local.get $x) (local.get $elem))
+            )
+        )"#,
+    )
+    .unwrap();
+    let engine = Engine::default();
+    let module = Module::new(&engine, &mut &wasm[..]).unwrap();
+    let mut store = Store::new(&engine, ());
+    let mut linker = <Linker<()>>::new(&engine);
+    linker
+        .func_wrap("env", "double", |value: i32| value * 2)
+        .unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let apply = instance.get_func(&store, "apply").unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let mut results = [Val::I32(0)];
+    for elem in 0..3 {
+        apply
+            .call_with_trace(
+                &mut store,
+                &[Val::I32(elem), Val::I32(5)],
+                &mut results,
+                tracer.clone(),
+            )
+            .unwrap();
+    }
+    let callees = tracer
+        .borrow()
+        .etable
+        .entries()
+        .iter()
+        .filter_map(|entry| match &entry.step_info {
+            StepInfo::CallIndirect {
+                resolved_func_index,
+                host,
+                ..
+            } => Some((*resolved_func_index, *host)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // The linker instantiates a distinct host function for each import.
+    assert_eq!(
+        callees,
+        [(Some(1), true), (Some(2), false), (Some(0), true)]
+    );
+}
+
+#[test]
+fn local_tee_is_traced_as_local_ops() {
+    let wat = r#"
+        (module
+            (func (export "f") (param $x i32) (result i32)
+                (i32.add (local.get $x) (local.tee $x (i32.const 5)))
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(3)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(8));
+    let local_ops = tracer
+        .etable
+        .entries()
+        .iter()
+        .filter(|entry| matches!(entry.step_info, StepInfo::LocalOp { .. }))
+        .collect::<Vec<_>>();
+    let [get, tee] = &local_ops[..] else {
+        panic!("expected two local steps: {local_ops:?}");
+    };
+    let StepInfo::LocalOp {
+        kind: LocalOpKind::Get,
+        source: x,
+        dest: preserved,
+        ..
+    } = &get.step_info
+    else {
+        panic!("expected the preservation of $x: {}", get.step_info)
+    };
+    // The old value of `$x` is preserved before the `local.tee` overwrites it.
+    assert_eq!(x.val.i64(), Some(3));
+    assert_eq!(preserved.val.i64(), Some(3));
+    assert_ne!(preserved.addr, x.addr);
+    let StepInfo::LocalOp {
+        kind: LocalOpKind::Set,
+        source: value,
+        dest: teed,
+        ..
+    } = &tee.step_info
+    else {
+        panic!("expected the local.tee of $x: {}", tee.step_info)
+    };
+    assert_eq!(tee.step_info.opcode(), "local.set");
+    assert_eq!(value.addr, None);
+    assert_eq!(teed.addr, x.addr);
+    assert_eq!(teed.val.i64(), Some(5));
+    let mtable = tracer.get_mtable();
+    let writes = mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.atype == AccessType::Write && entry.eid <= tee.eid)
+        .map(|entry| (entry.addr, entry.value.i64()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        writes,
+        [
+            (preserved.addr.unwrap(), Some(3)),
+            (x.addr.unwrap(), Some(5)),
+        ]
+    );
+    mtable.validate().unwrap();
+}
+
+#[test]
+fn drop_steps_read_their_operand_and_translated_drops_are_elided() {
+    let wat = r#"
+        (module
+            (func (export "f") (param i32) (result i32)
+                (drop (i32.add (local.get 0) (local.get 0)))
+                (i32.const 0)
+            )
+        )"#;
+    let (tracer, result) = trace_wat(wat, "f", &[Val::I32(7)]);
+    assert_eq!(result.unwrap()[0].i32(), Some(0));
+    // The translator frees the register of the dropped sum instead of emitting a drop.
+    let entries = tracer.etable.entries();
+    assert!(entries
+        .iter()
+        .any(|entry| entry.step_info.opcode() == "i32.add"));
+    assert!(!entries
+        .iter()
+        .any(|entry| matches!(entry.step_info, StepInfo::Drop { .. })));
+
+    let mut etable = ETable::new(Vec::new());
+    etable.push(
+        0,
+        StepInfo::Drop {
+            value: IVal::stack(Val::I32(8), 3),
+        },
+    );
+    assert_eq!(etable.entries()[0].step_info.opcode(), "drop");
+    let mtable = MTable::new(memory_events_of_steps(etable.entries()));
+    let accesses = mtable
+        .entries()
+        .iter()
+        .map(|entry| (entry.addr, entry.ltype, entry.atype, entry.value.i32()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        accesses,
+        [(3, LocationType::Stack, AccessType::Read, Some(8))]
+    );
+}
@@ -4,3 +4,4 @@ mod func;
 mod host_calls_wasm;
 mod resource_limiter;
 mod resumable_call;
+mod trace;
@@ -0,0 +1,66 @@
+//! Tests to assert that traced calls of exported functions work as intended.
+
+use std::{cell::RefCell, rc::Rc};
+use wasmi::{etable::StepInfo, Engine, Linker, Module, Store, Tracer, Val};
+
+fn test_setup() -> (Store<()>, Linker<()>) {
+    let engine = Engine::default();
+    let store = Store::new(&engine, ());
+    let linker = <Linker<()>>::new(&engine);
+    (store, linker)
+}
+
+#[test]
+fn call_with_trace_records_steps() {
+    let (mut store, linker) = test_setup();
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func (export "double") (param i32) (result i32)
+                (i32.add
+                    (local.get 0)
+                    (local.get 0)
+                )
+            )
+        )
+        "#,
+    )
+    .unwrap();
+    let module = Module::new(store.engine(), &mut &wasm[..]).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let double = instance.get_func(&store, "double").unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let mut results = [Val::I32(0)];
+    double
+        .call_with_trace(&mut store, &[Val::I32(21)], &mut results, tracer.clone())
+        .unwrap();
+    assert_eq!(results[0].i32(), Some(42));
+    let tracer = tracer.borrow();
+    let entries = tracer.etable.entries();
+    assert_eq!(entries.first().map(|entry| entry.eid), Some(1));
+    assert!(entries.iter().any(|entry| matches!(
+        &entry.step_info,
+        StepInfo::I32BinOp { result, .. } if result.val.i32() == Some(42)
+    )));
+}
+
+#[test]
+fn call_with_trace_validates_inputs() {
+    let (mut store, linker) = test_setup();
+    let wasm = wat::parse_str(r#"(module (func (export "f") (param i32)))"#).unwrap();
+    let module = Module::new(store.engine(), &mut &wasm[..]).unwrap();
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .unwrap()
+        .start(&mut store)
+        .unwrap();
+    let f = instance.get_func(&store, "f").unwrap();
+    let tracer = Rc::new(RefCell::new(Tracer::new()));
+    let error = f.call_with_trace(&mut store, &[Val::I64(1)], &mut [], tracer.clone());
+    assert!(error.is_err());
+    assert!(tracer.borrow().etable.entries().is_empty());
+}
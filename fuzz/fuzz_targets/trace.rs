@@ -0,0 +1,26 @@
+#![no_main]
+
+mod utils;
+
+use libfuzzer_sys::fuzz_target;
+use utils::{ty_to_val, ExecConfig};
+use wasm_smith::ConfiguredModule;
+use wasmi::{fuzzing::assert_trace_consistent, Engine, ExternType, Module};
+
+fuzz_target!(|cfg_module: ConfiguredModule<ExecConfig>| {
+    let mut smith_module = cfg_module.module;
+    smith_module.ensure_termination(1000 /* fuel */);
+    let wasm = smith_module.to_bytes();
+    let engine = Engine::default();
+    let Ok(module) = Module::new(&engine, wasm.as_slice()) else {
+        return;
+    };
+    for export in module.exports() {
+        let ExternType::Func(ty) = export.ty() else {
+            // Export is no function which we cannot execute, therefore we ignore it.
+            continue;
+        };
+        let params = ty.params().iter().map(ty_to_val).collect::<Vec<_>>();
+        assert_trace_consistent(&wasm, export.name(), &params);
+    }
+});